@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX accession_numbers_accession_number_key;
@@ -0,0 +1,8 @@
+-- Collapse any duplicates written by concurrent scrapers before enforcing uniqueness
+DELETE FROM accession_numbers a
+       USING accession_numbers b
+       WHERE a.accession_number = b.accession_number
+       AND a.id > b.id;
+
+CREATE UNIQUE INDEX accession_numbers_accession_number_key
+       ON accession_numbers (accession_number);
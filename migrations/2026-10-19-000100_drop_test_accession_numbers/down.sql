@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+CREATE TABLE test_accession_numbers (
+       id SERIAL PRIMARY KEY,
+       accession_number NUMERIC(20) NOT NULL
+       );
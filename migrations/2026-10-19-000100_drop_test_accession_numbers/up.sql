@@ -0,0 +1,2 @@
+-- Tests now run against their own throwaway schema instead of a shadow table
+DROP TABLE test_accession_numbers;
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE filings_archive;
+DROP INDEX accession_numbers_seen_at_idx;
+ALTER TABLE accession_numbers DROP COLUMN seen_at;
@@ -0,0 +1,16 @@
+-- Remember when each accession number was first seen, so old ones can be pruned
+ALTER TABLE accession_numbers
+      ADD COLUMN seen_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc');
+
+CREATE INDEX accession_numbers_seen_at_idx ON accession_numbers (seen_at);
+
+-- Filings moved out of `filings` by `db prune`
+CREATE TABLE filings_archive (
+       accession_number BIGINT PRIMARY KEY,
+       form_type TEXT NOT NULL,
+       name TEXT NOT NULL,
+       cik BIGINT NOT NULL,
+       role TEXT,
+       filing_date INTEGER NOT NULL,
+       timestamp TEXT NOT NULL
+       );
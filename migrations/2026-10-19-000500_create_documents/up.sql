@@ -0,0 +1,13 @@
+-- Filing documents downloaded into a local archive, and where they were put
+CREATE TABLE documents (
+       accession_number BIGINT NOT NULL,
+       filename TEXT NOT NULL,
+       cik BIGINT NOT NULL,
+       path TEXT NOT NULL,
+       sha256 TEXT NOT NULL,
+       size BIGINT NOT NULL,
+       fetched_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+       PRIMARY KEY (accession_number, filename)
+       );
+
+CREATE INDEX documents_sha256_idx ON documents (sha256);
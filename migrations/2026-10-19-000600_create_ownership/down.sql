@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE ownership_footnotes;
+DROP TABLE ownership_transactions;
+DROP TABLE reporting_owners;
+DROP TABLE ownership_documents;
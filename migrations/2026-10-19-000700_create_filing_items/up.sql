@@ -0,0 +1,8 @@
+-- The numbered items each 8-K reports, e.g. 2.02 for results of operations
+CREATE TABLE filing_items (
+       accession_number BIGINT NOT NULL,
+       item TEXT NOT NULL,
+       PRIMARY KEY (accession_number, item)
+       );
+
+CREATE INDEX filing_items_item_idx ON filing_items (item);
@@ -0,0 +1,19 @@
+-- Facts from a filing's XBRL, in document order. Periods are yyyymmdd: an
+-- instant has only period_end, and a forever period neither. Dimensions are a
+-- JSON object of axis to member, {} when there are none.
+CREATE TABLE xbrl_facts (
+       accession_number BIGINT NOT NULL,
+       position INTEGER NOT NULL,
+       concept TEXT NOT NULL,
+       context TEXT NOT NULL,
+       unit TEXT,
+       period_start INTEGER,
+       period_end INTEGER,
+       dimensions TEXT NOT NULL,
+       value TEXT NOT NULL,
+       number DOUBLE PRECISION,
+       decimals TEXT,
+       PRIMARY KEY (accession_number, position)
+       );
+
+CREATE INDEX xbrl_facts_concept_idx ON xbrl_facts (concept);
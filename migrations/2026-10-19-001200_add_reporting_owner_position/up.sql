@@ -0,0 +1,3 @@
+-- Where each owner comes in the form, which lists the one filing it first. Owners
+-- stored before this all share 0, and fall back to being ordered by CIK.
+ALTER TABLE reporting_owners ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,5 @@
+-- Mirrors the Postgres seen-set; accession numbers fit comfortably in 64 bits
+CREATE TABLE accession_numbers (
+       id INTEGER PRIMARY KEY AUTOINCREMENT,
+       accession_number BIGINT NOT NULL UNIQUE
+       );
@@ -0,0 +1,14 @@
+-- Every new filing the scraper has emitted, so they can be queried later
+CREATE TABLE filings (
+       accession_number BIGINT PRIMARY KEY,
+       form_type TEXT NOT NULL,
+       name TEXT NOT NULL,
+       cik BIGINT NOT NULL,
+       role TEXT,
+       filing_date INTEGER NOT NULL,
+       timestamp TEXT NOT NULL
+       );
+
+CREATE INDEX filings_filing_date_idx ON filings (filing_date, accession_number);
+CREATE INDEX filings_cik_idx ON filings (cik);
+CREATE INDEX filings_form_type_idx ON filings (form_type);
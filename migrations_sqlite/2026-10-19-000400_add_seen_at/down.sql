@@ -0,0 +1,13 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE filings_archive;
+
+CREATE TABLE accession_numbers_old (
+       id INTEGER PRIMARY KEY AUTOINCREMENT,
+       accession_number BIGINT NOT NULL UNIQUE
+       );
+
+INSERT INTO accession_numbers_old (id, accession_number)
+       SELECT id, accession_number FROM accession_numbers;
+
+DROP TABLE accession_numbers;
+ALTER TABLE accession_numbers_old RENAME TO accession_numbers;
@@ -0,0 +1,26 @@
+-- Remember when each accession number was first seen, so old ones can be pruned.
+-- SQLite can't add a column defaulting to the current time, so rebuild the table.
+CREATE TABLE accession_numbers_new (
+       id INTEGER PRIMARY KEY AUTOINCREMENT,
+       accession_number BIGINT NOT NULL UNIQUE,
+       seen_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+       );
+
+INSERT INTO accession_numbers_new (id, accession_number)
+       SELECT id, accession_number FROM accession_numbers;
+
+DROP TABLE accession_numbers;
+ALTER TABLE accession_numbers_new RENAME TO accession_numbers;
+
+CREATE INDEX accession_numbers_seen_at_idx ON accession_numbers (seen_at);
+
+-- Filings moved out of `filings` by `db prune`
+CREATE TABLE filings_archive (
+       accession_number BIGINT PRIMARY KEY,
+       form_type TEXT NOT NULL,
+       name TEXT NOT NULL,
+       cik BIGINT NOT NULL,
+       role TEXT,
+       filing_date INTEGER NOT NULL,
+       timestamp TEXT NOT NULL
+       );
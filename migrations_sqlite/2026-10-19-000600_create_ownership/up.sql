@@ -0,0 +1,56 @@
+-- Forms 3, 4 and 5, read out of their ownership XML. Dates are yyyymmdd like
+-- the filings table's.
+CREATE TABLE ownership_documents (
+       accession_number BIGINT PRIMARY KEY,
+       document_type TEXT NOT NULL,
+       period_of_report INTEGER,
+       issuer_cik BIGINT NOT NULL,
+       issuer_name TEXT NOT NULL,
+       trading_symbol TEXT
+       );
+
+CREATE INDEX ownership_documents_issuer_cik_idx ON ownership_documents (issuer_cik);
+
+CREATE TABLE reporting_owners (
+       accession_number BIGINT NOT NULL,
+       cik BIGINT NOT NULL,
+       name TEXT NOT NULL,
+       is_director BOOLEAN NOT NULL,
+       is_officer BOOLEAN NOT NULL,
+       is_ten_percent_owner BOOLEAN NOT NULL,
+       is_other BOOLEAN NOT NULL,
+       officer_title TEXT,
+       PRIMARY KEY (accession_number, cik)
+       );
+
+CREATE INDEX reporting_owners_cik_idx ON reporting_owners (cik);
+
+-- Both tables of the form, in order. Holdings reported without a transaction
+-- have no date or code, and footnotes are a comma separated list of ids.
+CREATE TABLE ownership_transactions (
+       accession_number BIGINT NOT NULL,
+       position INTEGER NOT NULL,
+       derivative BOOLEAN NOT NULL,
+       security_title TEXT NOT NULL,
+       transaction_date INTEGER,
+       code TEXT,
+       shares DOUBLE PRECISION,
+       price DOUBLE PRECISION,
+       acquired_disposed TEXT,
+       shares_owned_after DOUBLE PRECISION,
+       direct BOOLEAN,
+       exercise_price DOUBLE PRECISION,
+       exercise_date INTEGER,
+       expiration_date INTEGER,
+       underlying_title TEXT,
+       underlying_shares DOUBLE PRECISION,
+       footnotes TEXT NOT NULL,
+       PRIMARY KEY (accession_number, position)
+       );
+
+CREATE TABLE ownership_footnotes (
+       accession_number BIGINT NOT NULL,
+       id TEXT NOT NULL,
+       text TEXT NOT NULL,
+       PRIMARY KEY (accession_number, id)
+       );
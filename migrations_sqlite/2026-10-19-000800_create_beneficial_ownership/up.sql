@@ -0,0 +1,29 @@
+-- Schedule 13D and 13G cover pages, one row per reporting person. The
+-- schedule's own fields are repeated on each row; event_date is yyyymmdd.
+CREATE TABLE beneficial_ownership (
+       accession_number BIGINT NOT NULL,
+       position INTEGER NOT NULL,
+       form_type TEXT,
+       structured BOOLEAN NOT NULL,
+       subject_cik BIGINT,
+       subject_name TEXT,
+       cusip TEXT,
+       class_title TEXT,
+       event_date INTEGER,
+       filer_cik BIGINT,
+       filer_name TEXT,
+       reporting_person TEXT NOT NULL,
+       reporting_person_cik BIGINT,
+       citizenship TEXT,
+       type_of_person TEXT,
+       sole_voting_power DOUBLE PRECISION,
+       shared_voting_power DOUBLE PRECISION,
+       sole_dispositive_power DOUBLE PRECISION,
+       shared_dispositive_power DOUBLE PRECISION,
+       aggregate_amount DOUBLE PRECISION,
+       percent_of_class DOUBLE PRECISION,
+       PRIMARY KEY (accession_number, position)
+       );
+
+CREATE INDEX beneficial_ownership_cusip_idx ON beneficial_ownership (cusip);
+CREATE INDEX beneficial_ownership_subject_cik_idx ON beneficial_ownership (subject_cik);
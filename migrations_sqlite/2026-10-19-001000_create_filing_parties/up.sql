@@ -0,0 +1,20 @@
+-- Every party the feed lists for a filing, with its role. `filings` keeps only
+-- the first, so this is where the others, like a Form 4's issuer, are found.
+CREATE TABLE filing_parties (
+       accession_number BIGINT NOT NULL,
+       cik BIGINT NOT NULL,
+       name TEXT NOT NULL,
+       role TEXT,
+       PRIMARY KEY (accession_number, cik)
+       );
+
+CREATE INDEX filing_parties_cik_idx ON filing_parties (cik);
+
+INSERT INTO filing_parties (accession_number, cik, name, role)
+       SELECT accession_number, cik, name, role FROM filings;
+
+-- Filings already archived get theirs too, so every stored filing has its parties
+-- until the next prune, which drops those of everything in the archive.
+INSERT INTO filing_parties (accession_number, cik, name, role)
+       SELECT accession_number, cik, name, role FROM filings_archive
+       WHERE accession_number NOT IN (SELECT accession_number FROM filings);
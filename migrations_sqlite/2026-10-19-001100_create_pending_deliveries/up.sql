@@ -0,0 +1,7 @@
+-- Filings a sink has still to take. They are queued in the transaction that
+-- marks them as seen, and each sink's rows go once it has them.
+CREATE TABLE pending_deliveries (
+       sink TEXT NOT NULL,
+       accession_number BIGINT NOT NULL,
+       PRIMARY KEY (sink, accession_number)
+       );
@@ -0,0 +1,454 @@
+//! Keeps copies of filing documents on disk
+//!
+//! -> `Layout::Filing` puts them at `<root>/<cik>/<accession>/<filename>`
+//! -> `Layout::ContentAddressed` puts them at `<root>/sha256/<ab>/<rest of the hash>`,
+//!    so a document filed more than once is only kept once
+//!
+//! Downloads go into a `.part` file first and are only moved into place once
+//! they are whole, so an interrupted one carries on where it stopped next time.
+//! Every document that makes it is recorded in the `documents` table along with
+//! its SHA-256 checksum and size, and isn't downloaded again while its file is
+//! still there.
+use crate::db::{self, DbConnection};
+use crate::edgar::{self, ARCHIVES_URL};
+use crate::errors::*;
+use crate::models::{ArchivedDocument, NewArchivedDocument};
+use crate::sec_entry::SECEntry;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Layout {
+    Filing,
+    ContentAddressed,
+}
+
+impl FromStr for Layout {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Layout> {
+        match s {
+            "filing" => Ok(Layout::Filing),
+            "content" => Ok(Layout::ContentAddressed),
+            _ => bail!("Unknown archive layout {}, expected filing or content", s),
+        }
+    }
+}
+
+/// A file in a filing's directory on EDGAR
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Document {
+    /// The complete submission text file, `<accession>.txt`, holding every document
+    Submission,
+    /// A single document, by the filename the filing index lists it under
+    Named(String),
+}
+
+impl Document {
+    pub fn filename(&self, acc: usize) -> String {
+        match self {
+            Document::Submission => format!("{}.txt", SECEntry::dashed_accession(acc)),
+            Document::Named(name) => name.clone(),
+        }
+    }
+}
+
+pub struct Archive {
+    root: PathBuf,
+    layout: Layout,
+    base_url: String,
+}
+
+impl Archive {
+    pub fn new<P: AsRef<Path>>(root: P) -> Archive {
+        Archive {
+            root: root.as_ref().to_path_buf(),
+            layout: Layout::Filing,
+            base_url: ARCHIVES_URL.to_owned(),
+        }
+    }
+
+    pub fn layout(mut self, layout: Layout) -> Archive {
+        self.layout = layout;
+        self
+    }
+
+    /// Downloads from somewhere other than EDGAR's archives, e.g. a mirror
+    pub fn base_url(mut self, url: &str) -> Archive {
+        self.base_url = url.trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Where a recorded document is on disk
+    pub fn path_of(&self, document: &ArchivedDocument) -> PathBuf {
+        self.root.join(&document.path)
+    }
+
+    /// Downloads `documents` of `entry`'s filing, see `fetch_filing`
+    pub fn fetch(
+        &self,
+        conn: &DbConnection,
+        entry: &SECEntry,
+        documents: &[Document],
+    ) -> Result<Vec<ArchivedDocument>> {
+        self.fetch_filing(conn, entry.cik(), entry.accession_number(), documents)
+    }
+
+    /// Downloads whichever of `documents` aren't in the archive yet, returning
+    /// the records of all of them. A filename that isn't a plain file name, e.g.
+    /// `../x` or `a/b`, is refused before anything is downloaded.
+    pub fn fetch_filing(
+        &self,
+        conn: &DbConnection,
+        cik: usize,
+        acc: usize,
+        documents: &[Document],
+    ) -> Result<Vec<ArchivedDocument>> {
+        let filenames = documents
+            .iter()
+            .map(|document| check_filename(document.filename(acc)))
+            .collect::<Result<Vec<String>>>()?;
+
+        let mut known = db::get_documents(conn, acc)?;
+        let mut downloaded = false;
+        for filename in &filenames {
+            match known.iter().find(|d| &d.filename == filename) {
+                Some(document) if self.is_present(document) => {}
+                _ => {
+                    self.download(conn, cik, acc, filename)?;
+                    downloaded = true;
+                }
+            }
+        }
+        if downloaded {
+            known = db::get_documents(conn, acc)?;
+        }
+
+        filenames
+            .iter()
+            .map(|filename| {
+                known
+                    .iter()
+                    .find(|d| &d.filename == filename)
+                    .cloned()
+                    .chain_err(|| format!("{} was not recorded", filename))
+            })
+            .collect()
+    }
+
+    /// Whether a recorded document's file is still there, and still has the
+    /// checksum it was downloaded with
+    pub fn verify(&self, document: &ArchivedDocument) -> Result<bool> {
+        let path = self.path_of(document);
+        if !path.exists() {
+            return Ok(false);
+        }
+        Ok(checksum(&path)? == document.sha256)
+    }
+
+    fn is_present(&self, document: &ArchivedDocument) -> bool {
+        fs::metadata(self.path_of(document)).is_ok_and(|m| m.len() == document.size as u64)
+    }
+
+    fn download(&self, conn: &DbConnection, cik: usize, acc: usize, filename: &str) -> Result<()> {
+        let url = edgar::document_url(&self.base_url, cik, acc, filename);
+        let part = self.part_path(cik, acc, filename);
+        create_parent(&part)?;
+        if !edgar::resume(&url, &part)? {
+            bail!("{} not found", url);
+        }
+
+        let sha256 = checksum(&part)?;
+        let size = fs::metadata(&part)
+            .chain_err(|| format!("Unable to read {}", part.display()))?
+            .len();
+        let relative = match self.layout {
+            Layout::Filing => Path::new(&cik.to_string())
+                .join(SECEntry::dashed_accession(acc))
+                .join(filename),
+            Layout::ContentAddressed => Path::new("sha256").join(&sha256[..2]).join(&sha256[2..]),
+        };
+        let path = self.root.join(&relative);
+        create_parent(&path)?;
+        fs::rename(&part, &path).chain_err(|| format!("Unable to move {} into place", filename))?;
+
+        db::write_document(
+            conn,
+            &NewArchivedDocument {
+                accession_number: acc as i64,
+                filename,
+                cik: cik as i64,
+                path: &relative.to_string_lossy(),
+                sha256: &sha256,
+                size: size as i64,
+            },
+        )
+        .map(|_| ())
+    }
+
+    /// Where a download in progress is kept
+    fn part_path(&self, cik: usize, acc: usize, filename: &str) -> PathBuf {
+        let name = format!("{}.part", filename);
+        match self.layout {
+            Layout::Filing => self
+                .root
+                .join(cik.to_string())
+                .join(SECEntry::dashed_accession(acc))
+                .join(name),
+            Layout::ContentAddressed => self
+                .root
+                .join("partial")
+                .join(format!("{:018}-{}", acc, name)),
+        }
+    }
+}
+
+/// `filename` if it names a file in the filing's directory and nothing else, as
+/// it goes into both the URL and the path on disk
+fn check_filename(filename: String) -> Result<String> {
+    let mut components = Path::new(&filename).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(name)), None) if name == filename.as_str() => Ok(filename),
+        _ => bail!("{:?} is not a document's file name", filename),
+    }
+}
+
+fn create_parent(path: &Path) -> Result<()> {
+    match path.parent() {
+        Some(dir) => {
+            fs::create_dir_all(dir).chain_err(|| format!("Unable to create {}", dir.display()))
+        }
+        None => Ok(()),
+    }
+}
+
+/// The SHA-256 of the file at `path`, in hex
+pub fn checksum(path: &Path) -> Result<String> {
+    let mut file = File::open(path).chain_err(|| format!("Unable to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher).chain_err(|| format!("Unable to read {}", path.display()))?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod archive_tests {
+    use super::*;
+    use crate::sec_entry::FilingType;
+    use crate::test_db::TestDb;
+    use std::env;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    const BODY: &[u8] = b"<SEC-DOCUMENT>0001193125-18-000001.txt\n</SEC-DOCUMENT>\n";
+
+    /// Serves `BODY` for `connections` requests, honouring `Range`, and passes on
+    /// the path and range each one asked for
+    fn stand_in(connections: usize) -> (String, mpsc::Receiver<(String, Option<String>)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/Archives/edgar", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            for _ in 0..connections {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                let path = request.split_whitespace().nth(1).unwrap().to_owned();
+                let mut range = None;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim_end().is_empty() {
+                        break;
+                    }
+                    if let Some(r) = line.to_lowercase().strip_prefix("range: bytes=") {
+                        range = Some(r.trim().trim_end_matches('-').to_owned());
+                    }
+                }
+
+                let start = range.as_ref().map_or(0, |r| r.parse::<usize>().unwrap());
+                let (status, body) = if path.ends_with("missing.htm") {
+                    ("404 Not Found", &b""[..])
+                } else if range.is_some() {
+                    ("206 Partial Content", &BODY[start..])
+                } else {
+                    ("200 OK", BODY)
+                };
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    body.len()
+                )
+                .unwrap();
+                reader.get_mut().write_all(body).unwrap();
+                sender.send((path, range)).unwrap();
+            }
+        });
+
+        (url, receiver)
+    }
+
+    fn scratch_dir(test: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!(
+            "sec_scraper_archive_{}_{}",
+            std::process::id(),
+            test
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn entry() -> SECEntry {
+        SECEntry::new(
+            FilingType::Sec8K,
+            String::from("Name"),
+            320193,
+            119312518000001,
+            20261019,
+            String::from("12:00:00"),
+        )
+    }
+
+    fn fetch_test(db: &DbConnection, test: &str) {
+        let (url, requests) = stand_in(1);
+        let dir = scratch_dir(test);
+        let archive = Archive::new(&dir).base_url(&url);
+
+        let fetched = archive
+            .fetch(db, &entry(), &[Document::Submission])
+            .unwrap();
+        let (path, range) = requests.recv().unwrap();
+        assert_eq!(
+            path,
+            "/Archives/edgar/data/320193/000119312518000001/0001193125-18-000001.txt"
+        );
+        assert_eq!(range, None);
+
+        assert_eq!(fetched.len(), 1);
+        let document = &fetched[0];
+        assert_eq!(
+            archive.path_of(document),
+            dir.join("320193/0001193125-18-000001/0001193125-18-000001.txt")
+        );
+        assert_eq!(fs::read(archive.path_of(document)).unwrap(), BODY);
+        assert_eq!(document.size, BODY.len() as i64);
+        assert_eq!(document.sha256, hex::encode(Sha256::digest(BODY)));
+        assert!(archive.verify(document).unwrap());
+
+        // Already there, so nothing is asked for
+        let again = archive
+            .fetch(db, &entry(), &[Document::Submission])
+            .unwrap();
+        assert_eq!(again, fetched);
+        assert_eq!(db::get_documents(db, 119312518000001).unwrap(), fetched);
+
+        fs::write(archive.path_of(document), b"changed").unwrap();
+        assert!(!archive.verify(document).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn postgres_fetch_test() {
+        fetch_test(&TestDb::new(), "postgres_fetch");
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_fetch_test() {
+        fetch_test(&TestDb::sqlite(), "sqlite_fetch");
+    }
+
+    #[test]
+    fn resume_test() {
+        let db = TestDb::new();
+        let (url, requests) = stand_in(1);
+        let dir = scratch_dir("resume");
+        let archive = Archive::new(&dir)
+            .base_url(&url)
+            .layout(Layout::ContentAddressed);
+
+        let part = dir.join("partial/000119312518000001-d1.htm.part");
+        fs::create_dir_all(part.parent().unwrap()).unwrap();
+        fs::write(&part, &BODY[..10]).unwrap();
+
+        let fetched = archive
+            .fetch(&db, &entry(), &[Document::Named(String::from("d1.htm"))])
+            .unwrap();
+        let (path, range) = requests.recv().unwrap();
+        assert_eq!(
+            path,
+            "/Archives/edgar/data/320193/000119312518000001/d1.htm"
+        );
+        assert_eq!(range, Some(String::from("10")));
+
+        let hash = hex::encode(Sha256::digest(BODY));
+        assert_eq!(
+            fetched[0].path,
+            format!("sha256/{}/{}", &hash[..2], &hash[2..])
+        );
+        assert_eq!(fs::read(archive.path_of(&fetched[0])).unwrap(), BODY);
+        assert!(!part.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_test() {
+        let db = TestDb::new();
+        let (url, _requests) = stand_in(1);
+        let dir = scratch_dir("missing");
+        let archive = Archive::new(&dir).base_url(&url);
+
+        let result = archive.fetch(
+            &db,
+            &entry(),
+            &[Document::Named(String::from("missing.htm"))],
+        );
+        assert!(result.is_err());
+        assert_eq!(db::get_documents(&db, 119312518000001).unwrap(), vec![]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn filename_test() {
+        let db = TestDb::new();
+        let dir = scratch_dir("filename");
+        let archive = Archive::new(&dir).base_url("http://127.0.0.1:1");
+
+        for name in &[
+            "../d1.htm",
+            "/etc/passwd",
+            "a/d1.htm",
+            "..",
+            ".",
+            "",
+            "d1.htm/",
+        ] {
+            let documents = [Document::Named(String::from(*name))];
+            assert!(
+                archive.fetch(&db, &entry(), &documents).is_err(),
+                "{:?}",
+                name
+            );
+        }
+        assert!(fs::read_dir(&dir).map_or(true, |mut d| d.next().is_none()));
+        assert_eq!(check_filename(String::from("d1.htm")).unwrap(), "d1.htm");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn layout_from_str_test() {
+        assert_eq!("filing".parse::<Layout>().unwrap(), Layout::Filing);
+        assert_eq!(
+            "content".parse::<Layout>().unwrap(),
+            Layout::ContentAddressed
+        );
+        assert!("flat".parse::<Layout>().is_err());
+    }
+}
@@ -0,0 +1,546 @@
+//! Recovers filings the feed no longer shows from EDGAR's index files
+//!
+//! -> `full-index/{year}/QTR{n}/master.idx` lists a whole quarter, and is used
+//!    for quarters that are over and fall entirely inside the range
+//! -> `daily-index/{year}/QTR{n}/master.{yyyymmdd}.idx` lists a single day, and
+//!    is used for the ragged ends of the range and the current quarter
+//!
+//! `form.idx` holds the same rows as `master.idx` in fixed-width columns, and
+//! either can be fetched gzipped. The entries go through `write_new_entries`, so
+//! filings already seen are skipped and new ones are queued for the sinks and
+//! delivered as usual, a chunk at a time. The seen-set is pruned, so filings
+//! already stored in `filings` or `filings_archive` are skipped as well.
+//!
+//! Index files have no filing time, so the timestamp is the filing date.
+use crate::db::DbConnection;
+use crate::edgar::ARCHIVES_URL;
+use crate::errors::*;
+use crate::filings;
+use crate::read_rss::write_new_entries;
+use crate::sec_entry::{FilingType, SECEntry};
+use crate::sink::FanOut;
+use chrono::{Datelike, Duration, NaiveDate, Utc, Weekday};
+use flate2::read::GzDecoder;
+use std::io::Read;
+
+/// Entries handed to `write_new_entries` at a time, keeping each transaction
+/// and insert a reasonable size for quarterly indexes of several hundred
+/// thousand rows
+const CHUNK_SIZE: usize = 1000;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum IndexKind {
+    /// Pipe separated, sorted by CIK
+    Master,
+    /// Fixed-width columns, sorted by form type
+    Form,
+}
+
+impl IndexKind {
+    fn name(self) -> &'static str {
+        match self {
+            IndexKind::Master => "master",
+            IndexKind::Form => "form",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct BackfillReport {
+    /// Index files read
+    pub files: usize,
+    /// Index files that don't exist, e.g. for holidays
+    pub missing: usize,
+    /// Filings listed in the range
+    pub listed: usize,
+    /// Filings not seen before, which went to the sink
+    pub new: usize,
+    /// Listed filings of forms the scraper doesn't handle
+    pub skipped: usize,
+}
+
+/// Backfills filings dated `from` to `to`, inclusive
+#[derive(Debug, Clone)]
+pub struct Backfill {
+    from: NaiveDate,
+    to: NaiveDate,
+    kind: IndexKind,
+    gzip: bool,
+}
+
+impl Backfill {
+    pub fn new(from: NaiveDate, to: NaiveDate) -> Backfill {
+        Backfill {
+            from,
+            to,
+            kind: IndexKind::Master,
+            gzip: false,
+        }
+    }
+
+    pub fn index(mut self, kind: IndexKind) -> Backfill {
+        self.kind = kind;
+        self
+    }
+
+    /// Fetches the `.gz` versions of the index files
+    pub fn gzip(mut self, gzip: bool) -> Backfill {
+        self.gzip = gzip;
+        self
+    }
+
+    /// The index files covering the range, as of `today`
+    pub fn urls(&self, today: NaiveDate) -> Vec<String> {
+        let mut urls = Vec::new();
+        let mut start = quarter_start(self.from);
+        while start <= self.to {
+            let next = quarter_start(start + Duration::days(93));
+            let end = next - Duration::days(1);
+            let (year, quarter) = (start.year(), quarter_of(start));
+
+            if self.from <= start && end <= self.to && end < today {
+                urls.push(format!(
+                    "{}/full-index/{}/QTR{}/{}.{}",
+                    ARCHIVES_URL,
+                    year,
+                    quarter,
+                    self.kind.name(),
+                    if self.gzip { "gz" } else { "idx" }
+                ));
+            } else {
+                let mut day = start.max(self.from);
+                while day <= end.min(self.to) {
+                    if day.weekday() != Weekday::Sat && day.weekday() != Weekday::Sun {
+                        urls.push(format!(
+                            "{}/daily-index/{}/QTR{}/{}.{}.idx{}",
+                            ARCHIVES_URL,
+                            year,
+                            quarter,
+                            self.kind.name(),
+                            day.format("%Y%m%d"),
+                            if self.gzip { ".gz" } else { "" }
+                        ));
+                    }
+                    day += Duration::days(1);
+                }
+            }
+            start = next;
+        }
+        urls
+    }
+
+    /// Reads every index file in the range with `fetch`, which returns `None` for
+    /// files that don't exist, and hands the new filings to `sink`. A range that
+    /// ends before it starts is an error rather than nothing to do.
+    pub fn run<F>(
+        &self,
+        conn: &DbConnection,
+        mut fetch: F,
+        sink: &mut FanOut,
+    ) -> Result<BackfillReport>
+    where
+        F: FnMut(&str) -> Result<Option<Vec<u8>>>,
+    {
+        if self.from > self.to {
+            bail!(
+                "Backfill range ends on {} before it starts on {}",
+                self.to.format("%Y%m%d"),
+                self.from.format("%Y%m%d")
+            );
+        }
+        let mut report = BackfillReport::default();
+        let today = Utc::now().naive_utc().date();
+
+        for url in self.urls(today) {
+            let bytes = match fetch(&url)? {
+                Some(bytes) => bytes,
+                None => {
+                    report.missing += 1;
+                    continue;
+                }
+            };
+            report.files += 1;
+
+            let text = decode(&bytes, self.gzip).chain_err(|| format!("Unable to read {}", url))?;
+            let rows = match self.kind {
+                IndexKind::Master => parse_master(&text),
+                IndexKind::Form => parse_form(&text),
+            }
+            .chain_err(|| format!("Unable to parse {}", url))?;
+
+            let mut entries = Vec::new();
+            for row in rows {
+                if row.date < self.from || row.date > self.to {
+                    continue;
+                }
+                report.listed += 1;
+                match row.into_entry() {
+                    Some(entry) => entries.push(entry),
+                    None => report.skipped += 1,
+                }
+            }
+
+            while !entries.is_empty() {
+                let chunk = entries
+                    .drain(..CHUNK_SIZE.min(entries.len()))
+                    .collect::<Vec<SECEntry>>();
+                let acc_numbers = chunk
+                    .iter()
+                    .map(SECEntry::accession_number)
+                    .collect::<Vec<usize>>();
+                let stored = filings::stored(conn, &acc_numbers)?;
+                let chunk = chunk
+                    .into_iter()
+                    .filter(|entry| !stored.contains(&entry.accession_number()))
+                    .collect();
+                report.new += write_new_entries(conn, chunk, |new| sink.queue(conn, &new))?;
+                sink.deliver(conn)?;
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// One line of an index file
+#[derive(Debug, PartialEq, Eq)]
+pub struct IndexRow {
+    pub cik: usize,
+    pub name: String,
+    pub form_type: String,
+    pub date: NaiveDate,
+    /// Path of the complete submission, e.g. `edgar/data/320193/0000320193-18-000145.txt`
+    pub filename: String,
+}
+
+impl IndexRow {
+    /// The accession number from the submission's file name
+    pub fn accession_number(&self) -> Option<usize> {
+        let stem = self.filename.rsplit('/').next()?.trim_end_matches(".txt");
+        stem.replace('-', "").parse::<usize>().ok()
+    }
+
+    /// The row as an entry, or `None` for forms the scraper doesn't handle
+    pub fn into_entry(self) -> Option<SECEntry> {
+        let filing_type = FilingType::which(&self.form_type).ok()?;
+        let acc = self.accession_number()?;
+        let date = self
+            .date
+            .format("%Y%m%d")
+            .to_string()
+            .parse::<usize>()
+            .ok()?;
+        let timestamp = self.date.format("%Y-%m-%d").to_string();
+        Some(SECEntry::new(
+            filing_type,
+            self.name,
+            self.cik,
+            acc,
+            date,
+            timestamp,
+        ))
+    }
+}
+
+fn decode(bytes: &[u8], gzip: bool) -> Result<String> {
+    let mut text = String::new();
+    if gzip {
+        GzDecoder::new(bytes)
+            .read_to_string(&mut text)
+            .chain_err(|| "Not gzipped text")?;
+    } else {
+        // Older indexes have the odd Latin-1 company name
+        text = String::from_utf8_lossy(bytes).into_owned();
+    }
+    Ok(text)
+}
+
+/// Parses a `master.idx`, whose rows look like
+///      320193|APPLE INC|10-K|2018-11-05|edgar/data/320193/0000320193-18-000145.txt
+pub fn parse_master(text: &str) -> Result<Vec<IndexRow>> {
+    let mut rows = Vec::new();
+    for line in body(text)? {
+        let fields = line.split('|').collect::<Vec<&str>>();
+        if fields.len() != 5 {
+            bail!("Malformed index line {:?}", line);
+        }
+        rows.push(IndexRow {
+            cik: fields[0]
+                .parse::<usize>()
+                .chain_err(|| format!("Bad CIK in {:?}", line))?,
+            name: fields[1].trim().to_owned(),
+            form_type: fields[2].trim().to_owned(),
+            date: parse_date(fields[3])?,
+            filename: fields[4].trim().to_owned(),
+        });
+    }
+    Ok(rows)
+}
+
+/// Parses a `form.idx`, whose columns line up under the header
+///      Form Type   Company Name      CIK         Date Filed  File Name
+pub fn parse_form(text: &str) -> Result<Vec<IndexRow>> {
+    let header = text
+        .lines()
+        .find(|line| line.starts_with("Form Type"))
+        .chain_err(|| "No header line in index")?;
+    let column = |name: &str| -> Result<usize> {
+        header
+            .find(name)
+            .chain_err(|| format!("No {} column in index", name))
+    };
+    let (name, cik, date, file) = (
+        column("Company Name")?,
+        column("CIK")?,
+        column("Date Filed")?,
+        column("File Name")?,
+    );
+
+    let mut rows = Vec::new();
+    for line in body(text)? {
+        let field = |start: usize, end: usize| line.get(start..end.min(line.len())).map(str::trim);
+        let (form_type, company, cik_field, date_field, file_field) = match (
+            field(0, name),
+            field(name, cik),
+            field(cik, date),
+            field(date, file),
+            field(file, line.len()),
+        ) {
+            (Some(a), Some(b), Some(c), Some(d), Some(e)) => (a, b, c, d, e),
+            _ => bail!("Malformed index line {:?}", line),
+        };
+        rows.push(IndexRow {
+            cik: cik_field
+                .parse::<usize>()
+                .chain_err(|| format!("Bad CIK in {:?}", line))?,
+            name: company.to_owned(),
+            form_type: form_type.to_owned(),
+            date: parse_date(date_field)?,
+            filename: file_field.to_owned(),
+        });
+    }
+    Ok(rows)
+}
+
+/// The lines after the row of dashes that ends an index's preamble
+fn body(text: &str) -> Result<impl Iterator<Item = &str>> {
+    let mut lines = text.lines();
+    lines
+        .by_ref()
+        .find(|line| line.starts_with("-----"))
+        .chain_err(|| "No header line in index")?;
+    Ok(lines.filter(|line| !line.trim().is_empty()))
+}
+
+/// Quarterly indexes write `2018-11-05`, daily ones `20181105`
+fn parse_date(date: &str) -> Result<NaiveDate> {
+    let date = date.trim();
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(date, "%Y%m%d"))
+        .chain_err(|| format!("Bad date {:?} in index", date))
+}
+
+fn quarter_of(date: NaiveDate) -> u32 {
+    (date.month() - 1) / 3 + 1
+}
+
+fn quarter_start(date: NaiveDate) -> NaiveDate {
+    NaiveDate::from_ymd_opt(date.year(), (quarter_of(date) - 1) * 3 + 1, 1).unwrap()
+}
+
+#[cfg(test)]
+mod backfill_tests {
+    use super::*;
+    use crate::db::archive_filings_before;
+    use crate::filings::FilingQuery;
+    use crate::test_db::TestDb;
+    use crate::write_entries::{Format, OutputFile};
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::cell::RefCell;
+    use std::io::Write;
+
+    const MASTER: &str =
+        "Description:           Daily Index of EDGAR Dissemination Feed by Company Name
+Last Data Received:    Oct 15, 2018
+Comments:              webmaster@sec.gov
+Anonymous FTP:         ftp://ftp.sec.gov/edgar/
+
+
+CIK|Company Name|Form Type|Date Filed|File Name
+--------------------------------------------------------------------------------
+1000015|META GROUP INC|SC 13G/A|20181015|edgar/data/1000015/0000950123-18-011226.txt
+320193|APPLE INC|4|20181015|edgar/data/320193/0000320193-18-000120.txt
+1090872|AGILENT TECHNOLOGIES, INC.|S-8|20181015|edgar/data/1090872/0001090872-18-000020.txt
+1214128|Wang Janet|4|20181015|edgar/data/320193/0000320193-18-000120.txt
+";
+
+    const FORM: &str = "Description:           Master Index of EDGAR Dissemination Feed by Form Type
+Last Data Received:    December 31, 2018
+
+Form Type   Company Name                                                  CIK         Date Filed  File Name
+---------------------------------------------------------------------------------------------------------------------------------------------
+10-K        APPLE INC                                                     320193      2018-11-05  edgar/data/320193/0000320193-18-000145.txt
+SC 13G/A    META  GROUP INC                                               1000015     2018-10-15  edgar/data/1000015/0000950123-18-011226.txt
+";
+
+    fn day(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn parse_master_test() {
+        let rows = parse_master(MASTER).unwrap();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(
+            rows[1],
+            IndexRow {
+                cik: 320193,
+                name: String::from("APPLE INC"),
+                form_type: String::from("4"),
+                date: day(2018, 10, 15),
+                filename: String::from("edgar/data/320193/0000320193-18-000120.txt"),
+            }
+        );
+        assert_eq!(rows[0].accession_number(), Some(95012318011226));
+        assert_eq!(
+            rows.into_iter().next().unwrap().into_entry(),
+            Some(SECEntry::new(
+                FilingType::Sec13GA,
+                String::from("META GROUP INC"),
+                1000015,
+                95012318011226,
+                20181015,
+                String::from("2018-10-15"),
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_form_test() {
+        let rows = parse_form(FORM).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].form_type, "10-K");
+        assert_eq!(rows[0].cik, 320193);
+        assert_eq!(rows[0].date, day(2018, 11, 5));
+        assert_eq!(rows[1].form_type, "SC 13G/A");
+        assert_eq!(rows[1].name, "META  GROUP INC");
+        assert_eq!(
+            rows[1].filename,
+            "edgar/data/1000015/0000950123-18-011226.txt"
+        );
+    }
+
+    #[test]
+    fn urls_test() {
+        // Whole past quarter in the middle, days at the ragged ends
+        let backfill = Backfill::new(day(2018, 3, 30), day(2018, 7, 2)).gzip(true);
+        let urls = backfill.urls(day(2026, 10, 19));
+        assert_eq!(
+            urls,
+            vec![
+                format!(
+                    "{}/daily-index/2018/QTR1/master.20180330.idx.gz",
+                    ARCHIVES_URL
+                ),
+                format!("{}/full-index/2018/QTR2/master.gz", ARCHIVES_URL),
+                format!(
+                    "{}/daily-index/2018/QTR3/master.20180702.idx.gz",
+                    ARCHIVES_URL
+                ),
+            ]
+        );
+
+        // The current quarter isn't over, so it goes a day at a time, skipping weekends
+        let backfill = Backfill::new(day(2026, 10, 1), day(2026, 12, 31)).index(IndexKind::Form);
+        let urls = backfill.urls(day(2026, 10, 19));
+        assert_eq!(urls.len(), 66);
+        assert!(urls[0].ends_with("/daily-index/2026/QTR4/form.20261001.idx"));
+    }
+
+    #[test]
+    fn run_test() {
+        let conn = TestDb::new();
+        let mut gzipped = GzEncoder::new(Vec::new(), Compression::default());
+        gzipped.write_all(MASTER.as_bytes()).unwrap();
+        let gzipped = gzipped.finish().unwrap();
+
+        let backfill = Backfill::new(day(2018, 10, 12), day(2018, 10, 15)).gzip(true);
+        let emitted = RefCell::new(Vec::new());
+        let mut sink = FanOut::new().with("test", |entries: &[SECEntry]| -> Result<()> {
+            let mut emitted = emitted.borrow_mut();
+            emitted.extend(entries.iter().map(SECEntry::accession_number));
+            Ok(())
+        });
+        let fetch = |url: &str| -> Result<Option<Vec<u8>>> {
+            if url.ends_with("master.20181015.idx.gz") {
+                Ok(Some(gzipped.clone()))
+            } else {
+                Ok(None)
+            }
+        };
+
+        let report = backfill.run(&conn, fetch, &mut sink).unwrap();
+        assert_eq!(
+            report,
+            BackfillReport {
+                files: 1,
+                missing: 1,
+                listed: 4,
+                new: 2,
+                skipped: 1,
+            }
+        );
+        assert_eq!(*emitted.borrow(), vec![32019318000120, 95012318011226]);
+        assert_eq!(FilingQuery::new().run(&conn).unwrap().filings.len(), 2);
+
+        // Running it again finds nothing new
+        let report = backfill.run(&conn, fetch, &mut sink).unwrap();
+        assert_eq!(report.new, 0);
+
+        // Nor once the seen-set is pruned and the filings archived
+        conn.execute("DELETE FROM accession_numbers");
+        archive_filings_before(&conn, 20181016).unwrap();
+        let report = backfill.run(&conn, fetch, &mut sink).unwrap();
+        assert_eq!(report.new, 0);
+        assert_eq!(emitted.borrow().len(), 2);
+
+        let backwards = Backfill::new(day(2018, 10, 15), day(2018, 10, 12));
+        assert!(backwards.run(&conn, fetch, &mut sink).is_err());
+    }
+
+    #[test]
+    fn output_file_test() {
+        let conn = TestDb::new();
+        let rows = (1..=CHUNK_SIZE + 500)
+            .map(|n| {
+                format!(
+                    "{}|COMPANY {}|8-K|20181015|edgar/data/{}/0000950123-18-{:06}.txt\n",
+                    n, n, n, n
+                )
+            })
+            .collect::<String>();
+        let index = format!(
+            "CIK|Company Name|Form Type|Date Filed|File Name\n-----\n{}",
+            rows
+        );
+        let fetch = |url: &str| -> Result<Option<Vec<u8>>> {
+            if url.ends_with("master.20181015.idx") {
+                Ok(Some(index.clone().into_bytes()))
+            } else {
+                Ok(None)
+            }
+        };
+
+        // Without --append the file is still replaced only once per run
+        let path = std::env::temp_dir().join(format!("backfill_{}.tsv", std::process::id()));
+        let mut sink = FanOut::new().with("output", OutputFile::new(&path, Format::Table(b'\t')));
+        let backfill = Backfill::new(day(2018, 10, 15), day(2018, 10, 15));
+        let report = backfill.run(&conn, fetch, &mut sink).unwrap();
+        assert_eq!(report.new, CHUNK_SIZE + 500);
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(written.lines().count(), CHUNK_SIZE + 501);
+        assert_eq!(written.matches("Filing Type").count(), 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,812 @@
+//! Reads the cover pages of Schedules 13D and 13G, filed by anyone who comes to
+//! own more than five percent of a class of a company's shares
+//!
+//! -> `parse_xml` reads the structured `edgarSubmission` schedules are filed as
+//!    since the end of 2024
+//! -> `parse_text` makes a best effort at older schedules, which are free text or
+//!    HTML laid out after the SEC's printed cover page
+//! -> `from_submission` and `fetch` find either among a filing's documents, and
+//!    fill in the subject company and filer from the header
+//! -> `write` and `load` keep it in the `beneficial_ownership` table
+//!
+//! Each reporting person has their own cover page, so a group filing lists
+//! several, often counting the same shares more than once.
+use crate::db::{on_backend, DbConnection};
+use crate::errors::*;
+use crate::models::BeneficialOwnershipRow;
+use crate::schema::beneficial_ownership;
+use crate::sec_entry::{FilingRole, SECEntry};
+use crate::submission::{self, Submission};
+use crate::values::{self, from_number, to_number};
+use crate::xml_tree::{self, Element};
+use chrono::NaiveDate;
+use diesel::prelude::*;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::io::BufRead;
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct BeneficialOwnership {
+    /// e.g. `SCHEDULE 13G` or `SC 13D/A`
+    pub form_type: Option<String>,
+    /// Read from the structured XML rather than pieced together from text
+    pub structured: bool,
+    pub subject: Party,
+    pub filer: Party,
+    pub cusip: Option<String>,
+    /// e.g. `Common Stock, par value $0.01`
+    pub class_title: Option<String>,
+    /// The date of the event that requires the filing
+    pub event_date: Option<NaiveDate>,
+    pub reporting_persons: Vec<ReportingPerson>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Party {
+    pub cik: Option<usize>,
+    pub name: Option<String>,
+}
+
+/// One cover page. Amounts are numbers of shares.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ReportingPerson {
+    pub name: String,
+    pub cik: Option<usize>,
+    pub citizenship: Option<String>,
+    /// Codes such as `IN`, `IA`, `CO` or `PN`, comma separated if there are several
+    pub type_of_person: Option<String>,
+    pub sole_voting_power: Option<f64>,
+    pub shared_voting_power: Option<f64>,
+    pub sole_dispositive_power: Option<f64>,
+    pub shared_dispositive_power: Option<f64>,
+    pub aggregate_amount: Option<f64>,
+    /// As a percentage, so `8.5` for 8.5%
+    pub percent_of_class: Option<f64>,
+}
+
+/// Reads a schedule filed as XML. Schedule 13D and 13G have schemas of their own
+/// that name the same things differently, so each field is looked for under all
+/// of its names.
+pub fn parse_xml(xml: &str) -> Result<BeneficialOwnership> {
+    let root = xml_tree::parse(xml)?;
+    let cover = root
+        .descendants("coverPageHeader")
+        .into_iter()
+        .next()
+        .chain_err(|| "Schedule has no cover page")?;
+
+    let persons = root
+        .descendants("coverPageHeaderReportingPersonDetails")
+        .into_iter()
+        .chain(root.descendants("reportingPersonInfo"))
+        .map(|person| ReportingPerson {
+            name: first_text(person, &["reportingPersonName"]).unwrap_or_default(),
+            cik: first_text(person, &["reportingPersonCIK", "reportingPersonCik"])
+                .and_then(|c| c.parse::<usize>().ok()),
+            citizenship: first_text(person, &["citizenshipOrOrganization"]),
+            type_of_person: first_text(person, &["typeOfReportingPerson"]),
+            sole_voting_power: first_number(person, &["soleVotingPower"]),
+            shared_voting_power: first_number(person, &["sharedVotingPower"]),
+            sole_dispositive_power: first_number(person, &["soleDispositivePower"]),
+            shared_dispositive_power: first_number(person, &["sharedDispositivePower"]),
+            aggregate_amount: first_number(
+                person,
+                &[
+                    "reportingPersonBeneficiallyOwnedAggregateNumberOfShares",
+                    "aggregateAmountOwned",
+                ],
+            ),
+            percent_of_class: first_number(person, &["classPercent", "percentOfClass"]),
+        })
+        .collect::<Vec<ReportingPerson>>();
+    // Rows are stored per reporting person, so the schedule would have nowhere to go
+    if persons.is_empty() {
+        bail!("No reporting persons found on the cover page");
+    }
+
+    Ok(BeneficialOwnership {
+        form_type: first_text(&root, &["submissionType"]),
+        structured: true,
+        subject: Party {
+            cik: first_text(cover, &["issuerCik", "issuerCIK"])
+                .and_then(|c| c.parse::<usize>().ok()),
+            name: first_text(cover, &["issuerName"]),
+        },
+        filer: Party {
+            cik: root
+                .descendants("filerCredentials")
+                .into_iter()
+                .find_map(|f| f.text_at(&["cik"]))
+                .and_then(|c| c.parse::<usize>().ok()),
+            name: None,
+        },
+        cusip: first_text(cover, &["issuerCusip", "issuerCUSIP", "cusipNumber"]),
+        class_title: first_text(cover, &["securitiesClassTitle"]),
+        event_date: first_text(
+            cover,
+            &["eventDateRequiresFilingThisStatement", "dateOfEvent"],
+        )
+        .and_then(|d| date(&d)),
+        reporting_persons: persons,
+    })
+}
+
+/// The text of the first element below `element` with one of `names`
+fn first_text(element: &Element, names: &[&str]) -> Option<String> {
+    names.iter().find_map(|name| {
+        element
+            .descendants(name)
+            .into_iter()
+            .find_map(Element::trimmed_text)
+    })
+}
+
+fn first_number(element: &Element, names: &[&str]) -> Option<f64> {
+    first_text(element, names).and_then(|n| values::number(&n))
+}
+
+fn date(text: &str) -> Option<NaiveDate> {
+    ["%m/%d/%Y", "%Y-%m-%d", "%B %d, %Y", "%B %d %Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(text.trim(), format).ok())
+}
+
+/// The numbered rows of the printed cover page that are read, along with the
+/// ones that are only there to mark where the row before them ends
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Row {
+    Name,
+    Citizenship,
+    SoleVoting,
+    SharedVoting,
+    SoleDispositive,
+    SharedDispositive,
+    Aggregate,
+    Percent,
+    Type,
+    Other,
+}
+
+const ROWS: [(Row, &str); 13] = [
+    (
+        Row::Name,
+        r"NAMES?\s+OF\s+REPORTING\s+PERSONS?(?:[\s.,]*(?:S\.S\.\s+OR\s+)?I\.R\.S\.\s+IDENTIFICATION\s+NOS?\.?\s+OF\s+ABOVE\s+PERSONS?(?:\s*\(ENTITIES\s+ONLY\))?)?",
+    ),
+    (Row::Other, r"CHECK\s+THE\s+APPROPRIATE\s+BOX"),
+    (Row::Other, r"SEC\s+USE\s+ONLY"),
+    (Row::Other, r"SOURCE\s+OF\s+FUNDS"),
+    (
+        Row::Citizenship,
+        r"CITIZENSHIP\s+OR\s+PLACE\s+OF\s+ORGANI[SZ]ATION",
+    ),
+    (Row::SoleVoting, r"SOLE\s+VOTING\s+POWER"),
+    (Row::SharedVoting, r"SHARED\s+VOTING\s+POWER"),
+    (Row::SoleDispositive, r"SOLE\s+DISPOSITIVE\s+POWER"),
+    (Row::SharedDispositive, r"SHARED\s+DISPOSITIVE\s+POWER"),
+    (
+        Row::Aggregate,
+        r"AGGREGATE\s+AMOUNT\s+BENEFICIALLY\s+OWNED(?:\s+BY\s+EACH\s+REPORTING\s+PERSON)?",
+    ),
+    (
+        Row::Other,
+        r"CHECK\s+(?:BOX\s+)?IF\s+THE\s+AGGREGATE\s+AMOUNT",
+    ),
+    (
+        Row::Percent,
+        r"PERCENT\s+OF\s+CLASS(?:\s+REPRESENTED\s+BY\s+AMOUNT\s+IN\s+ROW\s*\(?\d+\)?)?",
+    ),
+    (
+        Row::Type,
+        r"TYPE\s+OF\s+REPORTING\s+PERSON(?:\s*\(SEE\s+INSTRUCTIONS\))?",
+    ),
+];
+
+lazy_static! {
+    static ref LABELS: Vec<(Row, Regex)> = ROWS
+        .iter()
+        .map(|(row, pattern)| (*row, Regex::new(&format!("(?i){}", pattern)).unwrap()))
+        .collect();
+    static ref CUSIP: Regex = Regex::new(
+        r"(?i)CUSIP\s*(?:No\.?|Number|#)?\s*:?\s*([0-9A-Z]{6}\s?[0-9A-Z]{2}\s?[0-9A-Z])\b",
+    )
+    .unwrap();
+    static ref ISSUER_CAPTION: Regex = Regex::new(r"(?i)\(Name\s+of\s+Issuer\)").unwrap();
+    static ref CUSIP_CAPTION: Regex = Regex::new(r"(?i)\(CUSIP\s+Number\)").unwrap();
+    static ref CLASS_CAPTION: Regex =
+        Regex::new(r"(?i)\(Title\s+of\s+Class\s+of\s+Securities\)").unwrap();
+    static ref EVENT_CAPTION: Regex =
+        Regex::new(r"(?i)\(Date\s+of\s+Event\s+Which\s+Requires").unwrap();
+    static ref HTML: Regex = Regex::new(r"(?i)<(?:html|body|p|div|table)\b").unwrap();
+    static ref BREAKS: Regex = Regex::new(r"(?i)<br\s*/?>|</(?:p|div|tr|td|th|li|h\d)>").unwrap();
+    static ref TAGS: Regex = Regex::new(r"(?s)<[^>]*>").unwrap();
+    static ref ROW_NUMBER: Regex = Regex::new(r"\(?\d{1,2}\)?\.?\s*$").unwrap();
+    static ref AMOUNT: Regex = Regex::new(r"-0-|\d[\d,]*(?:\.\d+)?").unwrap();
+}
+
+/// Reads a schedule's cover pages out of its text, plain or HTML. Each value is
+/// taken from between its row's label and the next label, so anything missing
+/// or laid out unusually comes back as `None` rather than failing the whole
+/// schedule.
+pub fn parse_text(text: &str) -> Result<BeneficialOwnership> {
+    let text = plain_text(text);
+
+    let mut labels = Vec::new();
+    for (row, re) in LABELS.iter() {
+        labels.extend(re.find_iter(&text).map(|m| (m.start(), m.end(), *row)));
+    }
+    labels.sort_by_key(|(start, _, _)| *start);
+
+    let mut persons: Vec<ReportingPerson> = Vec::new();
+    for (i, (_, end, row)) in labels.iter().enumerate() {
+        let next = labels.get(i + 1).map_or(text.len(), |(start, _, _)| *start);
+        let value = without_row_number(&text[*end..next.max(*end)]);
+        if *row == Row::Name {
+            persons.push(ReportingPerson {
+                name: first_line(value).unwrap_or_default(),
+                ..ReportingPerson::default()
+            });
+            continue;
+        }
+        let person = match persons.last_mut() {
+            Some(person) => person,
+            None => continue,
+        };
+        // Only the first of each, as the schedule's items repeat some labels
+        match row {
+            Row::Citizenship => fill(&mut person.citizenship, first_line(value)),
+            Row::SoleVoting => fill(&mut person.sole_voting_power, first_amount(value)),
+            Row::SharedVoting => fill(&mut person.shared_voting_power, first_amount(value)),
+            Row::SoleDispositive => fill(&mut person.sole_dispositive_power, first_amount(value)),
+            Row::SharedDispositive => {
+                fill(&mut person.shared_dispositive_power, first_amount(value))
+            }
+            Row::Aggregate => fill(&mut person.aggregate_amount, first_amount(value)),
+            Row::Percent => fill(&mut person.percent_of_class, first_amount(value)),
+            Row::Type => fill(&mut person.type_of_person, first_line(value)),
+            Row::Name | Row::Other => {}
+        }
+    }
+    persons.retain(|p| !p.name.is_empty());
+    if persons.is_empty() {
+        bail!("No reporting persons found on the cover page");
+    }
+
+    Ok(BeneficialOwnership {
+        form_type: None,
+        structured: false,
+        subject: Party {
+            cik: None,
+            name: line_before(&text, &ISSUER_CAPTION),
+        },
+        filer: Party::default(),
+        cusip: line_before(&text, &CUSIP_CAPTION)
+            .or_else(|| CUSIP.captures(&text).map(|c| c[1].to_owned()))
+            .map(|c| c.replace(char::is_whitespace, "")),
+        class_title: line_before(&text, &CLASS_CAPTION),
+        event_date: line_before(&text, &EVENT_CAPTION).and_then(|d| date(&d)),
+        reporting_persons: persons,
+    })
+}
+
+fn fill<T>(field: &mut Option<T>, value: Option<T>) {
+    if field.is_none() {
+        *field = value;
+    }
+}
+
+/// Drops the tags of an HTML document, keeping a line break wherever a block or
+/// a table cell ended
+fn plain_text(text: &str) -> String {
+    if !HTML.is_match(text) {
+        return text.to_owned();
+    }
+    let text = BREAKS.replace_all(text, "\n");
+    TAGS.replace_all(&text, " ")
+        .replace("&nbsp;", " ")
+        .replace("&#160;", " ")
+        .replace("&#xa0;", " ")
+        .replace("&amp;", "&")
+        .replace("&#8217;", "'")
+}
+
+/// Cuts off the number of the next row, which comes just before its label
+fn without_row_number(value: &str) -> &str {
+    match ROW_NUMBER.find(value) {
+        Some(m) => &value[..m.start()],
+        None => value,
+    }
+}
+
+fn first_line(value: &str) -> Option<String> {
+    value
+        .lines()
+        .map(|line| {
+            line.trim_matches(|c: char| c.is_whitespace() || c == ':')
+                .to_owned()
+        })
+        .find(|line| !line.is_empty())
+}
+
+fn first_amount(value: &str) -> Option<f64> {
+    AMOUNT.find(value).and_then(|m| values::number(m.as_str()))
+}
+
+/// The last non-empty line before `caption`, the way the printed cover page
+/// puts a value above the caption saying what it is
+fn line_before(text: &str, caption: &Regex) -> Option<String> {
+    let start = caption.find(text)?.start();
+    text[..start]
+        .lines()
+        .rev()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_owned)
+}
+
+/// Reads the schedule among a complete submission's documents, preferring the
+/// XML, and takes the subject company and filer from the header
+pub fn from_submission<R: BufRead>(
+    submission: Submission<R>,
+) -> Result<Option<BeneficialOwnership>> {
+    let header = submission.header();
+    let party = |role: FilingRole| {
+        header
+            .companies
+            .iter()
+            .find(|c| c.role == Some(role))
+            .map(|c| Party {
+                cik: Some(c.cik),
+                name: Some(c.name.clone()),
+            })
+    };
+    let subject = party(FilingRole::Subject);
+    let filer = party(FilingRole::FiledBy);
+    let form_type = header.form_type.clone();
+
+    let mut schedule = None;
+    let mut text = None;
+    for document in submission {
+        let document = document?;
+        let contents = document.text();
+        if contents.contains("<edgarSubmission") && contents.contains("coverPageHeader") {
+            schedule = Some(parse_xml(&contents)?);
+            break;
+        }
+        let cover = document.document_type.starts_with("SC 13")
+            || document.document_type.starts_with("SCHEDULE 13");
+        if cover && text.is_none() {
+            text = Some(contents);
+        }
+    }
+    let mut schedule = match (schedule, text) {
+        (Some(schedule), _) => schedule,
+        (None, Some(text)) => parse_text(&text)?,
+        (None, None) => return Ok(None),
+    };
+
+    schedule.form_type = schedule.form_type.or(form_type);
+    if let Some(subject) = subject {
+        schedule.subject.cik = schedule.subject.cik.or(subject.cik);
+        schedule.subject.name = schedule.subject.name.or(subject.name);
+    }
+    if let Some(filer) = filer {
+        schedule.filer.cik = schedule.filer.cik.or(filer.cik);
+        schedule.filer.name = schedule.filer.name.or(filer.name);
+    }
+    Ok(Some(schedule))
+}
+
+/// Downloads `entry`'s complete submission and reads its schedule
+pub fn fetch(entry: &SECEntry) -> Result<BeneficialOwnership> {
+    from_submission(submission::fetch(entry)?)?.chain_err(|| {
+        format!(
+            "No Schedule 13D or 13G in {}",
+            SECEntry::dashed_accession(entry.accession_number())
+        )
+    })
+}
+
+/// Stores `schedule` as the one of filing `acc`, replacing any stored before
+pub fn write(conn: &DbConnection, acc: usize, schedule: &BeneficialOwnership) -> Result<()> {
+    let acc = acc as i64;
+    let rows = schedule
+        .reporting_persons
+        .iter()
+        .enumerate()
+        .map(|(i, p)| BeneficialOwnershipRow {
+            accession_number: acc,
+            position: i as i32,
+            form_type: schedule.form_type.clone(),
+            structured: schedule.structured,
+            subject_cik: schedule.subject.cik.map(|c| c as i64),
+            subject_name: schedule.subject.name.clone(),
+            cusip: schedule.cusip.clone(),
+            class_title: schedule.class_title.clone(),
+            event_date: schedule.event_date.map(to_number),
+            filer_cik: schedule.filer.cik.map(|c| c as i64),
+            filer_name: schedule.filer.name.clone(),
+            reporting_person: p.name.clone(),
+            reporting_person_cik: p.cik.map(|c| c as i64),
+            citizenship: p.citizenship.clone(),
+            type_of_person: p.type_of_person.clone(),
+            sole_voting_power: p.sole_voting_power,
+            shared_voting_power: p.shared_voting_power,
+            sole_dispositive_power: p.sole_dispositive_power,
+            shared_dispositive_power: p.shared_dispositive_power,
+            aggregate_amount: p.aggregate_amount,
+            percent_of_class: p.percent_of_class,
+        })
+        .collect::<Vec<BeneficialOwnershipRow>>();
+
+    conn.transaction(|| {
+        on_backend!(conn, c => {
+            diesel::delete(
+                beneficial_ownership::table.filter(beneficial_ownership::accession_number.eq(acc)),
+            )
+            .execute(c)?;
+            diesel::insert_into(beneficial_ownership::table)
+                .values(&rows)
+                .execute(c)
+        })
+        .chain_err(|| "Unable to write beneficial ownership")?;
+        Ok(())
+    })
+}
+
+/// The schedule stored for filing `acc`, if there is one
+pub fn load(conn: &DbConnection, acc: usize) -> Result<Option<BeneficialOwnership>> {
+    let acc = acc as i64;
+    let rows = on_backend!(conn, c => {
+        beneficial_ownership::table
+            .filter(beneficial_ownership::accession_number.eq(acc))
+            .order(beneficial_ownership::position)
+            .load::<BeneficialOwnershipRow>(c)
+    })
+    .chain_err(|| "Unable to load beneficial ownership")?;
+
+    let first = match rows.first() {
+        Some(first) => first,
+        None => return Ok(None),
+    };
+    Ok(Some(BeneficialOwnership {
+        form_type: first.form_type.clone(),
+        structured: first.structured,
+        subject: Party {
+            cik: first.subject_cik.map(|c| c as usize),
+            name: first.subject_name.clone(),
+        },
+        filer: Party {
+            cik: first.filer_cik.map(|c| c as usize),
+            name: first.filer_name.clone(),
+        },
+        cusip: first.cusip.clone(),
+        class_title: first.class_title.clone(),
+        event_date: first.event_date.and_then(from_number),
+        reporting_persons: rows
+            .iter()
+            .map(|r| ReportingPerson {
+                name: r.reporting_person.clone(),
+                cik: r.reporting_person_cik.map(|c| c as usize),
+                citizenship: r.citizenship.clone(),
+                type_of_person: r.type_of_person.clone(),
+                sole_voting_power: r.sole_voting_power,
+                shared_voting_power: r.shared_voting_power,
+                sole_dispositive_power: r.sole_dispositive_power,
+                shared_dispositive_power: r.shared_dispositive_power,
+                aggregate_amount: r.aggregate_amount,
+                percent_of_class: r.percent_of_class,
+            })
+            .collect(),
+    }))
+}
+
+#[cfg(test)]
+mod beneficial_ownership_tests {
+    use super::*;
+    use crate::test_db::TestDb;
+    use std::io::Cursor;
+
+    const SCHEDULE_13G: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<edgarSubmission xmlns="http://www.sec.gov/edgar/schedule13g" xmlns:com="http://www.sec.gov/edgar/common">
+  <headerData>
+    <submissionType>SCHEDULE 13G/A</submissionType>
+    <filerInfo>
+      <filer>
+        <filerCredentials>
+          <cik>0000102909</cik>
+          <ccc>XXXXXXXX</ccc>
+        </filerCredentials>
+      </filer>
+    </filerInfo>
+  </headerData>
+  <formData>
+    <coverPageHeader>
+      <amendmentNo>3</amendmentNo>
+      <securitiesClassTitle>Common Stock</securitiesClassTitle>
+      <eventDateRequiresFilingThisStatement>12/31/2024</eventDateRequiresFilingThisStatement>
+      <issuerInfo>
+        <issuerCik>0000320193</issuerCik>
+        <issuerName>Apple Inc.</issuerName>
+        <issuerCusip>037833100</issuerCusip>
+      </issuerInfo>
+    </coverPageHeader>
+    <coverPageHeaderReportingPersonDetails>
+      <reportingPersonName>The Vanguard Group</reportingPersonName>
+      <citizenshipOrOrganization>PA</citizenshipOrOrganization>
+      <reportingPersonBeneficiallyOwnedNumberOfShares>
+        <soleVotingPower>0.00</soleVotingPower>
+        <sharedVotingPower>20,000,000</sharedVotingPower>
+        <soleDispositivePower>1,300,000,000</soleDispositivePower>
+        <sharedDispositivePower>60,000,000</sharedDispositivePower>
+      </reportingPersonBeneficiallyOwnedNumberOfShares>
+      <reportingPersonBeneficiallyOwnedAggregateNumberOfShares>1,360,000,000</reportingPersonBeneficiallyOwnedAggregateNumberOfShares>
+      <classPercent>9.0</classPercent>
+      <typeOfReportingPerson>IA</typeOfReportingPerson>
+    </coverPageHeaderReportingPersonDetails>
+  </formData>
+</edgarSubmission>
+"#;
+
+    const SCHEDULE_13D: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<edgarSubmission xmlns="http://www.sec.gov/edgar/schedule13D">
+  <headerData>
+    <submissionType>SCHEDULE 13D</submissionType>
+  </headerData>
+  <formData>
+    <coverPageHeader>
+      <securitiesClassTitle>Class A Common Stock</securitiesClassTitle>
+      <dateOfEvent>03/04/2025</dateOfEvent>
+      <issuerInfo>
+        <issuerCIK>0001660134</issuerCIK>
+        <issuerCUSIP>679295105</issuerCUSIP>
+        <issuerName>Okta, Inc.</issuerName>
+      </issuerInfo>
+    </coverPageHeader>
+    <reportingPersons>
+      <reportingPersonInfo>
+        <reportingPersonCIK>0001655081</reportingPersonCIK>
+        <reportingPersonName>Example Partners LP</reportingPersonName>
+        <citizenshipOrOrganization>DE</citizenshipOrOrganization>
+        <soleVotingPower>0</soleVotingPower>
+        <sharedVotingPower>9500000</sharedVotingPower>
+        <soleDispositivePower>0</soleDispositivePower>
+        <sharedDispositivePower>9500000</sharedDispositivePower>
+        <aggregateAmountOwned>9500000</aggregateAmountOwned>
+        <percentOfClass>5.6</percentOfClass>
+        <typeOfReportingPerson>PN</typeOfReportingPerson>
+      </reportingPersonInfo>
+      <reportingPersonInfo>
+        <reportingPersonName>Example GP LLC</reportingPersonName>
+        <aggregateAmountOwned>9500000</aggregateAmountOwned>
+        <percentOfClass>5.6</percentOfClass>
+        <typeOfReportingPerson>OO</typeOfReportingPerson>
+      </reportingPersonInfo>
+    </reportingPersons>
+  </formData>
+</edgarSubmission>
+"#;
+
+    const SCHEDULE_13G_TEXT: &str = "
+                                 SCHEDULE 13G
+
+                   Under the Securities Exchange Act of 1934
+                              (Amendment No. 2)*
+
+                                   APPLE INC.
+                                (Name of Issuer)
+
+                                  Common Stock
+                         (Title of Class of Securities)
+
+                                   037833100
+                                 (CUSIP Number)
+
+                               December 31, 2017
+            (Date of Event Which Requires Filing of this Statement)
+
+CUSIP No. 037833100
+
+1.   NAMES OF REPORTING PERSONS
+     I.R.S. IDENTIFICATION NOS. OF ABOVE PERSONS (ENTITIES ONLY)
+
+     The Vanguard Group - 23-1945930
+
+2.   CHECK THE APPROPRIATE BOX IF A MEMBER OF A GROUP
+     (a)
+     (b)
+
+3.   SEC USE ONLY
+
+4.   CITIZENSHIP OR PLACE OF ORGANIZATION
+
+     Pennsylvania
+
+NUMBER OF        5. SOLE VOTING POWER
+SHARES                  6,541,297
+BENEFICIALLY     6. SHARED VOTING POWER
+OWNED BY                1,119,568
+EACH             7. SOLE DISPOSITIVE POWER
+REPORTING               334,345,120
+PERSON WITH      8. SHARED DISPOSITIVE POWER
+                        -0-
+
+9.   AGGREGATE AMOUNT BENEFICIALLY OWNED BY EACH REPORTING PERSON
+
+     341,794,580
+
+10.  CHECK BOX IF THE AGGREGATE AMOUNT IN ROW (9) EXCLUDES CERTAIN SHARES
+
+11.  PERCENT OF CLASS REPRESENTED BY AMOUNT IN ROW 9
+
+     6.78%
+
+12.  TYPE OF REPORTING PERSON
+
+     IA
+
+Item 4.  Ownership:
+     (b) Percent of class: 6.78%
+";
+
+    #[test]
+    fn parse_13g_test() {
+        let schedule = parse_xml(SCHEDULE_13G).unwrap();
+        assert_eq!(schedule.form_type, Some(String::from("SCHEDULE 13G/A")));
+        assert!(schedule.structured);
+        assert_eq!(
+            schedule.subject,
+            Party {
+                cik: Some(320193),
+                name: Some(String::from("Apple Inc.")),
+            }
+        );
+        assert_eq!(schedule.filer.cik, Some(102909));
+        assert_eq!(schedule.cusip, Some(String::from("037833100")));
+        assert_eq!(schedule.class_title, Some(String::from("Common Stock")));
+        assert_eq!(schedule.event_date, NaiveDate::from_ymd_opt(2024, 12, 31));
+        assert_eq!(
+            schedule.reporting_persons,
+            vec![ReportingPerson {
+                name: String::from("The Vanguard Group"),
+                cik: None,
+                citizenship: Some(String::from("PA")),
+                type_of_person: Some(String::from("IA")),
+                sole_voting_power: Some(0.0),
+                shared_voting_power: Some(20_000_000.0),
+                sole_dispositive_power: Some(1_300_000_000.0),
+                shared_dispositive_power: Some(60_000_000.0),
+                aggregate_amount: Some(1_360_000_000.0),
+                percent_of_class: Some(9.0),
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_13d_test() {
+        let schedule = parse_xml(SCHEDULE_13D).unwrap();
+        assert_eq!(schedule.subject.cik, Some(1660134));
+        assert_eq!(schedule.cusip, Some(String::from("679295105")));
+        assert_eq!(schedule.event_date, NaiveDate::from_ymd_opt(2025, 3, 4));
+        assert_eq!(schedule.filer, Party::default());
+
+        let persons = &schedule.reporting_persons;
+        assert_eq!(persons.len(), 2);
+        assert_eq!(persons[0].cik, Some(1655081));
+        assert_eq!(persons[0].shared_voting_power, Some(9_500_000.0));
+        assert_eq!(persons[0].percent_of_class, Some(5.6));
+        assert_eq!(persons[1].name, "Example GP LLC");
+        assert_eq!(persons[1].sole_voting_power, None);
+        assert_eq!(persons[1].type_of_person, Some(String::from("OO")));
+    }
+
+    #[test]
+    fn no_reporting_persons_test() {
+        let start = SCHEDULE_13G
+            .find("<coverPageHeaderReportingPersonDetails>")
+            .unwrap();
+        let end = "</coverPageHeaderReportingPersonDetails>";
+        let end = SCHEDULE_13G.find(end).unwrap() + end.len();
+        let xml = format!("{}{}", &SCHEDULE_13G[..start], &SCHEDULE_13G[end..]);
+        assert!(parse_xml(&xml).is_err());
+    }
+
+    #[test]
+    fn parse_text_test() {
+        let schedule = parse_text(SCHEDULE_13G_TEXT).unwrap();
+        assert!(!schedule.structured);
+        assert_eq!(schedule.subject.name, Some(String::from("APPLE INC.")));
+        assert_eq!(schedule.cusip, Some(String::from("037833100")));
+        assert_eq!(schedule.class_title, Some(String::from("Common Stock")));
+        assert_eq!(schedule.event_date, NaiveDate::from_ymd_opt(2017, 12, 31));
+        assert_eq!(
+            schedule.reporting_persons,
+            vec![ReportingPerson {
+                name: String::from("The Vanguard Group - 23-1945930"),
+                cik: None,
+                citizenship: Some(String::from("Pennsylvania")),
+                type_of_person: Some(String::from("IA")),
+                sole_voting_power: Some(6_541_297.0),
+                shared_voting_power: Some(1_119_568.0),
+                sole_dispositive_power: Some(334_345_120.0),
+                shared_dispositive_power: Some(0.0),
+                aggregate_amount: Some(341_794_580.0),
+                percent_of_class: Some(6.78),
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_html_test() {
+        let html = "<html><body><table>\
+            <tr><td>1</td><td>NAME OF REPORTING PERSON</td></tr>\
+            <tr><td></td><td>BlackRock, Inc.</td></tr>\
+            <tr><td>5</td><td>SOLE VOTING POWER</td><td>1,000&nbsp;</td></tr>\
+            <tr><td>6</td><td>SHARED VOTING POWER</td><td></td></tr>\
+            <tr><td>11</td><td>PERCENT OF CLASS REPRESENTED BY AMOUNT IN ROW (9)</td><td>7.1%</td></tr>\
+            </table></body></html>";
+        let schedule = parse_text(html).unwrap();
+        let person = &schedule.reporting_persons[0];
+        assert_eq!(person.name, "BlackRock, Inc.");
+        assert_eq!(person.sole_voting_power, Some(1000.0));
+        assert_eq!(person.shared_voting_power, None);
+        assert_eq!(person.percent_of_class, Some(7.1));
+
+        assert!(parse_text("Nothing to see here").is_err());
+    }
+
+    #[test]
+    fn from_submission_test() {
+        let text = format!(
+            "<SEC-DOCUMENT>\n<SEC-HEADER>\nACCESSION NUMBER:\t0000932471-18-004321\n\
+             CONFORMED SUBMISSION TYPE:\tSC 13G/A\n\
+             SUBJECT COMPANY:\t\n\n\tCOMPANY DATA:\t\n\t\tCOMPANY CONFORMED NAME:\t\t\tAPPLE INC\n\
+             \t\tCENTRAL INDEX KEY:\t\t\t0000320193\n\n\
+             FILED BY:\t\n\n\tCOMPANY DATA:\t\n\t\tCOMPANY CONFORMED NAME:\t\t\tVANGUARD GROUP INC\n\
+             \t\tCENTRAL INDEX KEY:\t\t\t0000102909\n</SEC-HEADER>\n\
+             <DOCUMENT>\n<TYPE>SC 13G/A\n<SEQUENCE>1\n<FILENAME>apple.txt\n<TEXT>\n{}</TEXT>\n\
+             </DOCUMENT>\n</SEC-DOCUMENT>\n",
+            SCHEDULE_13G_TEXT
+        );
+        let submission = Submission::new(Cursor::new(text.as_bytes())).unwrap();
+        let schedule = from_submission(submission).unwrap().unwrap();
+        assert_eq!(schedule.form_type, Some(String::from("SC 13G/A")));
+        assert_eq!(
+            schedule.subject,
+            Party {
+                cik: Some(320193),
+                name: Some(String::from("APPLE INC.")),
+            }
+        );
+        assert_eq!(
+            schedule.filer,
+            Party {
+                cik: Some(102909),
+                name: Some(String::from("VANGUARD GROUP INC")),
+            }
+        );
+        assert_eq!(schedule.reporting_persons.len(), 1);
+    }
+
+    fn store_test(conn: &DbConnection) {
+        let schedule = parse_xml(SCHEDULE_13D).unwrap();
+        assert_eq!(load(conn, 93247118004321).unwrap(), None);
+
+        write(conn, 93247118004321, &schedule).unwrap();
+        assert_eq!(load(conn, 93247118004321).unwrap(), Some(schedule.clone()));
+
+        let text = parse_text(SCHEDULE_13G_TEXT).unwrap();
+        write(conn, 93247118004321, &text).unwrap();
+        assert_eq!(load(conn, 93247118004321).unwrap(), Some(text));
+    }
+
+    #[test]
+    fn postgres_store_test() {
+        store_test(&TestDb::new());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_store_test() {
+        store_test(&TestDb::sqlite());
+    }
+}
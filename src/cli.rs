@@ -0,0 +1,237 @@
+//! Command line arguments for the scraper
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+
+pub fn init() -> ArgMatches<'static> {
+    let app = App::new("sec_scraper")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Copies the most recent filings from the SEC into a table")
+        .arg(
+            Arg::with_name("migrate")
+                .long("migrate")
+                .help("Applies pending database migrations before scraping"),
+        )
+        .arg(
+            flag_with_value(
+                "output",
+                "Where new filings are written, replacing the last run's unless --append is \
+                 given; - for standard output",
+            )
+            .default_value("accession_numbers.txt"),
+        )
+        .arg(
+            Arg::with_name("append")
+                .long("append")
+                .help("Adds to the output file instead of replacing it"),
+        )
+        .arg(
+            flag_with_value(
+                "rotate",
+                "Starts a new output file each day (daily) or once it reaches a size, e.g. 10M",
+            )
+            .requires("append"),
+        )
+        .arg(
+            Arg::with_name("stdout")
+                .long("stdout")
+                .help("Also prints new filings to standard output"),
+        )
+        .arg(
+            flag_with_value(
+                "copy-to",
+                "Also stores new filings in the filings table of this Postgres database",
+            )
+            .multiple(true)
+            .number_of_values(1),
+        )
+        .arg(
+            flag_with_value("webhook", "Also POSTs new filings as JSON to this URL")
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            flag_with_value("webhook-secret", "Signs webhook requests with HMAC-SHA256")
+                .env("WEBHOOK_SECRET")
+                .hide_env_values(true),
+        )
+        .arg(
+            flag_with_value(
+                "dead-letter",
+                "Keeps webhook batches that could not be delivered in this file",
+            )
+            .requires("webhook"),
+        )
+        .arg(
+            Arg::with_name("items")
+                .long("items")
+                .help("Looks up which items new 8-K filings report, e.g. 2.02"),
+        )
+        .arg(
+            flag_with_value(
+                "webhook-item",
+                "Only POSTs 8-K filings reporting this item to webhooks, implies --items",
+            )
+            .requires("webhook")
+            .multiple(true)
+            .number_of_values(1),
+        )
+        .arg(
+            flag_with_value("format", "Lays out the output file")
+                .possible_values(&["tsv", "csv", "jsonl", "json"])
+                .default_value("tsv"),
+        )
+        .arg(
+            flag_with_value(
+                "columns",
+                "Columns of a tsv or csv table, e.g. form,cik,name,accession,url. Besides form, \
+                 name, cik, accession, date, timestamp, url and role there are accession_dashed, \
+                 cik_padded, index_json_url and items",
+            )
+            .global(true),
+        )
+        .arg(flag_with_value(
+            "delimiter",
+            "Separates the fields of a tsv or csv table, overriding its usual one",
+        ))
+        .subcommand(
+            SubCommand::with_name("db")
+                .about("Manages the filings database")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("migrate").about("Applies any pending migrations"),
+                )
+                .subcommand(
+                    SubCommand::with_name("status")
+                        .about("Lists each migration and whether it has been applied"),
+                )
+                .subcommand(
+                    SubCommand::with_name("prune")
+                        .about("Forgets old accession numbers and archives old filings")
+                        .arg(
+                            flag_with_value(
+                                "keep-days",
+                                "Days to remember an accession number for",
+                            )
+                            .default_value("90"),
+                        )
+                        .arg(flag_with_value(
+                            "archive-after-days",
+                            "Moves filings older than this into filings_archive",
+                        )),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("filings")
+                .about("Lists stored filings, newest first")
+                .args(&filter_args())
+                .arg(
+                    flag_with_value("limit", "Most filings to list, up to 10000")
+                        .default_value("100"),
+                )
+                .arg(flag_with_value(
+                    "after",
+                    "Cursor printed at the end of the previous page",
+                ))
+                .arg(
+                    Arg::with_name("oldest-first")
+                        .long("oldest-first")
+                        .help("Lists the oldest filings first"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("backfill")
+                .about(
+                    "Fetches filings for past dates from EDGAR's index files and writes the new \
+                     ones like a scrape would",
+                )
+                .arg(flag_with_value("from", "First filing date, as yyyymmdd").required(true))
+                .arg(flag_with_value(
+                    "to",
+                    "Last filing date, as yyyymmdd, today if left out",
+                ))
+                .arg(
+                    flag_with_value("index", "Which index files to read")
+                        .possible_values(&["master", "form"])
+                        .default_value("master"),
+                )
+                .arg(
+                    Arg::with_name("gzip")
+                        .long("gzip")
+                        .help("Downloads the gzipped index files"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("download")
+                .about("Downloads a filing's documents into a local archive")
+                .arg(flag_with_value("cik", "Central Index Key of the filer").required(true))
+                .arg(
+                    flag_with_value("accession", "Accession number, with or without dashes")
+                        .required(true),
+                )
+                .arg(
+                    flag_with_value(
+                        "document",
+                        "A document to download, by its name in the filing index",
+                    )
+                    .multiple(true)
+                    .number_of_values(1),
+                )
+                .arg(Arg::with_name("submission").long("submission").help(
+                    "Also downloads the complete submission text file, which is all that is \
+                     downloaded without --document",
+                ))
+                .arg(
+                    flag_with_value("dir", "Directory the archive is kept in")
+                        .default_value("filings"),
+                )
+                .arg(
+                    flag_with_value(
+                        "layout",
+                        "Lays files out by cik/accession/filename or by their SHA-256",
+                    )
+                    .possible_values(&["filing", "content"])
+                    .default_value("filing"),
+                ),
+        );
+
+    #[cfg(feature = "export")]
+    let app = app.subcommand(
+        SubCommand::with_name("export")
+            .about("Writes stored filings as Parquet or Arrow, one directory per filing date")
+            .arg(
+                Arg::with_name("dir")
+                    .required(true)
+                    .help("Directory to write the partitions under"),
+            )
+            .arg(
+                flag_with_value("format", "File format to write")
+                    .possible_values(&["parquet", "arrow"])
+                    .default_value("parquet"),
+            )
+            .args(&filter_args()),
+    );
+
+    app.get_matches()
+}
+
+/// The options that narrow down stored filings, shared by `filings` and `export`
+fn filter_args<'a>() -> Vec<Arg<'a, 'a>> {
+    vec![
+        flag_with_value("cik", "Central Index Key of the filer"),
+        flag_with_value("form", "Exact form type, e.g. 8-K"),
+        flag_with_value(
+            "family",
+            "Form type including its amendments, e.g. 8-K for 8-K and 8-K/A",
+        ),
+        flag_with_value("from", "Earliest filing date, as yyyymmdd"),
+        flag_with_value("to", "Latest filing date, as yyyymmdd"),
+        flag_with_value("name", "Part of the conformed name, any case"),
+        flag_with_value("role", "Filer, Filed by, Issuer, Reporting or Subject"),
+        flag_with_value("item", "8-K item reported, e.g. 2.02; any of several")
+            .multiple(true)
+            .number_of_values(1),
+    ]
+}
+
+fn flag_with_value<'a>(name: &'a str, help: &'a str) -> Arg<'a, 'a> {
+    Arg::with_name(name).long(name).takes_value(true).help(help)
+}
@@ -0,0 +1,220 @@
+//! Which fields tabular output has, and in what order
+//!
+//! A column spec is a comma separated list of column names, e.g.
+//! `form,cik,name,accession,url`. Besides the fields of `SECEntry` there are
+//! derived columns for joining against other EDGAR data:
+//!
+//! -> `accession_dashed`, the accession number as EDGAR prints it, `0001140361-18-030802`
+//! -> `cik_padded`, the CIK zero padded to ten digits, `0000320193`
+//! -> `index_json_url`, the JSON listing of the filing's documents
+//! -> `items`, the items an 8-K reports once they are looked up, `2.02,9.01`
+use crate::errors::*;
+use crate::sec_entry::SECEntry;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Column {
+    Form,
+    Name,
+    Cik,
+    Accession,
+    Date,
+    Timestamp,
+    Url,
+    Role,
+    AccessionDashed,
+    CikPadded,
+    IndexJsonUrl,
+    Items,
+}
+
+const ALL: [Column; 12] = [
+    Column::Form,
+    Column::Name,
+    Column::Cik,
+    Column::Accession,
+    Column::Date,
+    Column::Timestamp,
+    Column::Url,
+    Column::Role,
+    Column::AccessionDashed,
+    Column::CikPadded,
+    Column::IndexJsonUrl,
+    Column::Items,
+];
+
+impl Column {
+    /// What the column is called in a column spec
+    pub fn name(self) -> &'static str {
+        match self {
+            Column::Form => "form",
+            Column::Name => "name",
+            Column::Cik => "cik",
+            Column::Accession => "accession",
+            Column::Date => "date",
+            Column::Timestamp => "timestamp",
+            Column::Url => "url",
+            Column::Role => "role",
+            Column::AccessionDashed => "accession_dashed",
+            Column::CikPadded => "cik_padded",
+            Column::IndexJsonUrl => "index_json_url",
+            Column::Items => "items",
+        }
+    }
+
+    /// What the column is called in a table's header row
+    pub fn header(self) -> &'static str {
+        match self {
+            Column::Form => "Filing Type",
+            Column::Name => "Name",
+            Column::Cik => "CIK",
+            Column::Accession => "Accession Number",
+            Column::Date => "Date",
+            Column::Timestamp => "Timestamp",
+            Column::Url => "Url",
+            Column::Role => "Role",
+            Column::AccessionDashed => "Accession Number (Dashed)",
+            Column::CikPadded => "CIK (Padded)",
+            Column::IndexJsonUrl => "Index JSON Url",
+            Column::Items => "Items",
+        }
+    }
+
+    pub fn value(self, entry: &SECEntry) -> String {
+        match self {
+            Column::Form => entry.filing_type().form().to_owned(),
+            Column::Name => entry.name().to_owned(),
+            Column::Cik => entry.cik().to_string(),
+            Column::Accession => entry.accession_number().to_string(),
+            Column::Date => entry.date().to_string(),
+            Column::Timestamp => entry.timestamp().to_owned(),
+            Column::Url => entry.url().to_owned(),
+            Column::Role => entry.role().map(|r| r.as_str()).unwrap_or("").to_owned(),
+            Column::AccessionDashed => SECEntry::dashed_accession(entry.accession_number()),
+            Column::CikPadded => format!("{:010}", entry.cik()),
+            Column::IndexJsonUrl => {
+                SECEntry::get_index_json_url(entry.cik(), entry.accession_number())
+            }
+            Column::Items => entry.items().join(","),
+        }
+    }
+}
+
+impl FromStr for Column {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Column> {
+        match ALL.iter().find(|column| column.name() == s) {
+            Some(column) => Ok(*column),
+            None => Err(format!(
+                "Unknown column {:?}, expected one of {}",
+                s,
+                ALL.iter().map(|c| c.name()).collect::<Vec<_>>().join(", ")
+            )
+            .into()),
+        }
+    }
+}
+
+/// The columns of a table, in order. The default is every field of `SECEntry`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Columns(Vec<Column>);
+
+impl Default for Columns {
+    fn default() -> Columns {
+        Columns(ALL[..8].to_vec())
+    }
+}
+
+impl Columns {
+    pub fn new(columns: Vec<Column>) -> Columns {
+        Columns(columns)
+    }
+
+    pub fn header(&self) -> Vec<&'static str> {
+        self.0.iter().map(|c| c.header()).collect()
+    }
+
+    pub fn record(&self, entry: &SECEntry) -> Vec<String> {
+        self.0.iter().map(|c| c.value(entry)).collect()
+    }
+}
+
+impl FromStr for Columns {
+    type Err = Error;
+
+    /// Parses a column spec like `form,cik,name`
+    fn from_str(s: &str) -> Result<Columns> {
+        let columns = s
+            .split(',')
+            .map(|name| name.trim().parse::<Column>())
+            .collect::<Result<Vec<Column>>>()?;
+        Ok(Columns(columns))
+    }
+}
+
+#[cfg(test)]
+mod columns_tests {
+    use super::*;
+    use crate::sec_entry::FilingType;
+
+    fn entry() -> SECEntry {
+        SECEntry::new(
+            FilingType::Sec8K,
+            String::from("Apple Inc."),
+            320193,
+            114036118030802,
+            20180629,
+            String::from("2018-06-29T17:27:37-04:00"),
+        )
+    }
+
+    #[test]
+    fn spec_test() {
+        let columns = "form, cik,name,accession_dashed,cik_padded,index_json_url"
+            .parse::<Columns>()
+            .unwrap();
+        assert_eq!(
+            columns.header(),
+            vec![
+                "Filing Type",
+                "CIK",
+                "Name",
+                "Accession Number (Dashed)",
+                "CIK (Padded)",
+                "Index JSON Url"
+            ]
+        );
+        assert_eq!(
+            columns.record(&entry()),
+            vec![
+                "8-K",
+                "320193",
+                "Apple Inc.",
+                "0001140361-18-030802",
+                "0000320193",
+                "https://www.sec.gov/Archives/edgar/data/320193/000114036118030802/index.json"
+            ]
+        );
+    }
+
+    #[test]
+    fn items_test() {
+        let reported = entry().with_items(vec![String::from("2.02"), String::from("9.01")]);
+        assert_eq!(Column::Items.value(&reported), "2.02,9.01");
+        assert_eq!(Column::Items.value(&entry()), "");
+    }
+
+    #[test]
+    fn unknown_column_test() {
+        assert!("form,sic".parse::<Columns>().is_err());
+        assert!("".parse::<Columns>().is_err());
+    }
+
+    #[test]
+    fn name_round_trip_test() {
+        for column in ALL.iter() {
+            assert_eq!(column.name().parse::<Column>().unwrap(), *column);
+        }
+    }
+}
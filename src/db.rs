@@ -0,0 +1,316 @@
+//! Chooses where the seen-set lives from the database URL
+//!
+//! -> `postgres://` or `postgresql://` URLs connect to Postgres
+//! -> `sqlite://<path>`, or a bare file path, opens a SQLite file (needs the `sqlite` feature)
+//!
+//! The functions here hand off to whichever backend the connection is for. The
+//! seen-set is stored differently on each, so its functions live in `postgres`
+//! and `sqlite`. Everything else uses the same tables on both, and only needs a
+//! backend of its own for writes that skip or replace existing rows.
+use crate::errors::*;
+use crate::models::{
+    AccessionNumber, ArchivedDocument, NewArchivedDocument, NewFiling, NewFilingItem,
+    NewFilingParty, PendingDelivery,
+};
+use crate::postgres;
+#[cfg(feature = "sqlite")]
+use crate::sqlite;
+use bigdecimal::BigDecimal;
+use chrono::NaiveDateTime;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+#[cfg(feature = "sqlite")]
+use diesel::sqlite::SqliteConnection;
+use dotenv::dotenv;
+use std::env;
+
+pub enum DbConnection {
+    Postgres(PgConnection),
+    #[cfg(feature = "sqlite")]
+    Sqlite(SqliteConnection),
+}
+
+impl DbConnection {
+    /// Runs `f` inside a transaction on whichever backend this is, rolling back
+    /// if it returns an error.
+    pub fn transaction<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce() -> Result<T>,
+    {
+        match self {
+            DbConnection::Postgres(conn) => conn.transaction(f),
+            #[cfg(feature = "sqlite")]
+            DbConnection::Sqlite(conn) => conn.transaction(f),
+        }
+    }
+}
+
+/// Runs `$body` with `$c` bound to the connection inside a `DbConnection`, for
+/// diesel code that reads the same on every backend but has to be compiled once
+/// for each. The body is a closure's, so `?` in it stops at the end of the body.
+macro_rules! on_backend {
+    ($conn:expr, $c:ident => $body:expr) => {
+        match $conn {
+            crate::db::DbConnection::Postgres($c) => crate::db::run_body(|| $body),
+            #[cfg(feature = "sqlite")]
+            crate::db::DbConnection::Sqlite($c) => crate::db::run_body(|| $body),
+        }
+    };
+}
+pub(crate) use on_backend;
+
+/// Inserts `$values` into `$table`, skipping rows that clash with one already
+/// there. Each backend spells that differently.
+macro_rules! insert_ignoring {
+    ($conn:expr, $table:expr, $values:expr) => {
+        match $conn {
+            DbConnection::Postgres(c) => diesel::insert_into($table)
+                .values($values)
+                .on_conflict_do_nothing()
+                .execute(c),
+            #[cfg(feature = "sqlite")]
+            DbConnection::Sqlite(c) => diesel::insert_or_ignore_into($table)
+                .values($values)
+                .execute(c),
+        }
+    };
+}
+
+/// Calls `on_backend!`'s body, which gives `?` inside it a closure to return from
+#[doc(hidden)]
+pub(crate) fn run_body<T, F: FnOnce() -> T>(body: F) -> T {
+    body()
+}
+
+/// Connects to `DATABASE_URL` if it is set, and to `url` otherwise.
+pub fn establish_connection(url: &str) -> DbConnection {
+    dotenv().ok();
+
+    let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| url.to_owned());
+    connect(&database_url).unwrap_or_else(|e| panic!("Error connecting to {}: {}", database_url, e))
+}
+
+pub fn connect(url: &str) -> Result<DbConnection> {
+    if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+        PgConnection::establish(url)
+            .map(DbConnection::Postgres)
+            .chain_err(|| "Unable to connect to Postgres")
+    } else {
+        connect_sqlite(url.trim_start_matches("sqlite://"))
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn connect_sqlite(path: &str) -> Result<DbConnection> {
+    SqliteConnection::establish(path)
+        .map(DbConnection::Sqlite)
+        .chain_err(|| "Unable to open SQLite database")
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn connect_sqlite(path: &str) -> Result<DbConnection> {
+    bail!(
+        "{} is not a Postgres URL, and SQLite support needs the `sqlite` feature",
+        path
+    )
+}
+
+pub fn write_number(conn: &DbConnection, acc_number: usize) -> Result<bool> {
+    match conn {
+        DbConnection::Postgres(c) => postgres::write_number(c, acc_number),
+        #[cfg(feature = "sqlite")]
+        DbConnection::Sqlite(c) => sqlite::write_number(c, acc_number),
+    }
+    .chain_err(|| "Unable to write accession Number")
+}
+
+pub fn write_numbers(conn: &DbConnection, acc_numbers: &[usize]) -> Result<Vec<usize>> {
+    match conn {
+        DbConnection::Postgres(c) => postgres::write_numbers(c, acc_numbers),
+        #[cfg(feature = "sqlite")]
+        DbConnection::Sqlite(c) => sqlite::write_numbers(c, acc_numbers),
+    }
+    .chain_err(|| "Unable to write accession Numbers")
+}
+
+pub fn write_filings(conn: &DbConnection, new_filings: &[NewFiling]) -> Result<usize> {
+    if new_filings.is_empty() {
+        return Ok(0);
+    }
+    insert_ignoring!(conn, crate::schema::filings::table, new_filings)
+        .chain_err(|| "Unable to write filings")
+}
+
+pub fn write_filing_items(conn: &DbConnection, items: &[NewFilingItem]) -> Result<usize> {
+    if items.is_empty() {
+        return Ok(0);
+    }
+    insert_ignoring!(conn, crate::schema::filing_items::table, items)
+        .chain_err(|| "Unable to write filing items")
+}
+
+pub fn write_filing_parties(conn: &DbConnection, parties: &[NewFilingParty]) -> Result<usize> {
+    if parties.is_empty() {
+        return Ok(0);
+    }
+    insert_ignoring!(conn, crate::schema::filing_parties::table, parties)
+        .chain_err(|| "Unable to write filing parties")
+}
+
+pub fn write_pending_deliveries(
+    conn: &DbConnection,
+    deliveries: &[PendingDelivery],
+) -> Result<usize> {
+    if deliveries.is_empty() {
+        return Ok(0);
+    }
+    insert_ignoring!(conn, crate::schema::pending_deliveries::table, deliveries)
+        .chain_err(|| "Unable to queue deliveries")
+}
+
+pub fn delete_numbers_seen_before(conn: &DbConnection, cutoff: NaiveDateTime) -> Result<usize> {
+    match conn {
+        DbConnection::Postgres(c) => postgres::delete_numbers_seen_before(c, cutoff),
+        #[cfg(feature = "sqlite")]
+        DbConnection::Sqlite(c) => sqlite::delete_numbers_seen_before(c, cutoff),
+    }
+    .chain_err(|| "Unable to prune accession Numbers")
+}
+
+/// Moves filings dated before `date` (as `yyyymmdd`) into `filings_archive`,
+/// apart from any still queued for delivery, and drops the parties and items of
+/// every archived filing
+pub fn archive_filings_before(conn: &DbConnection, date: i32) -> Result<usize> {
+    use crate::schema::{
+        filing_items, filing_parties, filings, filings_archive, pending_deliveries,
+    };
+
+    let queued = pending_deliveries::table.select(pending_deliveries::accession_number);
+    let old = filings::filing_date
+        .lt(date)
+        .and(filings::accession_number.ne_all(queued));
+    insert_ignoring!(conn, filings_archive::table, filings::table.filter(old))
+        .chain_err(|| "Unable to archive filings")?;
+    let archived = on_backend!(conn, c => diesel::delete(filings::table.filter(old)).execute(c))
+        .chain_err(|| "Unable to archive filings")?;
+
+    on_backend!(conn, c => {
+        let archive = filings_archive::table.select(filings_archive::accession_number);
+        diesel::delete(
+            filing_parties::table.filter(filing_parties::accession_number.eq_any(archive)),
+        )
+        .execute(c)?;
+        diesel::delete(filing_items::table.filter(filing_items::accession_number.eq_any(archive)))
+            .execute(c)
+    })
+    .chain_err(|| "Unable to drop archived filings' parties and items")?;
+    Ok(archived)
+}
+
+pub fn write_document(conn: &DbConnection, document: &NewArchivedDocument) -> Result<usize> {
+    match conn {
+        DbConnection::Postgres(c) => postgres::write_document(c, document),
+        #[cfg(feature = "sqlite")]
+        DbConnection::Sqlite(c) => sqlite::write_document(c, document),
+    }
+    .chain_err(|| "Unable to record downloaded document")
+}
+
+/// The documents downloaded for a filing, by filename
+pub fn get_documents(conn: &DbConnection, acc: usize) -> Result<Vec<ArchivedDocument>> {
+    use crate::schema::documents;
+
+    on_backend!(conn, c => {
+        documents::table
+            .filter(documents::accession_number.eq(acc as i64))
+            .order(documents::filename)
+            .load::<ArchivedDocument>(c)
+    })
+    .chain_err(|| "Unable to load downloaded documents")
+}
+
+pub fn delete_number(conn: &DbConnection, acc: usize) -> Result<usize> {
+    match conn {
+        DbConnection::Postgres(c) => postgres::delete_number(c, acc),
+        #[cfg(feature = "sqlite")]
+        DbConnection::Sqlite(c) => sqlite::delete_number(c, acc),
+    }
+    .chain_err(|| "Unable to delete accession Number")
+}
+
+pub fn get_number(conn: &DbConnection, acc: usize) -> Option<Vec<AccessionNumber>> {
+    match conn {
+        DbConnection::Postgres(c) => postgres::get_number(c, acc),
+        #[cfg(feature = "sqlite")]
+        DbConnection::Sqlite(c) => sqlite::get_number(c, acc),
+    }
+}
+
+pub fn get_numbers(conn: &DbConnection) -> Option<Vec<BigDecimal>> {
+    match conn {
+        DbConnection::Postgres(c) => postgres::get_numbers(c),
+        #[cfg(feature = "sqlite")]
+        DbConnection::Sqlite(c) => sqlite::get_numbers(c),
+    }
+}
+
+pub fn delete_all_posts(conn: &DbConnection) {
+    match conn {
+        DbConnection::Postgres(c) => postgres::delete_all_posts(c),
+        #[cfg(feature = "sqlite")]
+        DbConnection::Sqlite(c) => sqlite::delete_all_posts(c),
+    }
+}
+
+#[cfg(test)]
+mod db_tests {
+    use super::*;
+    use crate::test_db::TestDb;
+
+    fn seen_set_test(conn: &DbConnection) {
+        assert_eq!(get_number(conn, 6), None);
+        assert!(write_number(conn, 6).unwrap());
+        assert!(!write_number(conn, 6).unwrap());
+        assert_eq!(
+            get_number(conn, 6).unwrap().pop().unwrap().accession_number,
+            BigDecimal::from(6)
+        );
+
+        let mut unseen = write_numbers(conn, &[5, 6, 7]).unwrap();
+        unseen.sort();
+        assert_eq!(unseen, vec![5, 7]);
+
+        assert_eq!(delete_number(conn, 6).unwrap(), 1);
+        assert_eq!(get_number(conn, 6), None);
+        delete_all_posts(conn);
+        assert_eq!(get_numbers(conn), Some(vec![]));
+    }
+
+    #[test]
+    fn postgres_seen_set_test() {
+        seen_set_test(&TestDb::new());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_seen_set_test() {
+        seen_set_test(&TestDb::sqlite());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn large_accession_number_test() {
+        // Would not survive a round trip through f64
+        let acc = 999999999918999999;
+        let conn = TestDb::sqlite();
+        assert!(write_number(&conn, acc).unwrap());
+        assert_eq!(
+            get_number(&conn, acc)
+                .unwrap()
+                .pop()
+                .unwrap()
+                .accession_number,
+            BigDecimal::from(acc as u64)
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! Fetches files from EDGAR
+//!
+//! The SEC asks automated clients to say who they are in the `User-Agent`
+//! header and turns away requests that don't. Set `SEC_USER_AGENT` to a name and
+//! contact address, e.g. `Example Corp admin@example.com`, to override the default.
+//! It also asks for no more than ten requests a second, so every request made
+//! here waits its turn behind the one before.
+use crate::errors::*;
+use lazy_static::lazy_static;
+use reqwest::header::{RANGE, USER_AGENT};
+use reqwest::{Response, StatusCode};
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::path::Path;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const ARCHIVES_URL: &str = "https://www.sec.gov/Archives/edgar";
+
+const DEFAULT_USER_AGENT: &str = concat!("sec_scraper/", env!("CARGO_PKG_VERSION"));
+
+pub const MAX_REQUESTS_PER_SECOND: u32 = 10;
+
+lazy_static! {
+    /// When this process last sent EDGAR a request
+    static ref LAST_REQUEST: Mutex<Option<Instant>> = Mutex::new(None);
+}
+
+/// The URL of `filename` in a filing's directory under `base`, usually `ARCHIVES_URL`
+pub fn document_url(base: &str, cik: usize, acc: usize, filename: &str) -> String {
+    format!("{}/data/{}/{:018}/{}", base, cik, acc, filename)
+}
+
+pub fn user_agent() -> String {
+    env::var("SEC_USER_AGENT").unwrap_or_else(|_| DEFAULT_USER_AGENT.to_owned())
+}
+
+/// Waits until a request can go out without going over `MAX_REQUESTS_PER_SECOND`
+fn throttle() {
+    let gap = Duration::from_secs(1) / MAX_REQUESTS_PER_SECOND;
+    let mut last = LAST_REQUEST.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(next) = last.map(|l| l + gap) {
+        let now = Instant::now();
+        if next > now {
+            thread::sleep(next - now);
+        }
+    }
+    *last = Some(Instant::now());
+}
+
+/// Downloads `url`, returning `None` if it doesn't exist
+pub fn get(url: &str) -> Result<Option<Vec<u8>>> {
+    let mut res = match open(url)? {
+        Some(res) => res,
+        None => return Ok(None),
+    };
+
+    let mut body = Vec::new();
+    res.copy_to(&mut body)
+        .chain_err(|| format!("Unable to read {}", url))?;
+    Ok(Some(body))
+}
+
+/// Starts downloading `url`, returning `None` if it doesn't exist. The body is
+/// read as it arrives, for files too big to want in memory all at once.
+pub fn open(url: &str) -> Result<Option<Response>> {
+    throttle();
+    let client = reqwest::Client::new();
+    let res = client
+        .get(url)
+        .header(USER_AGENT, user_agent())
+        .send()
+        .chain_err(|| format!("{} not reached", url))?;
+
+    if res.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !res.status().is_success() {
+        bail!("{} answered {}", url, res.status());
+    }
+    Ok(Some(res))
+}
+
+/// Downloads `url` into `path`, carrying on from the end of whatever is already
+/// there. Returns `false` if it doesn't exist.
+///
+/// A failed download leaves what it got in `path`, so calling this again resumes
+/// it. Servers that ignore the `Range` header send everything, which replaces it.
+pub fn resume(url: &str, path: &Path) -> Result<bool> {
+    let offset = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    throttle();
+    let client = reqwest::Client::new();
+    let mut request = client.get(url).header(USER_AGENT, user_agent());
+    if offset > 0 {
+        request = request.header(RANGE, format!("bytes={}-", offset));
+    }
+    let mut res = request
+        .send()
+        .chain_err(|| format!("{} not reached", url))?;
+
+    let file = match res.status() {
+        StatusCode::NOT_FOUND => return Ok(false),
+        // Asked for the bytes after the end, so it was already whole
+        StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => return Ok(true),
+        StatusCode::PARTIAL_CONTENT => OpenOptions::new().append(true).open(path),
+        status if status.is_success() => File::create(path),
+        status => bail!("{} answered {}", url, status),
+    };
+    let mut file = file.chain_err(|| format!("Unable to write {}", path.display()))?;
+    res.copy_to(&mut file)
+        .chain_err(|| format!("Unable to read {}", url))?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod edgar_tests {
+    use super::*;
+
+    #[test]
+    fn throttle_test() {
+        let started = Instant::now();
+        for _ in 0..3 {
+            throttle();
+        }
+        // The first may go straight away, the others wait their turn
+        assert!(started.elapsed() >= Duration::from_secs(2) / MAX_REQUESTS_PER_SECOND);
+    }
+}
@@ -1 +1,5 @@
-error_chain! {}
+error_chain! {
+    foreign_links {
+        Diesel(::diesel::result::Error);
+    }
+}
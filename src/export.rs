@@ -0,0 +1,320 @@
+//! Exports stored filings for columnar tools like DuckDB and pandas
+//!
+//! Filings are read from the store a page at a time, oldest first, and written
+//! as one file per filing date under hive style directories:
+//!
+//! -> `<dir>/filing_date=2026-10-18/filings.parquet` for Parquet
+//! -> `<dir>/filing_date=2026-10-18/filings.arrow` for the Arrow IPC file format
+//!
+//! Filing dates are `Date32` columns, feed timestamps UTC `Timestamp`s and form
+//! types dictionary encoded strings, so they load as categoricals. Exporting a
+//! date again replaces its file.
+use crate::db::DbConnection;
+use crate::errors::*;
+use crate::filings::{FilingOrder, FilingQuery};
+use crate::sec_entry::SECEntry;
+use arrow::array::{
+    ArrayRef, Date32Array, Int64Array, StringArray, StringDictionaryBuilder,
+    TimestampMillisecondArray,
+};
+use arrow::datatypes::{DataType, Field, Int32Type, Schema, SchemaRef, TimeUnit};
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::RecordBatch;
+use chrono::{DateTime, NaiveDate};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Filings read from the store at a time
+const PAGE_SIZE: usize = 5000;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ExportFormat {
+    Parquet,
+    ArrowIpc,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Parquet => "parquet",
+            ExportFormat::ArrowIpc => "arrow",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<ExportFormat> {
+        match s {
+            "parquet" => Ok(ExportFormat::Parquet),
+            "arrow" => Ok(ExportFormat::ArrowIpc),
+            _ => Err(format!("Unknown export format {:?}", s).into()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct ExportReport {
+    pub filings: usize,
+    pub partitions: usize,
+}
+
+/// The columns every exported file has
+pub fn schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("accession_number", DataType::Int64, false),
+        Field::new(
+            "form_type",
+            DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+            false,
+        ),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("cik", DataType::Int64, false),
+        Field::new("role", DataType::Utf8, true),
+        Field::new("filing_date", DataType::Date32, false),
+        Field::new(
+            "timestamp",
+            DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+            true,
+        ),
+        Field::new("url", DataType::Utf8, false),
+    ]))
+}
+
+/// Writes every filing `query` matches into `dir`, one partition per filing date.
+/// The query's own order, limit and cursor are replaced so the whole match is read.
+pub fn export(
+    conn: &DbConnection,
+    query: FilingQuery,
+    dir: &Path,
+    format: ExportFormat,
+) -> Result<ExportReport> {
+    let query = query.order(FilingOrder::OldestFirst).limit(PAGE_SIZE);
+    let mut report = ExportReport::default();
+    let mut partition: Vec<SECEntry> = Vec::new();
+    let mut cursor = None;
+
+    loop {
+        let mut page_query = query.clone();
+        if let Some(after) = cursor {
+            page_query = page_query.after(after);
+        }
+        let page = page_query.run(conn)?;
+
+        for entry in page.filings {
+            if partition
+                .last()
+                .is_some_and(|last| last.date() != entry.date())
+            {
+                write_partition(dir, &partition, format)?;
+                report.partitions += 1;
+                partition.clear();
+            }
+            report.filings += 1;
+            partition.push(entry);
+        }
+
+        cursor = page.next;
+        if cursor.is_none() {
+            break;
+        }
+    }
+
+    if !partition.is_empty() {
+        write_partition(dir, &partition, format)?;
+        report.partitions += 1;
+    }
+    Ok(report)
+}
+
+/// Writes filings that all share a filing date to that date's file
+fn write_partition(dir: &Path, entries: &[SECEntry], format: ExportFormat) -> Result<PathBuf> {
+    let date = to_date(entries[0].date())?;
+    let partition = dir.join(format!("filing_date={}", date.format("%Y-%m-%d")));
+    fs::create_dir_all(&partition).chain_err(|| format!("Could not create {:#?}", partition))?;
+
+    let path = partition.join(format!("filings.{}", format.extension()));
+    let file = File::create(&path).chain_err(|| format!("{:#?} not found", path))?;
+    let batch = to_batch(entries)?;
+
+    match format {
+        ExportFormat::Parquet => {
+            let properties = WriterProperties::builder()
+                .set_compression(Compression::SNAPPY)
+                .build();
+            let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(properties))
+                .chain_err(|| "Unable to start Parquet file")?;
+            writer.write(&batch).chain_err(|| "Write failed")?;
+            writer.close().chain_err(|| "Write failed")?;
+        }
+        ExportFormat::ArrowIpc => {
+            let mut writer = FileWriter::try_new(file, &batch.schema())
+                .chain_err(|| "Unable to start Arrow file")?;
+            writer.write(&batch).chain_err(|| "Write failed")?;
+            writer.finish().chain_err(|| "Write failed")?;
+        }
+    }
+    Ok(path)
+}
+
+/// Lays `entries` out in the columns of `schema()`
+pub fn to_batch(entries: &[SECEntry]) -> Result<RecordBatch> {
+    let mut form_types = StringDictionaryBuilder::<Int32Type>::new();
+    for entry in entries {
+        form_types.append_value(entry.filing_type().form());
+    }
+
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+    let dates = entries
+        .iter()
+        .map(|entry| to_date(entry.date()).map(|d| (d - epoch).num_days() as i32))
+        .collect::<Result<Vec<i32>>>()?;
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(Int64Array::from_iter_values(
+            entries.iter().map(|e| e.accession_number() as i64),
+        )),
+        Arc::new(form_types.finish()),
+        Arc::new(StringArray::from_iter_values(
+            entries.iter().map(SECEntry::name),
+        )),
+        Arc::new(Int64Array::from_iter_values(
+            entries.iter().map(|e| e.cik() as i64),
+        )),
+        Arc::new(
+            entries
+                .iter()
+                .map(|e| e.role().map(|r| r.as_str()))
+                .collect::<StringArray>(),
+        ),
+        Arc::new(Date32Array::from(dates)),
+        Arc::new(
+            entries
+                .iter()
+                .map(|e| {
+                    DateTime::parse_from_rfc3339(e.timestamp())
+                        .ok()
+                        .map(|t| t.timestamp_millis())
+                })
+                .collect::<TimestampMillisecondArray>()
+                .with_timezone("UTC"),
+        ),
+        Arc::new(StringArray::from_iter_values(
+            entries.iter().map(SECEntry::url),
+        )),
+    ];
+
+    RecordBatch::try_new(schema(), columns).chain_err(|| "Unable to build record batch")
+}
+
+/// A `yyyymmdd` filing date as a calendar date
+fn to_date(date: usize) -> Result<NaiveDate> {
+    NaiveDate::from_ymd_opt(
+        (date / 10000) as i32,
+        (date / 100 % 100) as u32,
+        (date % 100) as u32,
+    )
+    .chain_err(|| format!("{} is not a yyyymmdd date", date))
+}
+
+#[cfg(test)]
+mod export_tests {
+    use super::*;
+    use crate::filings::write_filings;
+    use crate::sec_entry::{FilingRole, FilingType};
+    use crate::test_db::TestDb;
+    use arrow::array::{Array, DictionaryArray};
+    use arrow::ipc::reader::FileReader;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    fn entry(acc: usize, date: usize) -> SECEntry {
+        SECEntry::new(
+            FilingType::Sec10Q,
+            String::from("Name"),
+            320193,
+            acc,
+            date,
+            String::from("2026-10-18T16:05:02-04:00"),
+        )
+        .with_role(Some(FilingRole::Filer))
+    }
+
+    fn scratch_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sec_scraper_{}_{}", std::process::id(), test));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn to_batch_test() {
+        let batch = to_batch(&[entry(1, 20261018), entry(2, 20261018)]).unwrap();
+        assert_eq!(batch.num_rows(), 2);
+
+        let forms = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<DictionaryArray<Int32Type>>()
+            .unwrap();
+        assert_eq!(forms.values().len(), 1);
+
+        let dates = batch
+            .column(5)
+            .as_any()
+            .downcast_ref::<Date32Array>()
+            .unwrap();
+        assert_eq!(
+            dates.value_as_date(0),
+            NaiveDate::from_ymd_opt(2026, 10, 18)
+        );
+
+        let times = batch
+            .column(6)
+            .as_any()
+            .downcast_ref::<TimestampMillisecondArray>()
+            .unwrap();
+        assert_eq!(times.value(0), 1_792_353_902_000);
+    }
+
+    #[test]
+    fn export_test() {
+        let conn = TestDb::new();
+        write_filings(
+            &conn,
+            &[entry(1, 20261017), entry(2, 20261018), entry(3, 20261018)],
+        )
+        .unwrap();
+
+        let dir = scratch_dir("export_parquet");
+        let report = export(&conn, FilingQuery::new(), &dir, ExportFormat::Parquet).unwrap();
+        assert_eq!(
+            report,
+            ExportReport {
+                filings: 3,
+                partitions: 2
+            }
+        );
+
+        let file = File::open(dir.join("filing_date=2026-10-18/filings.parquet")).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap();
+        let rows: usize = reader.map(|batch| batch.unwrap().num_rows()).sum();
+        assert_eq!(rows, 2);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let dir = scratch_dir("export_arrow");
+        export(&conn, FilingQuery::new(), &dir, ExportFormat::ArrowIpc).unwrap();
+        let file = File::open(dir.join("filing_date=2026-10-17/filings.arrow")).unwrap();
+        let mut reader = FileReader::try_new(file, None).unwrap();
+        assert_eq!(reader.schema(), schema());
+        assert_eq!(reader.next().unwrap().unwrap().num_rows(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
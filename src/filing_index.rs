@@ -0,0 +1,486 @@
+//! Reads a filing's index page, which `SECEntry::get_url` points at
+//!
+//! -> `parse_html` reads the `-index.htm` page: the filing's dates, its
+//!    documents and a block for each company involved (filer, issuer, ...)
+//! -> `parse_directory` reads the `index.json` directory listing, which only
+//!    knows file names and sizes
+//!
+//! EDGAR's index pages aren't well formed XML, so they are picked apart with
+//! regular expressions in the same way as the feed's summaries.
+use crate::edgar;
+use crate::errors::*;
+use crate::sec_entry::{FilingRole, SECEntry};
+use chrono::{NaiveDate, NaiveDateTime};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+
+lazy_static! {
+    static ref FORM: Regex =
+        Regex::new(r#"<div id="formName">\s*<strong>Form ([^<]+)</strong>"#).unwrap();
+    static ref INFO: Regex =
+        Regex::new(r#"<div class="infoHead">([^<]*)</div>\s*<div class="info">([^<]*)</div>"#)
+            .unwrap();
+    static ref ITEMS: Regex =
+        Regex::new(r#"(?s)<div class="infoHead">Items</div>\s*<div class="info">(.*?)</div>"#)
+            .unwrap();
+    static ref ITEM_NUMBER: Regex = Regex::new(r"Item (\d+\.\d+)").unwrap();
+    static ref TABLE: Regex =
+        Regex::new(r#"(?s)<table class="tableFile"[^>]*>(.*?)</table>"#).unwrap();
+    static ref FILER: Regex =
+        Regex::new(r#"(?s)<div id="filerDiv">(.*?)<div class="clear">"#).unwrap();
+    static ref ROW: Regex = Regex::new(r"(?s)<tr[^>]*>(.*?)</tr>").unwrap();
+    static ref CELL: Regex = Regex::new(r"(?s)<td[^>]*>(.*?)</td>").unwrap();
+    static ref LINK: Regex = Regex::new(r"<a [^>]*>([^<]*)</a>").unwrap();
+    static ref COMPANY_NAME: Regex = Regex::new(r#"<span class="companyName">([^<]*)"#).unwrap();
+    static ref ROLE: Regex = Regex::new(r"\(([^()]+)\)\s*$").unwrap();
+    static ref CIK: Regex = Regex::new(r"CIK(?:</acronym>)?:\s*<a[^>]*>\s*(\d+)").unwrap();
+    static ref IRS_NUMBER: Regex = strong(r"IRS No\.");
+    static ref STATE_OF_INCORPORATION: Regex = strong(r"State of Incorp\.");
+    static ref FISCAL_YEAR_END: Regex = strong("Fiscal Year End");
+    static ref SIC: Regex = Regex::new(r"SIC:\s*<b>\s*<a[^>]*>(\d+)</a>").unwrap();
+    static ref MAILING_ADDRESS: Regex = mailer("Mailing Address");
+    static ref BUSINESS_ADDRESS: Regex = mailer("Business Address");
+    static ref ADDRESS_LINE: Regex =
+        Regex::new(r#"<span class="mailerAddress">([^<]*)</span>"#).unwrap();
+    static ref TAG: Regex = Regex::new(r"<[^>]*>").unwrap();
+}
+
+/// Matches the bold value after `label` in a company block
+fn strong(label: &str) -> Regex {
+    Regex::new(&format!(
+        r"{}\s*(?:</acronym>)?:\s*<strong>([^<]*)</strong>",
+        label
+    ))
+    .unwrap()
+}
+
+/// Matches the address block under `heading`
+fn mailer(heading: &str) -> Regex {
+    Regex::new(&format!(
+        r#"(?s)<div class="mailer">\s*{}(.*?)</div>"#,
+        heading
+    ))
+    .unwrap()
+}
+
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct FilingIndex {
+    /// e.g. `10-K`
+    pub form_type: Option<String>,
+    pub filing_date: Option<NaiveDate>,
+    pub period_of_report: Option<NaiveDate>,
+    /// When EDGAR accepted the submission, in Eastern time
+    pub accepted: Option<NaiveDateTime>,
+    /// The numbers of the items an 8-K reports, e.g. `2.02`
+    pub items: Vec<String>,
+    pub documents: Vec<IndexDocument>,
+    pub companies: Vec<IndexCompany>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct IndexDocument {
+    /// Position in the submission; data files and the listing's own files have none
+    pub sequence: Option<u32>,
+    pub description: Option<String>,
+    pub filename: String,
+    /// e.g. `10-K`, `EX-21.1` or `XML`
+    pub document_type: Option<String>,
+    /// In bytes
+    pub size: Option<u64>,
+}
+
+/// One of the filer, issuer, reporting owner, ... blocks at the foot of the page
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct IndexCompany {
+    pub name: String,
+    pub cik: usize,
+    pub role: Option<FilingRole>,
+    pub mailing_address: Vec<String>,
+    pub business_address: Vec<String>,
+    pub irs_number: Option<String>,
+    pub state_of_incorporation: Option<String>,
+    /// As `mmdd`
+    pub fiscal_year_end: Option<String>,
+    /// Standard Industrial Classification code
+    pub sic: Option<u32>,
+}
+
+/// Fetches and parses the `-index.htm` page of `entry`
+pub fn fetch(entry: &SECEntry) -> Result<FilingIndex> {
+    let html = fetch_text(entry.url())?;
+    parse_html(&html)
+}
+
+/// Fetches and parses the `index.json` listing of `entry`'s directory
+pub fn fetch_directory(entry: &SECEntry) -> Result<FilingIndex> {
+    let url = SECEntry::get_index_json_url(entry.cik(), entry.accession_number());
+    parse_directory(&fetch_text(&url)?)
+}
+
+fn fetch_text(url: &str) -> Result<String> {
+    let bytes = edgar::get(url)?.chain_err(|| format!("{} not found", url))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+pub fn parse_html(html: &str) -> Result<FilingIndex> {
+    let mut index = FilingIndex {
+        form_type: FORM.captures(html).map(|c| c[1].trim().to_owned()),
+        ..FilingIndex::default()
+    };
+
+    for c in INFO.captures_iter(html) {
+        let value = c[2].trim();
+        match c[1].trim() {
+            "Filing Date" => index.filing_date = Some(parse_date(value)?),
+            "Period of Report" => index.period_of_report = Some(parse_date(value)?),
+            "Accepted" => {
+                index.accepted = Some(
+                    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+                        .chain_err(|| format!("Bad acceptance time {:?}", value))?,
+                )
+            }
+            _ => {}
+        }
+    }
+
+    // The only info block with markup in it, one `Item 2.02: ...` per line
+    if let Some(c) = ITEMS.captures(html) {
+        index.items = ITEM_NUMBER
+            .captures_iter(&c[1])
+            .map(|n| n[1].to_owned())
+            .collect();
+    }
+
+    for t in TABLE.captures_iter(html) {
+        for cells in rows(&t[1]) {
+            index.documents.push(document(&cells)?);
+        }
+    }
+
+    for f in FILER.captures_iter(html) {
+        index.companies.push(company(&f[1])?);
+    }
+
+    Ok(index)
+}
+
+/// The cells of each row of a table that has any, as raw HTML
+fn rows(table: &str) -> Vec<Vec<String>> {
+    ROW.captures_iter(table)
+        .map(|r| {
+            CELL.captures_iter(&r[1])
+                .map(|c| c[1].to_owned())
+                .collect::<Vec<String>>()
+        })
+        .filter(|cells| !cells.is_empty())
+        .collect()
+}
+
+fn document(cells: &[String]) -> Result<IndexDocument> {
+    if cells.len() < 5 {
+        bail!("Document row has {} cells, expected 5", cells.len());
+    }
+    let filename = match LINK.captures(&cells[2]) {
+        Some(c) => text(&c[1]),
+        None => text(&cells[2]),
+    };
+
+    Ok(IndexDocument {
+        sequence: non_empty(text(&cells[0])).and_then(|s| s.parse::<u32>().ok()),
+        description: non_empty(text(&cells[1])),
+        filename,
+        document_type: non_empty(text(&cells[3])),
+        size: non_empty(text(&cells[4])).and_then(|s| s.parse::<u64>().ok()),
+    })
+}
+
+fn company(block: &str) -> Result<IndexCompany> {
+    let heading = COMPANY_NAME
+        .captures(block)
+        .map(|c| text(&c[1]))
+        .chain_err(|| "No company name in filer block")?;
+    let (name, role) = match ROLE.captures(&heading) {
+        Some(c) => (
+            heading[..c.get(0).unwrap().start()].trim().to_owned(),
+            FilingRole::which(c[1].trim()).ok(),
+        ),
+        None => (heading.clone(), None),
+    };
+    let cik = CIK
+        .captures(block)
+        .and_then(|c| c[1].parse::<usize>().ok())
+        .chain_err(|| format!("No CIK for {}", name))?;
+
+    let strong = |re: &Regex| re.captures(block).and_then(|c| non_empty(text(&c[1])));
+
+    Ok(IndexCompany {
+        name,
+        cik,
+        role,
+        mailing_address: address(block, &MAILING_ADDRESS),
+        business_address: address(block, &BUSINESS_ADDRESS),
+        irs_number: strong(&IRS_NUMBER),
+        state_of_incorporation: strong(&STATE_OF_INCORPORATION),
+        fiscal_year_end: strong(&FISCAL_YEAR_END),
+        sic: SIC.captures(block).and_then(|c| c[1].parse::<u32>().ok()),
+    })
+}
+
+/// The lines of the address block `mailer` finds
+fn address(block: &str, mailer: &Regex) -> Vec<String> {
+    match mailer.captures(block) {
+        Some(m) => ADDRESS_LINE
+            .captures_iter(&m[1])
+            .filter_map(|l| non_empty(text(&l[1])))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+#[derive(Deserialize)]
+struct Listing {
+    directory: Directory,
+}
+
+#[derive(Deserialize)]
+struct Directory {
+    item: Vec<DirectoryItem>,
+}
+
+#[derive(Deserialize)]
+struct DirectoryItem {
+    name: String,
+    size: String,
+}
+
+/// Parses an `index.json` directory listing. Only file names and sizes are known.
+pub fn parse_directory(json: &str) -> Result<FilingIndex> {
+    let listing: Listing = serde_json::from_str(json).chain_err(|| "Not a directory listing")?;
+    let documents = listing
+        .directory
+        .item
+        .into_iter()
+        .map(|item| IndexDocument {
+            sequence: None,
+            description: None,
+            filename: item.name,
+            document_type: None,
+            size: item.size.parse::<u64>().ok(),
+        })
+        .collect();
+
+    Ok(FilingIndex {
+        documents,
+        ..FilingIndex::default()
+    })
+}
+
+/// Cell contents without markup, entities or surrounding space
+fn text(html: &str) -> String {
+    TAG.replace_all(html, "")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+fn non_empty(s: String) -> Option<String> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(s)
+    }
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").chain_err(|| format!("Bad date {:?}", date))
+}
+
+#[cfg(test)]
+mod filing_index_tests {
+    use super::*;
+
+    const HTML: &str = r#"<div id="PageTitle">Filing Detail</div>
+<div id="formDiv">
+<div id="formHeader">
+<div id="formName">
+<strong>Form 4</strong> - Statement of changes in beneficial ownership of securities:
+</div>
+<div id="secNum">
+<strong><acronym title="Securities and Exchange Commission">SEC</acronym> Accession <acronym title="Number">No.</acronym></strong> 0001140361-18-030802
+</div>
+</div>
+<div class="formContent">
+<div class="formGrouping">
+<div class="infoHead">Filing Date</div>
+<div class="info">2018-06-29</div>
+<div class="infoHead">Accepted</div>
+<div class="info">2018-06-29 17:27:37</div>
+<div class="infoHead">Documents</div>
+<div class="info">1</div>
+</div>
+<div class="formGrouping">
+<div class="infoHead">Period of Report</div>
+<div class="info">2018-06-27</div>
+</div>
+</div>
+<div style="padding: 4px 0px 4px 0px; font-size: 12px; margin: 0px 2px 0px 5px; width: 100%; overflow:hidden">
+<p>Document Format Files</p>
+<table class="tableFile" summary="Document Format Files">
+         <tr>
+            <th scope="col" style="width: 5%;"><acronym title="Sequence Number">Seq</acronym></th>
+            <th scope="col" style="width: 40%;">Description</th>
+            <th scope="col" style="width: 20%;">Document</th>
+            <th scope="col" style="width: 10%;">Type</th>
+            <th scope="col">Size</th>
+         </tr>
+         <tr>
+            <td scope="row">1</td>
+            <td scope="row">FORM 4</td>
+            <td scope="row"><a href="/Archives/edgar/data/1655081/000114036118030802/xslF345X03/form4.xml">form4.html</a></td>
+            <td scope="row">4</td>
+            <td scope="row">&nbsp;</td>
+         </tr>
+         <tr class="blueRow">
+            <td scope="row">1</td>
+            <td scope="row">FORM 4</td>
+            <td scope="row"><a href="/Archives/edgar/data/1655081/000114036118030802/form4.xml">form4.xml</a></td>
+            <td scope="row">4</td>
+            <td scope="row">4517</td>
+         </tr>
+         <tr>
+            <td scope="row">&nbsp;</td>
+            <td scope="row"><b>Complete submission text file</b></td>
+            <td scope="row"><a href="/Archives/edgar/data/1655081/000114036118030802/0001140361-18-030802.txt">0001140361-18-030802.txt</a></td>
+            <td scope="row">&nbsp;</td>
+            <td scope="row">6148</td>
+         </tr>
+      </table>
+</div>
+</div>
+<div id="filerDiv">
+<div class="mailer">Mailing Address
+<span class="mailerAddress">C/O OKTA, INC.</span>
+<span class="mailerAddress">100 FIRST STREET, 6TH FLOOR</span>
+<span class="mailerAddress">
+SAN FRANCISCO CA 94105         </span>
+</div>
+<div class="companyInfo">
+<span class="companyName">Wang Janet (Reporting)
+ <acronym title="Central Index Key">CIK</acronym>: <a href="/cgi-bin/browse-edgar?action=getcompany&amp;CIK=0001655081&amp;owner=include&amp;count=40">0001655081 (see all company filings)</a></span>
+<p class="identInfo">Type: <strong>4</strong> | Act: <strong>34</strong> | File No.: <a href="/cgi-bin/browse-edgar?action=getcompany&amp;filenum=001-38044&amp;owner=include&amp;count=40">001-38044</a> | Film No.: <strong>18930022</strong></p>
+</div>
+<div class="clear"></div>
+</div>
+<div id="filerDiv">
+<div class="mailer">Business Address
+<span class="mailerAddress">100 FIRST STREET</span>
+<span class="mailerAddress">SAN FRANCISCO CA 94105</span>
+<span class="mailerAddress">888-722-7871</span>
+</div>
+<div class="companyInfo">
+<span class="companyName">Okta, Inc. (Issuer)
+ <acronym title="Central Index Key">CIK</acronym>: <a href="/cgi-bin/browse-edgar?action=getcompany&amp;CIK=0001660134&amp;owner=include&amp;count=40">0001660134 (see all company filings)</a></span>
+<p class="identInfo"><acronym title="Internal Revenue Service Number">IRS No.</acronym>: <strong>261592408</strong> | State of Incorp.: <strong>DE</strong> | Fiscal Year End: <strong>0131</strong><br />SIC: <b><a href="/cgi-bin/browse-edgar?action=getcompany&amp;SIC=7372&amp;owner=include">7372</a></b> Services-Prepackaged Software</p>
+</div>
+<div class="clear"></div>
+</div>
+"#;
+
+    const JSON: &str = r#"{"directory":{"item":[{"last-modified":"2018-06-29 17:27:37","name":"0001140361-18-030802.txt","type":"text.gif","size":"6148"},{"last-modified":"2018-06-29 17:27:37","name":"0001140361-18-030802-index-headers.html","type":"text.gif","size":""}],"name":"/Archives/edgar/data/1655081/000114036118030802","parent-dir":"/Archives/edgar/data/1655081/"}}"#;
+
+    #[test]
+    fn parse_html_test() {
+        let index = parse_html(HTML).unwrap();
+        assert_eq!(index.form_type.as_ref().unwrap(), "4");
+        assert_eq!(index.filing_date, NaiveDate::from_ymd_opt(2018, 6, 29));
+        assert_eq!(index.period_of_report, NaiveDate::from_ymd_opt(2018, 6, 27));
+        assert_eq!(
+            index.accepted,
+            NaiveDate::from_ymd_opt(2018, 6, 29).and_then(|d| d.and_hms_opt(17, 27, 37))
+        );
+
+        assert_eq!(index.documents.len(), 3);
+        assert_eq!(
+            index.documents[1],
+            IndexDocument {
+                sequence: Some(1),
+                description: Some(String::from("FORM 4")),
+                filename: String::from("form4.xml"),
+                document_type: Some(String::from("4")),
+                size: Some(4517),
+            }
+        );
+        assert_eq!(index.documents[2].sequence, None);
+        assert_eq!(index.documents[2].document_type, None);
+        assert_eq!(
+            index.documents[2].description.as_ref().unwrap(),
+            "Complete submission text file"
+        );
+    }
+
+    #[test]
+    fn parse_companies_test() {
+        let index = parse_html(HTML).unwrap();
+        assert_eq!(index.companies.len(), 2);
+
+        let owner = &index.companies[0];
+        assert_eq!(owner.name, "Wang Janet");
+        assert_eq!(owner.cik, 1655081);
+        assert_eq!(owner.role, Some(FilingRole::Reporting));
+        assert_eq!(
+            owner.mailing_address,
+            vec![
+                "C/O OKTA, INC.",
+                "100 FIRST STREET, 6TH FLOOR",
+                "SAN FRANCISCO CA 94105"
+            ]
+        );
+        assert_eq!(owner.sic, None);
+
+        assert_eq!(
+            index.companies[1],
+            IndexCompany {
+                name: String::from("Okta, Inc."),
+                cik: 1660134,
+                role: Some(FilingRole::Issuer),
+                mailing_address: vec![],
+                business_address: vec![
+                    String::from("100 FIRST STREET"),
+                    String::from("SAN FRANCISCO CA 94105"),
+                    String::from("888-722-7871"),
+                ],
+                irs_number: Some(String::from("261592408")),
+                state_of_incorporation: Some(String::from("DE")),
+                fiscal_year_end: Some(String::from("0131")),
+                sic: Some(7372),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_items_test() {
+        let html = r#"<div class="formGrouping">
+<div class="infoHead">Items</div>
+<div class="info">Item 2.02: Results of Operations and Financial Condition<br>Item 9.01: Financial Statements and Exhibits</div>
+</div>"#;
+        assert_eq!(parse_html(html).unwrap().items, vec!["2.02", "9.01"]);
+        assert!(parse_html(HTML).unwrap().items.is_empty());
+    }
+
+    #[test]
+    fn parse_directory_test() {
+        let index = parse_directory(JSON).unwrap();
+        assert_eq!(index.documents.len(), 2);
+        assert_eq!(index.documents[0].filename, "0001140361-18-030802.txt");
+        assert_eq!(index.documents[0].size, Some(6148));
+        assert_eq!(index.documents[1].size, None);
+        assert!(parse_directory("{}").is_err());
+    }
+}
@@ -0,0 +1,656 @@
+//! Stores the filings the scraper emits, and reads them back out
+//!
+//! `FilingQuery` narrows the stored filings down by CIK, form, filing date, name,
+//! role and 8-K item, and pages through them in date order. Each page comes with a cursor for the
+//! next one, so results stay stable while new filings are being written.
+//!
+//! A filing is stored once, under the first party the feed lists it for, but every
+//! party and its role goes into `filing_parties`, so looking one up by CIK or role
+//! finds it whichever party that is.
+use crate::db::{self, on_backend, DbConnection};
+use crate::errors::*;
+use crate::models::{Filing, NewFiling, NewFilingItem, NewFilingParty};
+use crate::schema::{filing_items, filing_parties, filings, filings_archive};
+use crate::sec_entry::{FilingRole, FilingType, SECEntry};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+#[cfg(feature = "sqlite")]
+use diesel::sqlite::Sqlite;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+sql_function!(fn lower(x: diesel::sql_types::Text) -> diesel::sql_types::Text);
+
+const DEFAULT_LIMIT: usize = 100;
+/// Longer pages are cut down to this
+pub const MAX_LIMIT: usize = 10_000;
+
+/// Records `entries` in the filings table, skipping any already there.
+pub fn write_filings(conn: &DbConnection, entries: &[SECEntry]) -> Result<usize> {
+    let count = db::write_filings(conn, &new_filings(entries))?;
+    write_parties(conn, entries)?;
+    write_items(conn, entries)?;
+    Ok(count)
+}
+
+/// Records the party each of `entries` lists, including those of filings already
+/// written under a different party
+pub fn write_parties(conn: &DbConnection, entries: &[SECEntry]) -> Result<usize> {
+    db::write_filing_parties(conn, &new_filing_parties(entries))
+}
+
+/// Records the items of the 8-Ks among `entries`, for filings already written
+/// before their items were looked up
+pub fn write_items(conn: &DbConnection, entries: &[SECEntry]) -> Result<usize> {
+    db::write_filing_items(conn, &new_filing_items(entries))
+}
+
+/// Those of `accession_numbers` stored in either `filings` or `filings_archive`
+pub fn stored(conn: &DbConnection, accession_numbers: &[usize]) -> Result<HashSet<usize>> {
+    let accession_numbers = accession_numbers
+        .iter()
+        .map(|&acc| acc as i64)
+        .collect::<Vec<i64>>();
+    let (current, archived) = on_backend!(conn, c => {
+        let current = filings::table
+            .filter(filings::accession_number.eq_any(&accession_numbers))
+            .select(filings::accession_number)
+            .load::<i64>(c)?;
+        let archived = filings_archive::table
+            .filter(filings_archive::accession_number.eq_any(&accession_numbers))
+            .select(filings_archive::accession_number)
+            .load::<i64>(c)?;
+        Ok::<_, diesel::result::Error>((current, archived))
+    })
+    .chain_err(|| "Unable to look up stored filings")?;
+
+    Ok(current
+        .into_iter()
+        .chain(archived)
+        .map(|acc| acc as usize)
+        .collect())
+}
+
+/// The rows `entries` become in the filings table
+pub fn new_filings(entries: &[SECEntry]) -> Vec<NewFiling<'_>> {
+    entries
+        .iter()
+        .map(|entry| NewFiling {
+            accession_number: entry.accession_number() as i64,
+            form_type: entry.filing_type().form(),
+            name: entry.name(),
+            cik: entry.cik() as i64,
+            role: entry.role().map(FilingRole::as_str),
+            filing_date: entry.date() as i32,
+            timestamp: entry.timestamp(),
+        })
+        .collect::<Vec<NewFiling>>()
+}
+
+/// The rows the parties of `entries` become in the filing_parties table
+pub fn new_filing_parties(entries: &[SECEntry]) -> Vec<NewFilingParty<'_>> {
+    entries
+        .iter()
+        .map(|entry| NewFilingParty {
+            accession_number: entry.accession_number() as i64,
+            cik: entry.cik() as i64,
+            name: entry.name(),
+            role: entry.role().map(FilingRole::as_str),
+        })
+        .collect::<Vec<NewFilingParty>>()
+}
+
+/// The rows the items of `entries` become in the filing_items table
+pub fn new_filing_items(entries: &[SECEntry]) -> Vec<NewFilingItem<'_>> {
+    entries
+        .iter()
+        .flat_map(|entry| {
+            entry.items().iter().map(move |item| NewFilingItem {
+                accession_number: entry.accession_number() as i64,
+                item,
+            })
+        })
+        .collect::<Vec<NewFilingItem>>()
+}
+
+/// The items stored for each of `accession_numbers`, in order
+fn load_items(conn: &DbConnection, accession_numbers: &[i64]) -> Result<HashMap<i64, Vec<String>>> {
+    let rows = on_backend!(conn, c => {
+        filing_items::table
+            .filter(filing_items::accession_number.eq_any(accession_numbers))
+            .order((filing_items::accession_number, filing_items::item))
+            .load::<(i64, String)>(c)
+    })
+    .chain_err(|| "Unable to load filing items")?;
+
+    let mut items = HashMap::new();
+    for (acc, item) in rows {
+        items.entry(acc).or_insert_with(Vec::new).push(item);
+    }
+    Ok(items)
+}
+
+/// The stored filings among `accession_numbers`, oldest first, with their items.
+/// Any that aren't in `filings`, say because they have been archived, are left out.
+pub fn load(conn: &DbConnection, accession_numbers: &[usize]) -> Result<Vec<SECEntry>> {
+    let accession_numbers = accession_numbers
+        .iter()
+        .map(|&acc| acc as i64)
+        .collect::<Vec<i64>>();
+    let rows = on_backend!(conn, c => {
+        filings::table
+            .filter(filings::accession_number.eq_any(&accession_numbers))
+            .order((filings::filing_date, filings::accession_number))
+            .load::<Filing>(c)
+    })
+    .chain_err(|| "Unable to load filings")?;
+    to_entries(conn, rows)
+}
+
+/// Turns stored rows back into entries, along with their items
+fn to_entries(conn: &DbConnection, rows: Vec<Filing>) -> Result<Vec<SECEntry>> {
+    let accession_numbers = rows
+        .iter()
+        .map(|row| row.accession_number)
+        .collect::<Vec<i64>>();
+    let mut items = load_items(conn, &accession_numbers)?;
+    rows.into_iter()
+        .map(|row| {
+            let items = items.remove(&row.accession_number).unwrap_or_default();
+            to_entry(row).map(|entry| entry.with_items(items))
+        })
+        .collect()
+}
+
+fn to_entry(filing: Filing) -> Result<SECEntry> {
+    let filing_type = FilingType::which(&filing.form_type)
+        .chain_err(|| format!("Unknown filing type {} stored", filing.form_type))?;
+    let role = filing.role.and_then(|r| FilingRole::which(&r).ok());
+
+    Ok(SECEntry::new(
+        filing_type,
+        filing.name,
+        filing.cik as usize,
+        filing.accession_number as usize,
+        filing.filing_date as usize,
+        filing.timestamp,
+    )
+    .with_role(role))
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FilingOrder {
+    NewestFirst,
+    OldestFirst,
+}
+
+/// Where a page of results left off, written as `<date>-<accession number>`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct FilingCursor {
+    date: usize,
+    accession_number: usize,
+}
+
+impl FilingCursor {
+    fn of(entry: &SECEntry) -> FilingCursor {
+        FilingCursor {
+            date: entry.date(),
+            accession_number: entry.accession_number(),
+        }
+    }
+}
+
+impl fmt::Display for FilingCursor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.date, self.accession_number)
+    }
+}
+
+impl FromStr for FilingCursor {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<FilingCursor> {
+        let mut parts = s.splitn(2, '-');
+        match (parts.next(), parts.next()) {
+            (Some(date), Some(acc)) => Ok(FilingCursor {
+                date: date.parse().chain_err(|| "Cursor date is not a number")?,
+                accession_number: acc
+                    .parse()
+                    .chain_err(|| "Cursor accession number is not a number")?,
+            }),
+            _ => bail!("Cursor should look like <date>-<accession number>"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct FilingPage {
+    pub filings: Vec<SECEntry>,
+    /// Pass to `FilingQuery::after` for the following page, `None` on the last one
+    pub next: Option<FilingCursor>,
+}
+
+/// A filter over stored filings. Every condition that is set must hold.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FilingQuery {
+    cik: Option<usize>,
+    form_type: Option<FilingType>,
+    form_family: Option<FilingType>,
+    filed_from: Option<usize>,
+    filed_to: Option<usize>,
+    name_contains: Option<String>,
+    role: Option<FilingRole>,
+    items: Vec<String>,
+    order: FilingOrder,
+    limit: usize,
+    after: Option<FilingCursor>,
+}
+
+impl Default for FilingQuery {
+    fn default() -> FilingQuery {
+        FilingQuery {
+            cik: None,
+            form_type: None,
+            form_family: None,
+            filed_from: None,
+            filed_to: None,
+            name_contains: None,
+            role: None,
+            items: Vec::new(),
+            order: FilingOrder::NewestFirst,
+            limit: DEFAULT_LIMIT,
+            after: None,
+        }
+    }
+}
+
+/// Applies a `FilingQuery` to a boxed query on either backend and loads one page.
+/// Boxed queries are tied to their backend, hence a macro rather than a function.
+macro_rules! load_page {
+    ($query:expr, $q:expr, $conn:expr) => {{
+        use crate::schema::filings::dsl::*;
+
+        let q = $q;
+        let mut query = $query;
+        // Both have to hold for the same party
+        match (q.cik, q.role) {
+            (Some(c), Some(r)) => {
+                let parties = filing_parties::table
+                    .filter(filing_parties::cik.eq(c as i64))
+                    .filter(filing_parties::role.eq(r.as_str()))
+                    .select(filing_parties::accession_number);
+                query = query.filter(accession_number.eq_any(parties));
+            }
+            (Some(c), None) => {
+                let parties = filing_parties::table
+                    .filter(filing_parties::cik.eq(c as i64))
+                    .select(filing_parties::accession_number);
+                query = query.filter(accession_number.eq_any(parties));
+            }
+            (None, Some(r)) => {
+                let parties = filing_parties::table
+                    .filter(filing_parties::role.eq(r.as_str()))
+                    .select(filing_parties::accession_number);
+                query = query.filter(accession_number.eq_any(parties));
+            }
+            (None, None) => {}
+        }
+        if let Some(f) = q.form_type {
+            query = query.filter(form_type.eq(f.form()));
+        }
+        if let Some(f) = q.form_family {
+            let family = f.family();
+            query = query.filter(
+                form_type
+                    .eq(family)
+                    .or(form_type.eq(format!("{}/A", family))),
+            );
+        }
+        if let Some(d) = q.filed_from {
+            query = query.filter(filing_date.ge(d as i32));
+        }
+        if let Some(d) = q.filed_to {
+            query = query.filter(filing_date.le(d as i32));
+        }
+        if let Some(ref n) = q.name_contains {
+            let named = filing_parties::table
+                .filter(
+                    lower(filing_parties::name)
+                        .like(like_pattern(n))
+                        .escape('\\'),
+                )
+                .select(filing_parties::accession_number);
+            query = query.filter(accession_number.eq_any(named));
+        }
+        if !q.items.is_empty() {
+            let reporting = filing_items::table
+                .filter(filing_items::item.eq_any(&q.items))
+                .select(filing_items::accession_number);
+            query = query.filter(accession_number.eq_any(reporting));
+        }
+
+        match q.order {
+            FilingOrder::NewestFirst => {
+                if let Some(c) = q.after {
+                    let (d, a) = (c.date as i32, c.accession_number as i64);
+                    query = query.filter(
+                        filing_date
+                            .lt(d)
+                            .or(filing_date.eq(d).and(accession_number.lt(a))),
+                    );
+                }
+                query = query.order((filing_date.desc(), accession_number.desc()));
+            }
+            FilingOrder::OldestFirst => {
+                if let Some(c) = q.after {
+                    let (d, a) = (c.date as i32, c.accession_number as i64);
+                    query = query.filter(
+                        filing_date
+                            .gt(d)
+                            .or(filing_date.eq(d).and(accession_number.gt(a))),
+                    );
+                }
+                query = query.order((filing_date.asc(), accession_number.asc()));
+            }
+        }
+
+        // One extra row tells us whether there is another page
+        query.limit(q.limit as i64 + 1).load::<Filing>($conn)
+    }};
+}
+
+/// Lowercases `name` and escapes LIKE wildcards so it only matches literally
+fn like_pattern(name: &str) -> String {
+    let mut pattern = String::from("%");
+    for c in name.to_lowercase().chars() {
+        if c == '%' || c == '_' || c == '\\' {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+impl FilingQuery {
+    pub fn new() -> FilingQuery {
+        FilingQuery::default()
+    }
+
+    pub fn cik(mut self, cik: usize) -> FilingQuery {
+        self.cik = Some(cik);
+        self
+    }
+
+    /// Only this exact form, e.g. `8-K` but not `8-K/A`
+    pub fn form_type(mut self, form_type: FilingType) -> FilingQuery {
+        self.form_type = Some(form_type);
+        self
+    }
+
+    /// The form along with its amendments, e.g. both `8-K` and `8-K/A`
+    pub fn form_family(mut self, form_type: FilingType) -> FilingQuery {
+        self.form_family = Some(form_type);
+        self
+    }
+
+    /// Filed on or between the two dates, given as `yyyymmdd` like `SECEntry`'s
+    pub fn filed_between(mut self, from: Option<usize>, to: Option<usize>) -> FilingQuery {
+        self.filed_from = from;
+        self.filed_to = to;
+        self
+    }
+
+    /// Filings with a party whose conformed name contains `name`, ignoring case
+    pub fn name_contains(mut self, name: &str) -> FilingQuery {
+        self.name_contains = Some(name.to_owned());
+        self
+    }
+
+    pub fn role(mut self, role: FilingRole) -> FilingQuery {
+        self.role = Some(role);
+        self
+    }
+
+    /// 8-Ks reporting `item`, e.g. `2.02`. Given more than once, 8-Ks reporting
+    /// any of them.
+    pub fn item(mut self, item: &str) -> FilingQuery {
+        self.items.push(item.to_owned());
+        self
+    }
+
+    pub fn order(mut self, order: FilingOrder) -> FilingQuery {
+        self.order = order;
+        self
+    }
+
+    /// Filings per page, at most `MAX_LIMIT`. Must be at least one.
+    pub fn limit(mut self, limit: usize) -> FilingQuery {
+        self.limit = limit.min(MAX_LIMIT);
+        self
+    }
+
+    /// Continues from where a previous page left off
+    pub fn after(mut self, cursor: FilingCursor) -> FilingQuery {
+        self.after = Some(cursor);
+        self
+    }
+
+    pub fn run(&self, conn: &DbConnection) -> Result<FilingPage> {
+        if self.limit == 0 {
+            bail!("A page has to hold at least one filing");
+        }
+        let table = filings::table;
+        let rows = match conn {
+            DbConnection::Postgres(c) => load_page!(table.into_boxed::<Pg>(), self, c),
+            #[cfg(feature = "sqlite")]
+            DbConnection::Sqlite(c) => {
+                load_page!(table.into_boxed::<Sqlite>(), self, c)
+            }
+        }
+        .chain_err(|| "Unable to load filings")?;
+
+        let mut filings = to_entries(conn, rows)?;
+        let next = if filings.len() > self.limit {
+            filings.truncate(self.limit);
+            filings.last().map(FilingCursor::of)
+        } else {
+            None
+        };
+
+        Ok(FilingPage { filings, next })
+    }
+}
+
+#[cfg(test)]
+mod filings_tests {
+    use super::*;
+    use crate::test_db::TestDb;
+
+    fn entry(
+        filing_type: FilingType,
+        name: &str,
+        cik: usize,
+        acc: usize,
+        date: usize,
+        role: FilingRole,
+    ) -> SECEntry {
+        SECEntry::new(
+            filing_type,
+            String::from(name),
+            cik,
+            acc,
+            date,
+            String::from("2018-09-05T12:36:45-04:00"),
+        )
+        .with_role(Some(role))
+    }
+
+    fn sample() -> Vec<SECEntry> {
+        vec![
+            entry(
+                FilingType::Sec8K,
+                "APPLE INC",
+                320193,
+                1,
+                20180901,
+                FilingRole::Filer,
+            )
+            .with_items(vec![String::from("1.01"), String::from("2.02")]),
+            entry(
+                FilingType::Sec8KA,
+                "APPLE INC",
+                320193,
+                2,
+                20180902,
+                FilingRole::Filer,
+            )
+            .with_items(vec![String::from("2.02"), String::from("7.01")]),
+            entry(
+                FilingType::Sec4,
+                "Wang Janet",
+                1655081,
+                3,
+                20180903,
+                FilingRole::Reporting,
+            ),
+            entry(
+                FilingType::Sec4,
+                "MARTIN MARIETTA",
+                916076,
+                4,
+                20180903,
+                FilingRole::Issuer,
+            ),
+            entry(
+                FilingType::SecFWP,
+                "WELLS 100%_FARGO",
+                72971,
+                5,
+                20180905,
+                FilingRole::Subject,
+            ),
+        ]
+    }
+
+    fn accession_numbers(page: &FilingPage) -> Vec<usize> {
+        page.filings
+            .iter()
+            .map(SECEntry::accession_number)
+            .collect::<Vec<usize>>()
+    }
+
+    fn query_test(conn: &DbConnection) {
+        assert_eq!(write_filings(conn, &sample()).unwrap(), 5);
+        assert_eq!(write_filings(conn, &sample()[..1]).unwrap(), 0);
+
+        let all = FilingQuery::new().run(conn).unwrap();
+        assert_eq!(accession_numbers(&all), vec![5, 4, 3, 2, 1]);
+        assert_eq!(all.filings[2], sample()[2]);
+        assert_eq!(all.filings[4], sample()[0]);
+        assert_eq!(all.next, None);
+
+        let q = |query: FilingQuery| accession_numbers(&query.run(conn).unwrap());
+        assert_eq!(q(FilingQuery::new().cik(320193)), vec![2, 1]);
+        assert_eq!(q(FilingQuery::new().form_type(FilingType::Sec8K)), vec![1]);
+        assert_eq!(
+            q(FilingQuery::new().form_family(FilingType::Sec8KA)),
+            vec![2, 1]
+        );
+        assert_eq!(
+            q(FilingQuery::new().filed_between(Some(20180902), Some(20180903))),
+            vec![4, 3, 2]
+        );
+        assert_eq!(q(FilingQuery::new().name_contains("apple")), vec![2, 1]);
+        assert_eq!(q(FilingQuery::new().name_contains("100%_")), vec![5]);
+        assert_eq!(q(FilingQuery::new().name_contains("%")), vec![5]);
+        assert_eq!(q(FilingQuery::new().role(FilingRole::Issuer)), vec![4]);
+
+        // Later parties of a filing already stored under its first
+        let issuer = entry(
+            FilingType::Sec4,
+            "APPLE INC",
+            320193,
+            3,
+            20180903,
+            FilingRole::Issuer,
+        );
+        assert_eq!(write_filings(conn, &[issuer]).unwrap(), 0);
+        assert_eq!(q(FilingQuery::new().cik(320193)), vec![3, 2, 1]);
+        assert_eq!(q(FilingQuery::new().name_contains("apple")), vec![3, 2, 1]);
+        assert_eq!(q(FilingQuery::new().role(FilingRole::Issuer)), vec![4, 3]);
+        assert_eq!(
+            q(FilingQuery::new().cik(320193).role(FilingRole::Issuer)),
+            vec![3]
+        );
+        assert_eq!(
+            q(FilingQuery::new().cik(1655081).role(FilingRole::Issuer)),
+            Vec::<usize>::new()
+        );
+        let stored = FilingQuery::new().cik(320193).run(conn).unwrap();
+        assert_eq!(stored.filings[0], sample()[2]);
+        assert_eq!(q(FilingQuery::new().item("2.02")), vec![2, 1]);
+        assert_eq!(q(FilingQuery::new().item("1.01")), vec![1]);
+        assert_eq!(q(FilingQuery::new().item("1.01").item("7.01")), vec![2, 1]);
+        assert_eq!(q(FilingQuery::new().item("5.02")), Vec::<usize>::new());
+        assert_eq!(
+            q(FilingQuery::new().order(FilingOrder::OldestFirst)),
+            vec![1, 2, 3, 4, 5]
+        );
+    }
+
+    fn pagination_test(conn: &DbConnection) {
+        write_filings(conn, &sample()).unwrap();
+
+        let query = FilingQuery::new().order(FilingOrder::OldestFirst).limit(2);
+        let mut pages = Vec::new();
+        let mut page = query.run(conn).unwrap();
+        loop {
+            pages.push(accession_numbers(&page));
+            match page.next {
+                Some(cursor) => page = query.clone().after(cursor).run(conn).unwrap(),
+                None => break,
+            }
+        }
+        assert_eq!(pages, vec![vec![1, 2], vec![3, 4], vec![5]]);
+
+        assert!(FilingQuery::new().limit(0).run(conn).is_err());
+        let everything = FilingQuery::new().limit(usize::MAX);
+        assert_eq!(everything.limit, MAX_LIMIT);
+        assert_eq!(everything.run(conn).unwrap().filings.len(), 5);
+    }
+
+    #[test]
+    fn postgres_query_test() {
+        query_test(&TestDb::new());
+    }
+
+    #[test]
+    fn postgres_pagination_test() {
+        pagination_test(&TestDb::new());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_query_test() {
+        query_test(&TestDb::sqlite());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_pagination_test() {
+        pagination_test(&TestDb::sqlite());
+    }
+
+    #[test]
+    fn cursor_test() {
+        let cursor = FilingCursor {
+            date: 20180705,
+            accession_number: 145438718000188,
+        };
+        assert_eq!(cursor.to_string(), "20180705-145438718000188");
+        assert_eq!(cursor.to_string().parse::<FilingCursor>().unwrap(), cursor);
+        assert!("20180705".parse::<FilingCursor>().is_err());
+    }
+}
@@ -0,0 +1,447 @@
+//! Reads Form D, the notice a company files after selling securities in a
+//! private offering
+//!
+//! -> `parse` reads `primary_doc.xml` into a `FormD`: the issuer, the people
+//!    involved, the industry, how much is being raised and how much has been
+//!    sold, the exemptions relied on and who is paid to sell it
+//! -> `from_submission` and `fetch` find it among a filing's documents
+//!
+//! Amendments (`D/A`) restate the whole form, so the latest one for an offering
+//! has the current amounts.
+use crate::errors::*;
+use crate::sec_entry::SECEntry;
+use crate::submission::{self, Submission};
+use crate::values::{self, whole_number};
+use crate::xml_tree::{self, Element};
+use chrono::NaiveDate;
+use std::io::BufRead;
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct FormD {
+    /// `D` or `D/A`
+    pub submission_type: String,
+    pub issuer: FormDIssuer,
+    pub related_persons: Vec<RelatedPerson>,
+    /// e.g. `Pooled Investment Fund`, `Biotechnology` or `Other Technology`
+    pub industry_group: Option<String>,
+    /// For pooled investment funds, e.g. `Hedge Fund` or `Private Equity Fund`
+    pub investment_fund_type: Option<String>,
+    /// e.g. `$1 - $1,000,000` or `Decline to Disclose`
+    pub revenue_range: Option<String>,
+    /// Rules and exemptions relied on, e.g. `06b` for Rule 506(b) or `3C.7`
+    pub exemptions: Vec<String>,
+    pub is_amendment: bool,
+    /// `None` if the first sale is yet to happen
+    pub date_of_first_sale: Option<NaiveDate>,
+    pub minimum_investment: Option<u64>,
+    pub total_offering_amount: Option<Amount>,
+    pub total_amount_sold: Option<u64>,
+    pub total_remaining: Option<Amount>,
+    pub investors: Option<u32>,
+    pub has_non_accredited_investors: bool,
+    pub sales_commissions: Option<u64>,
+    pub finders_fees: Option<u64>,
+    pub sales_compensation: Vec<CompensationRecipient>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Amount {
+    Dollars(u64),
+    /// Funds that keep raising without a target say so instead of an amount
+    Indefinite,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct FormDIssuer {
+    pub cik: usize,
+    pub name: String,
+    pub address: Vec<String>,
+    pub phone: Option<String>,
+    pub jurisdiction: Option<String>,
+    /// e.g. `Corporation` or `Limited Partnership`
+    pub entity_type: Option<String>,
+    pub year_of_incorporation: Option<u32>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct RelatedPerson {
+    /// First, middle and last names, as given
+    pub name: String,
+    pub address: Vec<String>,
+    /// `Executive Officer`, `Director` and/or `Promoter`
+    pub relationships: Vec<String>,
+    pub clarification: Option<String>,
+}
+
+/// A broker or finder paid for selling the offering
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct CompensationRecipient {
+    pub name: String,
+    pub crd_number: Option<String>,
+    pub broker_dealer: Option<String>,
+    pub broker_dealer_crd_number: Option<String>,
+    pub address: Vec<String>,
+    /// Two letter codes of the states solicited in
+    pub states: Vec<String>,
+    pub all_states: bool,
+}
+
+pub fn parse(xml: &str) -> Result<FormD> {
+    let root = xml_tree::parse(xml)?;
+    let issuer = root
+        .child("primaryIssuer")
+        .chain_err(|| "Form D has no primary issuer")?;
+    let offering = root
+        .child("offeringData")
+        .chain_err(|| "Form D has no offering data")?;
+
+    let flag = |element: &Element, path: &[&str]| {
+        element
+            .text_at(path)
+            .is_some_and(|v| v == "true" || v == "Y")
+    };
+    let dollars = |path: &[&str]| offering.text_at(path).and_then(|v| whole_number(&v));
+    let amount = |path: &[&str]| {
+        offering.text_at(path).and_then(|v| match v.as_str() {
+            "Indefinite" => Some(Amount::Indefinite),
+            v => whole_number(v).map(Amount::Dollars),
+        })
+    };
+
+    Ok(FormD {
+        submission_type: root.text_at(&["submissionType"]).unwrap_or_default(),
+        issuer: FormDIssuer {
+            cik: issuer
+                .text_at(&["cik"])
+                .and_then(|c| c.parse::<usize>().ok())
+                .chain_err(|| "Form D issuer has no CIK")?,
+            name: issuer.text_at(&["entityName"]).unwrap_or_default(),
+            address: issuer
+                .child("issuerAddress")
+                .map(Element::address_lines)
+                .unwrap_or_default(),
+            phone: issuer.text_at(&["issuerPhoneNumber"]),
+            jurisdiction: issuer.text_at(&["jurisdictionOfInc"]),
+            entity_type: issuer.text_at(&["entityType"]),
+            year_of_incorporation: issuer
+                .text_at(&["yearOfInc", "value"])
+                .and_then(|y| y.parse::<u32>().ok()),
+        },
+        related_persons: root
+            .find(&["relatedPersonsList"])
+            .map(|l| {
+                l.children("relatedPersonInfo")
+                    .map(related_person)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        industry_group: offering.text_at(&["industryGroup", "industryGroupType"]),
+        investment_fund_type: offering.text_at(&[
+            "industryGroup",
+            "investmentFundInfo",
+            "investmentFundType",
+        ]),
+        revenue_range: offering
+            .text_at(&["issuerSize", "revenueRange"])
+            .or_else(|| offering.text_at(&["issuerSize", "aggregateNetAssetValueRange"])),
+        exemptions: offering
+            .find(&["federalExemptionsExclusions"])
+            .map(|e| {
+                e.children("item")
+                    .filter_map(Element::trimmed_text)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        is_amendment: flag(offering, &["typeOfFiling", "newOrAmendment", "isAmendment"]),
+        date_of_first_sale: offering
+            .text_at(&["typeOfFiling", "dateOfFirstSale", "value"])
+            .and_then(|d| values::date(&d)),
+        minimum_investment: dollars(&["minimumInvestmentAccepted"]),
+        total_offering_amount: amount(&["offeringSalesAmounts", "totalOfferingAmount"]),
+        total_amount_sold: dollars(&["offeringSalesAmounts", "totalAmountSold"]),
+        total_remaining: amount(&["offeringSalesAmounts", "totalRemaining"]),
+        investors: offering
+            .text_at(&["investors", "totalNumberAlreadyInvested"])
+            .and_then(|n| n.parse::<u32>().ok()),
+        has_non_accredited_investors: flag(offering, &["investors", "hasNonAccreditedInvestors"]),
+        sales_commissions: dollars(&[
+            "salesCommissionsFindersFees",
+            "salesCommissions",
+            "dollarAmount",
+        ]),
+        finders_fees: dollars(&["salesCommissionsFindersFees", "findersFees", "dollarAmount"]),
+        sales_compensation: offering
+            .find(&["salesCompensationList"])
+            .map(|l| l.children("recipient").map(recipient).collect())
+            .unwrap_or_default(),
+    })
+}
+
+fn related_person(person: &Element) -> RelatedPerson {
+    let name = ["firstName", "middleName", "lastName"]
+        .iter()
+        .filter_map(|n| person.text_at(&["relatedPersonName", n]))
+        .collect::<Vec<String>>()
+        .join(" ");
+    RelatedPerson {
+        name,
+        address: person
+            .child("relatedPersonAddress")
+            .map(Element::address_lines)
+            .unwrap_or_default(),
+        relationships: person
+            .find(&["relatedPersonRelationshipList"])
+            .map(|l| {
+                l.children("relationship")
+                    .filter_map(Element::trimmed_text)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        clarification: person.text_at(&["relationshipClarification"]),
+    }
+}
+
+fn recipient(recipient: &Element) -> CompensationRecipient {
+    // Fields that don't apply are filled in with `None` rather than left out
+    let field = |name: &str| recipient.text_at(&[name]).filter(|v| v != "None");
+    let states = recipient.find(&["statesOfSolicitationList"]);
+    CompensationRecipient {
+        name: field("recipientName").unwrap_or_default(),
+        crd_number: field("recipientCRDNumber"),
+        broker_dealer: field("associatedBDName"),
+        broker_dealer_crd_number: field("associatedBDCRDNumber"),
+        address: recipient
+            .child("recipientAddress")
+            .map(Element::address_lines)
+            .unwrap_or_default(),
+        states: states
+            .map(|s| {
+                s.children("state")
+                    .filter_map(Element::trimmed_text)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        all_states: states.is_some_and(|s| {
+            s.text_at(&["value"]).as_deref() == Some("All States")
+                || s.text_at(&["allStates"]).as_deref() == Some("true")
+        }),
+    }
+}
+
+/// Parses the Form D among a complete submission's documents
+pub fn from_submission<R: BufRead>(submission: Submission<R>) -> Result<Option<FormD>> {
+    for document in submission {
+        let text = document?.text();
+        if text.contains("<edgarSubmission") && text.contains("<offeringData") {
+            return parse(&text).map(Some);
+        }
+    }
+    Ok(None)
+}
+
+/// Downloads `entry`'s complete submission and parses its Form D
+pub fn fetch(entry: &SECEntry) -> Result<FormD> {
+    from_submission(submission::fetch(entry)?)?.chain_err(|| {
+        format!(
+            "No Form D in {}",
+            SECEntry::dashed_accession(entry.accession_number())
+        )
+    })
+}
+
+#[cfg(test)]
+mod form_d_tests {
+    use super::*;
+
+    const FORM_D: &str = r#"<?xml version="1.0"?>
+<edgarSubmission>
+  <schemaVersion>X0708</schemaVersion>
+  <submissionType>D</submissionType>
+  <testOrLive>LIVE</testOrLive>
+  <primaryIssuer>
+    <cik>0001754195</cik>
+    <entityName>Example Capital Fund, LP</entityName>
+    <issuerAddress>
+      <street1>1 MAIN STREET</street1>
+      <street2>SUITE 100</street2>
+      <city>NEW YORK</city>
+      <stateOrCountry>NY</stateOrCountry>
+      <stateOrCountryDescription>NEW YORK</stateOrCountryDescription>
+      <zipCode>10001</zipCode>
+    </issuerAddress>
+    <issuerPhoneNumber>212-555-0100</issuerPhoneNumber>
+    <jurisdictionOfInc>DELAWARE</jurisdictionOfInc>
+    <entityType>Limited Partnership</entityType>
+    <yearOfInc>
+      <withinFiveYears>true</withinFiveYears>
+      <value>2018</value>
+    </yearOfInc>
+  </primaryIssuer>
+  <relatedPersonsList>
+    <relatedPersonInfo>
+      <relatedPersonName>
+        <firstName>Jane</firstName>
+        <middleName></middleName>
+        <lastName>Doe</lastName>
+      </relatedPersonName>
+      <relatedPersonAddress>
+        <street1>1 MAIN STREET</street1>
+        <city>NEW YORK</city>
+        <stateOrCountry>NY</stateOrCountry>
+        <zipCode>10001</zipCode>
+      </relatedPersonAddress>
+      <relatedPersonRelationshipList>
+        <relationship>Executive Officer</relationship>
+        <relationship>Director</relationship>
+      </relatedPersonRelationshipList>
+      <relationshipClarification>Managing member of the general partner</relationshipClarification>
+    </relatedPersonInfo>
+  </relatedPersonsList>
+  <offeringData>
+    <industryGroup>
+      <industryGroupType>Pooled Investment Fund</industryGroupType>
+      <investmentFundInfo>
+        <investmentFundType>Hedge Fund</investmentFundType>
+        <is40Act>false</is40Act>
+      </investmentFundInfo>
+    </industryGroup>
+    <issuerSize>
+      <aggregateNetAssetValueRange>Decline to Disclose</aggregateNetAssetValueRange>
+    </issuerSize>
+    <federalExemptionsExclusions>
+      <item>06b</item>
+      <item>3C</item>
+      <item>3C.7</item>
+    </federalExemptionsExclusions>
+    <typeOfFiling>
+      <newOrAmendment>
+        <isAmendment>false</isAmendment>
+      </newOrAmendment>
+      <dateOfFirstSale>
+        <value>2018-09-01</value>
+      </dateOfFirstSale>
+    </typeOfFiling>
+    <minimumInvestmentAccepted>250000</minimumInvestmentAccepted>
+    <salesCompensationList>
+      <recipient>
+        <recipientName>Example Securities LLC</recipientName>
+        <recipientCRDNumber>123456</recipientCRDNumber>
+        <associatedBDName>None</associatedBDName>
+        <associatedBDCRDNumber>None</associatedBDCRDNumber>
+        <recipientAddress>
+          <street1>2 BROAD STREET</street1>
+          <city>BOSTON</city>
+          <stateOrCountry>MA</stateOrCountry>
+          <zipCode>02109</zipCode>
+        </recipientAddress>
+        <statesOfSolicitationList>
+          <state>NY</state>
+          <state>MA</state>
+        </statesOfSolicitationList>
+        <foreignSolicitation>false</foreignSolicitation>
+      </recipient>
+    </salesCompensationList>
+    <offeringSalesAmounts>
+      <totalOfferingAmount>Indefinite</totalOfferingAmount>
+      <totalAmountSold>15000000</totalAmountSold>
+      <totalRemaining>Indefinite</totalRemaining>
+    </offeringSalesAmounts>
+    <investors>
+      <hasNonAccreditedInvestors>false</hasNonAccreditedInvestors>
+      <totalNumberAlreadyInvested>12</totalNumberAlreadyInvested>
+    </investors>
+    <salesCommissionsFindersFees>
+      <salesCommissions>
+        <dollarAmount>30000</dollarAmount>
+      </salesCommissions>
+      <findersFees>
+        <dollarAmount>0</dollarAmount>
+      </findersFees>
+    </salesCommissionsFindersFees>
+  </offeringData>
+</edgarSubmission>
+"#;
+
+    #[test]
+    fn parse_test() {
+        let form = parse(FORM_D).unwrap();
+        assert_eq!(form.submission_type, "D");
+        assert_eq!(
+            form.issuer,
+            FormDIssuer {
+                cik: 1754195,
+                name: String::from("Example Capital Fund, LP"),
+                address: vec![
+                    String::from("1 MAIN STREET"),
+                    String::from("SUITE 100"),
+                    String::from("NEW YORK NY 10001"),
+                ],
+                phone: Some(String::from("212-555-0100")),
+                jurisdiction: Some(String::from("DELAWARE")),
+                entity_type: Some(String::from("Limited Partnership")),
+                year_of_incorporation: Some(2018),
+            }
+        );
+        assert_eq!(
+            form.related_persons,
+            vec![RelatedPerson {
+                name: String::from("Jane Doe"),
+                address: vec![
+                    String::from("1 MAIN STREET"),
+                    String::from("NEW YORK NY 10001"),
+                ],
+                relationships: vec![String::from("Executive Officer"), String::from("Director")],
+                clarification: Some(String::from("Managing member of the general partner")),
+            }]
+        );
+    }
+
+    #[test]
+    fn offering_test() {
+        let form = parse(FORM_D).unwrap();
+        assert_eq!(
+            form.industry_group,
+            Some(String::from("Pooled Investment Fund"))
+        );
+        assert_eq!(form.investment_fund_type, Some(String::from("Hedge Fund")));
+        assert_eq!(
+            form.revenue_range,
+            Some(String::from("Decline to Disclose"))
+        );
+        assert_eq!(form.exemptions, vec!["06b", "3C", "3C.7"]);
+        assert!(!form.is_amendment);
+        assert_eq!(form.date_of_first_sale, NaiveDate::from_ymd_opt(2018, 9, 1));
+        assert_eq!(form.minimum_investment, Some(250000));
+        assert_eq!(form.total_offering_amount, Some(Amount::Indefinite));
+        assert_eq!(form.total_amount_sold, Some(15000000));
+        assert_eq!(form.total_remaining, Some(Amount::Indefinite));
+        assert_eq!(form.investors, Some(12));
+        assert!(!form.has_non_accredited_investors);
+        assert_eq!(form.sales_commissions, Some(30000));
+        assert_eq!(form.finders_fees, Some(0));
+        assert_eq!(
+            form.sales_compensation,
+            vec![CompensationRecipient {
+                name: String::from("Example Securities LLC"),
+                crd_number: Some(String::from("123456")),
+                broker_dealer: None,
+                broker_dealer_crd_number: None,
+                address: vec![
+                    String::from("2 BROAD STREET"),
+                    String::from("BOSTON MA 02109"),
+                ],
+                states: vec![String::from("NY"), String::from("MA")],
+                all_states: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn malformed_test() {
+        assert!(parse("<edgarSubmission><offeringData/></edgarSubmission>").is_err());
+        assert!(parse(
+            "<edgarSubmission><primaryIssuer><cik>1</cik></primaryIssuer></edgarSubmission>"
+        )
+        .is_err());
+    }
+}
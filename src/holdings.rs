@@ -0,0 +1,465 @@
+//! Reads 13F-HR holdings reports, where institutional managers list what they
+//! held at the end of a quarter
+//!
+//! -> `parse_cover` reads the cover and summary pages of `primary_doc.xml`
+//! -> `parse_information_table` reads the holdings themselves
+//! -> `from_submission` and `fetch` find both among a filing's documents
+//! -> `diff` compares two quarters of a manager's holdings
+//!
+//! Values are as reported, which is thousands of dollars in reports for periods
+//! before 2023 and dollars since, so comparing values across that change needs
+//! one side scaling first. Share counts don't change units.
+use crate::errors::*;
+use crate::sec_entry::SECEntry;
+use crate::submission::{self, Submission};
+use crate::values::whole_number;
+use crate::xml_tree::{self, Element};
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+use std::io::BufRead;
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct CoverPage {
+    pub manager_cik: Option<usize>,
+    pub manager_name: String,
+    pub manager_address: Vec<String>,
+    /// The end of the quarter the holdings are for
+    pub period: Option<NaiveDate>,
+    /// `RESTATEMENT` or `NEW HOLDINGS` for amendments
+    pub amendment_type: Option<String>,
+    /// e.g. `13F HOLDINGS REPORT` or `13F COMBINATION REPORT`
+    pub report_type: Option<String>,
+    pub file_number: Option<String>,
+    pub table_entry_total: Option<u32>,
+    pub table_value_total: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum PutCall {
+    Put,
+    Call,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum AmountType {
+    Shares,
+    /// A principal amount, for debt
+    Principal,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Holding {
+    pub name_of_issuer: String,
+    pub title_of_class: String,
+    pub cusip: String,
+    pub value: u64,
+    /// Shares, or the principal amount of debt
+    pub amount: u64,
+    pub amount_type: AmountType,
+    pub put_call: Option<PutCall>,
+    /// `SOLE`, `DFND` (shared-defined) or `OTR` (shared-other)
+    pub investment_discretion: String,
+    /// Numbers of the other managers sharing in it, as listed on the cover page
+    pub other_managers: Option<String>,
+    pub voting_sole: u64,
+    pub voting_shared: u64,
+    pub voting_none: u64,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct HoldingsReport {
+    pub cover: CoverPage,
+    pub holdings: Vec<Holding>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Change {
+    Opened,
+    Increased,
+    Decreased,
+    Closed,
+}
+
+/// How a position moved from one report to the next
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct HoldingChange {
+    pub cusip: String,
+    pub put_call: Option<PutCall>,
+    pub name_of_issuer: String,
+    pub change: Change,
+    pub amount_before: u64,
+    pub amount_after: u64,
+    pub value_before: u64,
+    pub value_after: u64,
+}
+
+pub fn parse_cover(xml: &str) -> Result<CoverPage> {
+    let root = xml_tree::parse(xml)?;
+    let cover = root
+        .find(&["formData", "coverPage"])
+        .chain_err(|| "13F has no cover page")?;
+    let summary = root.find(&["formData", "summaryPage"]);
+    let manager = cover.child("filingManager");
+
+    let amended = cover
+        .text_at(&["isAmendment"])
+        .is_some_and(|a| a == "true" || a == "Y");
+    Ok(CoverPage {
+        manager_cik: root
+            .text_at(&["headerData", "filerInfo", "filer", "credentials", "cik"])
+            .and_then(|c| c.parse::<usize>().ok()),
+        manager_name: manager
+            .and_then(|m| m.text_at(&["name"]))
+            .unwrap_or_default(),
+        manager_address: manager
+            .and_then(|m| m.child("address"))
+            .map(Element::address_lines)
+            .unwrap_or_default(),
+        period: cover
+            .text_at(&["reportCalendarOrQuarter"])
+            .and_then(|p| NaiveDate::parse_from_str(&p, "%m-%d-%Y").ok()),
+        amendment_type: cover
+            .text_at(&["amendmentInfo", "amendmentType"])
+            .filter(|_| amended),
+        report_type: cover.text_at(&["reportType"]),
+        file_number: cover.text_at(&["form13FFileNumber"]),
+        table_entry_total: summary
+            .and_then(|s| s.text_at(&["tableEntryTotal"]))
+            .and_then(|t| t.parse::<u32>().ok()),
+        table_value_total: summary
+            .and_then(|s| s.text_at(&["tableValueTotal"]))
+            .and_then(|t| whole_number(&t)),
+    })
+}
+
+pub fn parse_information_table(xml: &str) -> Result<Vec<Holding>> {
+    let root = xml_tree::parse(xml)?;
+    if root.name != "informationTable" {
+        bail!("Expected an informationTable, not {}", root.name);
+    }
+    root.children("infoTable").map(holding).collect()
+}
+
+fn holding(row: &Element) -> Result<Holding> {
+    let cusip = row
+        .text_at(&["cusip"])
+        .chain_err(|| "Holding has no CUSIP")?;
+    let amount = |path: &[&str]| {
+        row.text_at(path)
+            .and_then(|v| whole_number(&v))
+            .unwrap_or(0)
+    };
+
+    Ok(Holding {
+        name_of_issuer: row.text_at(&["nameOfIssuer"]).unwrap_or_default(),
+        title_of_class: row.text_at(&["titleOfClass"]).unwrap_or_default(),
+        value: row
+            .text_at(&["value"])
+            .and_then(|v| whole_number(&v))
+            .chain_err(|| format!("Holding {} has no value", cusip))?,
+        amount: amount(&["shrsOrPrnAmt", "sshPrnamt"]),
+        amount_type: match row.text_at(&["shrsOrPrnAmt", "sshPrnamtType"]).as_deref() {
+            Some("PRN") => AmountType::Principal,
+            _ => AmountType::Shares,
+        },
+        put_call: match row
+            .text_at(&["putCall"])
+            .map(|p| p.to_uppercase())
+            .as_deref()
+        {
+            Some("PUT") => Some(PutCall::Put),
+            Some("CALL") => Some(PutCall::Call),
+            _ => None,
+        },
+        investment_discretion: row.text_at(&["investmentDiscretion"]).unwrap_or_default(),
+        other_managers: row.text_at(&["otherManager"]),
+        voting_sole: amount(&["votingAuthority", "Sole"]),
+        voting_shared: amount(&["votingAuthority", "Shared"]),
+        voting_none: amount(&["votingAuthority", "None"]),
+        cusip,
+    })
+}
+
+/// Reads the cover page and information table out of a complete submission
+pub fn from_submission<R: BufRead>(submission: Submission<R>) -> Result<HoldingsReport> {
+    let mut cover = None;
+    let mut holdings = None;
+    for document in submission {
+        let text = document?.text();
+        if cover.is_none() && text.contains("<edgarSubmission") {
+            cover = Some(parse_cover(&text)?);
+        } else if holdings.is_none() && text.contains("informationTable") {
+            holdings = Some(parse_information_table(&text)?);
+        }
+    }
+    Ok(HoldingsReport {
+        cover: cover.chain_err(|| "No 13F cover page in submission")?,
+        // Notice filings report no holdings of their own
+        holdings: holdings.unwrap_or_default(),
+    })
+}
+
+/// Downloads `entry`'s complete submission and reads its holdings
+pub fn fetch(entry: &SECEntry) -> Result<HoldingsReport> {
+    from_submission(submission::fetch(entry)?)
+}
+
+/// The positions that were opened, closed, grown or cut between `before` and
+/// `after`, ordered by CUSIP. Rows for the same security (split by discretion or
+/// manager) are added together, with puts and calls kept apart from the shares.
+pub fn diff(before: &[Holding], after: &[Holding]) -> Vec<HoldingChange> {
+    let before = positions(before);
+    let mut after = positions(after);
+
+    let mut changes = Vec::new();
+    for (key, old) in before {
+        let new = after.remove(&key);
+        let (amount_after, value_after) = new.as_ref().map_or((0, 0), |n| (n.1, n.2));
+        let change = match amount_after {
+            0 => Change::Closed,
+            a if a > old.1 => Change::Increased,
+            a if a < old.1 => Change::Decreased,
+            _ => continue,
+        };
+        changes.push(HoldingChange {
+            cusip: key.0,
+            put_call: key.1,
+            name_of_issuer: old.0,
+            change,
+            amount_before: old.1,
+            amount_after,
+            value_before: old.2,
+            value_after,
+        });
+    }
+    for (key, new) in after {
+        changes.push(HoldingChange {
+            cusip: key.0,
+            put_call: key.1,
+            name_of_issuer: new.0,
+            change: Change::Opened,
+            amount_before: 0,
+            amount_after: new.1,
+            value_before: 0,
+            value_after: new.2,
+        });
+    }
+    changes.sort_by(|a, b| (&a.cusip, a.put_call).cmp(&(&b.cusip, b.put_call)));
+    changes
+}
+
+type Position = (String, u64, u64);
+
+/// Name, amount and value for each CUSIP and option type
+fn positions(holdings: &[Holding]) -> BTreeMap<(String, Option<PutCall>), Position> {
+    let mut positions: BTreeMap<(String, Option<PutCall>), Position> = BTreeMap::new();
+    for holding in holdings {
+        let position = positions
+            .entry((holding.cusip.to_uppercase(), holding.put_call))
+            .or_insert_with(|| (holding.name_of_issuer.clone(), 0, 0));
+        position.1 += holding.amount;
+        position.2 += holding.value;
+    }
+    positions
+}
+
+#[cfg(test)]
+mod holdings_tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const COVER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<edgarSubmission xmlns="http://www.sec.gov/edgar/thirteenffiler">
+  <headerData>
+    <submissionType>13F-HR</submissionType>
+    <filerInfo>
+      <filer>
+        <credentials><cik>0001067983</cik><ccc>XXXXXXXX</ccc></credentials>
+      </filer>
+      <periodOfReport>09-30-2018</periodOfReport>
+    </filerInfo>
+  </headerData>
+  <formData>
+    <coverPage>
+      <reportCalendarOrQuarter>09-30-2018</reportCalendarOrQuarter>
+      <isAmendment>false</isAmendment>
+      <filingManager>
+        <name>BERKSHIRE HATHAWAY INC</name>
+        <address>
+          <street1>3555 FARNAM STREET</street1>
+          <city>OMAHA</city>
+          <stateOrCountry>NE</stateOrCountry>
+          <zipCode>68131</zipCode>
+        </address>
+      </filingManager>
+      <reportType>13F HOLDINGS REPORT</reportType>
+      <form13FFileNumber>028-04545</form13FFileNumber>
+    </coverPage>
+    <summaryPage>
+      <tableEntryTotal>2</tableEntryTotal>
+      <tableValueTotal>1,234</tableValueTotal>
+    </summaryPage>
+  </formData>
+</edgarSubmission>
+"#;
+
+    const TABLE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ns1:informationTable xmlns:ns1="http://www.sec.gov/edgar/document/thirteenf/informationtable">
+  <ns1:infoTable>
+    <ns1:nameOfIssuer>APPLE INC</ns1:nameOfIssuer>
+    <ns1:titleOfClass>COM</ns1:titleOfClass>
+    <ns1:cusip>037833100</ns1:cusip>
+    <ns1:value>1000</ns1:value>
+    <ns1:shrsOrPrnAmt>
+      <ns1:sshPrnamt>5000</ns1:sshPrnamt>
+      <ns1:sshPrnamtType>SH</ns1:sshPrnamtType>
+    </ns1:shrsOrPrnAmt>
+    <ns1:investmentDiscretion>DFND</ns1:investmentDiscretion>
+    <ns1:otherManager>4,11</ns1:otherManager>
+    <ns1:votingAuthority>
+      <ns1:Sole>5000</ns1:Sole>
+      <ns1:Shared>0</ns1:Shared>
+      <ns1:None>0</ns1:None>
+    </ns1:votingAuthority>
+  </ns1:infoTable>
+  <ns1:infoTable>
+    <ns1:nameOfIssuer>TESLA INC</ns1:nameOfIssuer>
+    <ns1:titleOfClass>NOTE 1.250% 3/0</ns1:titleOfClass>
+    <ns1:cusip>88160RAC5</ns1:cusip>
+    <ns1:value>234</ns1:value>
+    <ns1:shrsOrPrnAmt>
+      <ns1:sshPrnamt>250000</ns1:sshPrnamt>
+      <ns1:sshPrnamtType>PRN</ns1:sshPrnamtType>
+    </ns1:shrsOrPrnAmt>
+    <ns1:putCall>Call</ns1:putCall>
+    <ns1:investmentDiscretion>SOLE</ns1:investmentDiscretion>
+    <ns1:votingAuthority>
+      <ns1:Sole>0</ns1:Sole>
+      <ns1:Shared>0</ns1:Shared>
+      <ns1:None>250000</ns1:None>
+    </ns1:votingAuthority>
+  </ns1:infoTable>
+</ns1:informationTable>
+"#;
+
+    fn holding(cusip: &str, put_call: Option<PutCall>, amount: u64, value: u64) -> Holding {
+        Holding {
+            name_of_issuer: format!("ISSUER {}", cusip),
+            title_of_class: String::from("COM"),
+            cusip: String::from(cusip),
+            value,
+            amount,
+            amount_type: AmountType::Shares,
+            put_call,
+            investment_discretion: String::from("SOLE"),
+            other_managers: None,
+            voting_sole: amount,
+            voting_shared: 0,
+            voting_none: 0,
+        }
+    }
+
+    #[test]
+    fn parse_cover_test() {
+        assert_eq!(
+            parse_cover(COVER).unwrap(),
+            CoverPage {
+                manager_cik: Some(1067983),
+                manager_name: String::from("BERKSHIRE HATHAWAY INC"),
+                manager_address: vec![
+                    String::from("3555 FARNAM STREET"),
+                    String::from("OMAHA NE 68131"),
+                ],
+                period: NaiveDate::from_ymd_opt(2018, 9, 30),
+                amendment_type: None,
+                report_type: Some(String::from("13F HOLDINGS REPORT")),
+                file_number: Some(String::from("028-04545")),
+                table_entry_total: Some(2),
+                table_value_total: Some(1234),
+            }
+        );
+        assert!(parse_cover(TABLE).is_err());
+    }
+
+    #[test]
+    fn parse_information_table_test() {
+        let holdings = parse_information_table(TABLE).unwrap();
+        assert_eq!(holdings.len(), 2);
+        assert_eq!(
+            holdings[0],
+            Holding {
+                name_of_issuer: String::from("APPLE INC"),
+                title_of_class: String::from("COM"),
+                cusip: String::from("037833100"),
+                value: 1000,
+                amount: 5000,
+                amount_type: AmountType::Shares,
+                put_call: None,
+                investment_discretion: String::from("DFND"),
+                other_managers: Some(String::from("4,11")),
+                voting_sole: 5000,
+                voting_shared: 0,
+                voting_none: 0,
+            }
+        );
+        assert_eq!(holdings[1].amount_type, AmountType::Principal);
+        assert_eq!(holdings[1].put_call, Some(PutCall::Call));
+        assert_eq!(holdings[1].voting_none, 250000);
+    }
+
+    #[test]
+    fn from_submission_test() {
+        let text = format!(
+            "<SEC-DOCUMENT>\n<SEC-HEADER>\n</SEC-HEADER>\n\
+             <DOCUMENT>\n<TYPE>13F-HR\n<TEXT>\n<XML>\n{}</XML>\n</TEXT>\n</DOCUMENT>\n\
+             <DOCUMENT>\n<TYPE>INFORMATION TABLE\n<TEXT>\n<XML>\n{}</XML>\n</TEXT>\n</DOCUMENT>\n\
+             </SEC-DOCUMENT>\n",
+            COVER, TABLE
+        );
+        let report =
+            from_submission(Submission::new(Cursor::new(text.as_bytes())).unwrap()).unwrap();
+        assert_eq!(report.cover.manager_name, "BERKSHIRE HATHAWAY INC");
+        assert_eq!(report.holdings.len(), 2);
+    }
+
+    #[test]
+    fn diff_test() {
+        let before = vec![
+            holding("A", None, 100, 10),
+            holding("A", None, 50, 5),
+            holding("B", None, 100, 10),
+            holding("C", None, 100, 10),
+            holding("D", None, 100, 10),
+        ];
+        let after = vec![
+            holding("A", None, 150, 20),
+            holding("B", None, 200, 20),
+            holding("C", None, 40, 4),
+            holding("C", Some(PutCall::Put), 10, 1),
+            holding("E", None, 70, 7),
+        ];
+
+        let changes = diff(&before, &after)
+            .into_iter()
+            .map(|c| {
+                (
+                    c.cusip,
+                    c.put_call,
+                    c.change,
+                    c.amount_before,
+                    c.amount_after,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![
+                (String::from("B"), None, Change::Increased, 100, 200),
+                (String::from("C"), None, Change::Decreased, 100, 40),
+                (String::from("C"), Some(PutCall::Put), Change::Opened, 0, 10),
+                (String::from("D"), None, Change::Closed, 100, 0),
+                (String::from("E"), None, Change::Opened, 0, 70),
+            ]
+        );
+    }
+}
@@ -0,0 +1,306 @@
+//! The numbered items an 8-K reports, e.g. 2.02 for an earnings release
+//!
+//! Neither the feed nor the index files say which items an 8-K covers, so they
+//! are looked up afterwards:
+//!
+//! -> `from_header` reads them off the `ITEM INFORMATION` lines of a submission's
+//!    header, which give each item's title rather than its number
+//! -> `filing_index::parse_html` reads the numbers off the filing's index page
+//! -> `look_up` fetches them for the 8-Ks in a batch of new filings and stores them
+//!    beside the filings, where `FilingQuery::item` can find them
+//! -> `ItemFilter` passes only the 8-Ks reporting certain items on to a sink, so a
+//!    webhook can alert on them
+use crate::db::DbConnection;
+use crate::errors::*;
+use crate::filings;
+use crate::sec_entry::{FilingType, SECEntry};
+use crate::sink::Sink;
+use crate::submission::{self, SubmissionHeader};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// Every item of Form 8-K, with its title as the header gives it
+const ITEMS: [(&str, &str); 33] = [
+    ("1.01", "Entry into a Material Definitive Agreement"),
+    ("1.02", "Termination of a Material Definitive Agreement"),
+    ("1.03", "Bankruptcy or Receivership"),
+    ("1.04", "Mine Safety - Reporting of Shutdowns and Patterns of Violations"),
+    ("1.05", "Material Cybersecurity Incidents"),
+    ("2.01", "Completion of Acquisition or Disposition of Assets"),
+    ("2.02", "Results of Operations and Financial Condition"),
+    ("2.03", "Creation of a Direct Financial Obligation or an Obligation under an Off-Balance Sheet Arrangement of a Registrant"),
+    ("2.04", "Triggering Events That Accelerate or Increase a Direct Financial Obligation or an Obligation under an Off-Balance Sheet Arrangement"),
+    ("2.05", "Costs Associated with Exit or Disposal Activities"),
+    ("2.06", "Material Impairments"),
+    ("3.01", "Notice of Delisting or Failure to Satisfy a Continued Listing Rule or Standard; Transfer of Listing"),
+    ("3.02", "Unregistered Sales of Equity Securities"),
+    ("3.03", "Material Modification to Rights of Security Holders"),
+    ("4.01", "Changes in Registrant's Certifying Accountant"),
+    ("4.02", "Non-Reliance on Previously Issued Financial Statements or a Related Audit Report or Completed Interim Review"),
+    ("5.01", "Changes in Control of Registrant"),
+    ("5.02", "Departure of Directors or Certain Officers; Election of Directors; Appointment of Certain Officers; Compensatory Arrangements of Certain Officers"),
+    ("5.03", "Amendments to Articles of Incorporation or Bylaws; Change in Fiscal Year"),
+    ("5.04", "Temporary Suspension of Trading Under Registrant's Employee Benefit Plans"),
+    ("5.05", "Amendments to the Registrant's Code of Ethics, or Waiver of a Provision of the Code of Ethics"),
+    ("5.06", "Change in Shell Company Status"),
+    ("5.07", "Submission of Matters to a Vote of Security Holders"),
+    ("5.08", "Shareholder Director Nominations"),
+    ("6.01", "ABS Informational and Computational Material"),
+    ("6.02", "Change of Servicer or Trustee"),
+    ("6.03", "Change in Credit Enhancement or Other External Support"),
+    ("6.04", "Failure to Make a Required Distribution"),
+    ("6.05", "Securities Act Updating Disclosure"),
+    ("6.06", "Static Pool"),
+    ("7.01", "Regulation FD Disclosure"),
+    ("8.01", "Other Events"),
+    ("9.01", "Financial Statements and Exhibits"),
+];
+
+/// Whether filings of this type report items
+pub fn has_items(filing_type: FilingType) -> bool {
+    filing_type == FilingType::Sec8K || filing_type == FilingType::Sec8KA
+}
+
+/// The title of item `number`, e.g. `Other Events` for `8.01`
+pub fn title(number: &str) -> Option<&'static str> {
+    ITEMS.iter().find(|(n, _)| *n == number).map(|(_, t)| *t)
+}
+
+/// The number of the item `description` names, either as `Item 2.02: ...` or by
+/// its title. Titles are compared ignoring case and punctuation, and may be cut
+/// short, as long headers sometimes are.
+pub fn number(description: &str) -> Option<&'static str> {
+    lazy_static! {
+        static ref NUMBERED: Regex = Regex::new(r"^\s*Item\s+(\d+\.\d+)").unwrap();
+    }
+    if let Some(c) = NUMBERED.captures(description) {
+        return ITEMS.iter().find(|(n, _)| *n == &c[1]).map(|(n, _)| *n);
+    }
+
+    let description = normalize(description);
+    if description.is_empty() {
+        return None;
+    }
+    ITEMS
+        .iter()
+        .find(|(_, t)| normalize(t) == description)
+        .or_else(|| {
+            ITEMS
+                .iter()
+                .find(|(_, t)| normalize(t).starts_with(&description))
+        })
+        .map(|(n, _)| *n)
+}
+
+fn normalize(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// The numbers of the items in a submission's header, in order, skipping any
+/// that aren't recognised
+pub fn from_header(header: &SubmissionHeader) -> Vec<String> {
+    let mut numbers = Vec::new();
+    for n in header.items.iter().filter_map(|i| number(i)) {
+        if !numbers.iter().any(|m| m == n) {
+            numbers.push(n.to_owned());
+        }
+    }
+    numbers
+}
+
+/// Reads the items of `entry` from its submission's header, without downloading
+/// the documents after it
+pub fn fetch(entry: &SECEntry) -> Result<Vec<String>> {
+    Ok(from_header(submission::fetch(entry)?.header()))
+}
+
+/// Looks up the items of each 8-K among `entries` that doesn't have them yet with
+/// `fetch`, and stores them for the filings, which must already be written. An
+/// 8-K whose items can't be fetched is reported on standard error and left
+/// without them, rather than holding up the rest.
+pub fn look_up<F>(
+    conn: &DbConnection,
+    entries: Vec<SECEntry>,
+    mut fetch: F,
+) -> Result<Vec<SECEntry>>
+where
+    F: FnMut(&SECEntry) -> Result<Vec<String>>,
+{
+    let entries = entries
+        .into_iter()
+        .map(|entry| {
+            if !has_items(entry.filing_type()) || !entry.items().is_empty() {
+                return entry;
+            }
+            match fetch(&entry) {
+                Ok(items) => entry.with_items(items),
+                Err(e) => {
+                    eprintln!(
+                        "Unable to look up the items of {}: {}",
+                        SECEntry::dashed_accession(entry.accession_number()),
+                        e
+                    );
+                    entry
+                }
+            }
+        })
+        .collect::<Vec<SECEntry>>();
+    filings::write_items(conn, &entries)?;
+    Ok(entries)
+}
+
+/// Hands on only the 8-Ks that report at least one of `items`
+pub struct ItemFilter<S> {
+    sink: S,
+    items: Vec<String>,
+}
+
+impl<S: Sink> ItemFilter<S> {
+    pub fn new(sink: S, items: Vec<String>) -> ItemFilter<S> {
+        ItemFilter { sink, items }
+    }
+}
+
+impl<S: Sink> Sink for ItemFilter<S> {
+    fn emit(&mut self, entries: &[SECEntry]) -> Result<()> {
+        let items = &self.items;
+        let wanted = entries
+            .iter()
+            .filter(|entry| entry.items().iter().any(|i| items.contains(i)))
+            .cloned()
+            .collect::<Vec<SECEntry>>();
+        if wanted.is_empty() {
+            return Ok(());
+        }
+        self.sink.emit(&wanted)
+    }
+}
+
+#[cfg(test)]
+mod items_tests {
+    use super::*;
+    use crate::filings::{write_filings, FilingQuery};
+    use crate::test_db::TestDb;
+
+    fn entry(filing_type: FilingType, acc: usize) -> SECEntry {
+        SECEntry::new(
+            filing_type,
+            String::from("APPLE INC"),
+            320193,
+            acc,
+            20180901,
+            String::from("2018-09-05T12:36:45-04:00"),
+        )
+    }
+
+    #[test]
+    fn number_test() {
+        assert_eq!(
+            number("Results of Operations and Financial Condition"),
+            Some("2.02")
+        );
+        assert_eq!(number("REGULATION FD DISCLOSURE"), Some("7.01"));
+        assert_eq!(
+            number("Changes in Registrants Certifying Accountant"),
+            Some("4.01")
+        );
+        assert_eq!(
+            number("Departure of Directors or Certain Officers; Election of Directors"),
+            Some("5.02")
+        );
+        assert_eq!(
+            number("Item 9.01: Financial Statements and Exhibits"),
+            Some("9.01")
+        );
+        assert_eq!(number("Item 9.99"), None);
+        assert_eq!(number("Bollocks"), None);
+        assert_eq!(number(""), None);
+        assert_eq!(title("8.01"), Some("Other Events"));
+    }
+
+    #[test]
+    fn from_header_test() {
+        let header = SubmissionHeader {
+            items: vec![
+                String::from("Results of Operations and Financial Condition"),
+                String::from("Financial Statements and Exhibits"),
+                String::from("Results of Operations and Financial Condition"),
+                String::from("Something new"),
+            ],
+            ..SubmissionHeader::default()
+        };
+        assert_eq!(from_header(&header), vec!["2.02", "9.01"]);
+    }
+
+    fn look_up_test(conn: &DbConnection) {
+        let entries = vec![
+            entry(FilingType::Sec8K, 1),
+            entry(FilingType::Sec4, 2),
+            entry(FilingType::Sec8KA, 3).with_items(vec![String::from("8.01")]),
+        ];
+        write_filings(conn, &entries).unwrap();
+
+        let mut fetched = Vec::new();
+        let entries = look_up(conn, entries, |entry| {
+            fetched.push(entry.accession_number());
+            Ok(vec![String::from("2.02"), String::from("9.01")])
+        })
+        .unwrap();
+        assert_eq!(fetched, vec![1]);
+        assert_eq!(entries[0].items(), ["2.02", "9.01"]);
+        assert!(entries[1].items().is_empty());
+
+        let found = FilingQuery::new().item("9.01").run(conn).unwrap();
+        assert_eq!(found.filings, vec![entries[0].clone()]);
+        let found = FilingQuery::new().item("8.01").run(conn).unwrap();
+        assert_eq!(found.filings, vec![entries[2].clone()]);
+
+        // A failed lookup leaves that filing without items and the rest as usual
+        let failing = vec![entry(FilingType::Sec8K, 4), entry(FilingType::Sec8K, 5)];
+        write_filings(conn, &failing).unwrap();
+        let entries = look_up(conn, failing, |entry| match entry.accession_number() {
+            4 => Err("Not found".into()),
+            _ => Ok(vec![String::from("7.01")]),
+        })
+        .unwrap();
+        assert!(entries[0].items().is_empty());
+        assert_eq!(entries[1].items(), ["7.01"]);
+    }
+
+    #[test]
+    fn postgres_look_up_test() {
+        look_up_test(&TestDb::new());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_look_up_test() {
+        look_up_test(&TestDb::sqlite());
+    }
+
+    #[test]
+    fn filter_test() {
+        let mut emitted = Vec::new();
+        {
+            let sink = |entries: &[SECEntry]| {
+                emitted.push(entries.to_vec());
+                Ok(())
+            };
+            let mut filter = ItemFilter::new(sink, vec![String::from("2.02")]);
+            filter
+                .emit(&[
+                    entry(FilingType::Sec8K, 1).with_items(vec![String::from("2.02")]),
+                    entry(FilingType::Sec8K, 2).with_items(vec![String::from("8.01")]),
+                    entry(FilingType::Sec4, 3),
+                ])
+                .unwrap();
+            filter.emit(&[entry(FilingType::Sec4, 4)]).unwrap();
+        }
+        assert_eq!(emitted.len(), 1);
+        assert_eq!(emitted[0].len(), 1);
+        assert_eq!(emitted[0][0].accession_number(), 1);
+    }
+}
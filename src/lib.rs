@@ -7,12 +7,41 @@ extern crate http;
 #[macro_use]
 extern crate error_chain;
 
+#[macro_use]
+extern crate diesel_migrations;
+
+pub mod archive;
+pub mod backfill;
+pub mod beneficial_ownership;
 pub mod cli;
+pub mod columns;
+pub mod db;
+pub mod edgar;
 pub mod errors;
+#[cfg(feature = "export")]
+pub mod export;
+pub mod filing_index;
+pub mod filings;
+pub mod form_d;
+pub mod holdings;
+pub mod items;
+pub mod migrations;
 pub mod models;
+pub mod ownership;
 pub mod postgres;
 pub mod read_rss;
+pub mod retention;
 pub mod schema;
 pub mod sec_entry;
+pub mod sink;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod submission;
+#[cfg(test)]
+mod test_db;
 pub mod timing;
+mod values;
+pub mod webhook;
 pub mod write_entries;
+pub mod xbrl;
+pub mod xml_tree;
@@ -1,21 +1,417 @@
+use std::io;
+#[cfg(feature = "export")]
 use std::path::Path;
+use std::process;
 
+use chrono::{NaiveDate, Utc};
+use clap::ArgMatches;
+use sec_scraper::archive::{Archive, Document, Layout};
+use sec_scraper::backfill::{Backfill, IndexKind};
 use sec_scraper::cli::*;
-use sec_scraper::read_rss::read_rss;
+use sec_scraper::columns::Columns;
+use sec_scraper::db::{establish_connection, DbConnection};
+use sec_scraper::edgar;
+use sec_scraper::errors::*;
+use sec_scraper::filings::{FilingOrder, FilingQuery};
+use sec_scraper::items::{self, ItemFilter};
+use sec_scraper::migrations::{check_schema, run_pending, status};
+use sec_scraper::read_rss::{read_rss, write_new_entries};
+use sec_scraper::retention::{prune, RetentionPolicy};
+use sec_scraper::sec_entry::{FilingRole, FilingType};
+use sec_scraper::sink::{FanOut, PostgresSink, StdoutSink, STREAMED_JSON};
 use sec_scraper::timing::get_rss;
-use sec_scraper::write_entries::write_table;
+use sec_scraper::webhook::WebhookSink;
+use sec_scraper::write_entries::{write_body, Format, OutputFile, Rotation};
+use sha2::{Digest, Sha256};
 use std::collections::HashSet;
 
 const SEC_RSS_URL: &str = "https://www.sec.gov/cgi-bin/browse-edgar?action=getcurrent&CIK=&type=&company=&dateb=&owner=include&start=0&count=40&output=atom";
 
 fn main() {
-    let _ = init();
-    let log_file: &Path = Path::new("accession_numbers.txt");
-    println!("Hello, world!");
+    let matches = init();
+    let conn = establish_connection("");
+
+    if let ("db", Some(db)) = matches.subcommand() {
+        return db_command(&conn, db);
+    }
+
+    if matches.is_present("migrate") {
+        // Standard output may be carrying filings, see `--output -`
+        run_pending(&conn, &mut io::stderr()).expect("Could not migrate database");
+    }
+    if let Err(e) = check_schema(&conn) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+
+    if let ("filings", Some(args)) = matches.subcommand() {
+        return filings_command(&conn, args);
+    }
+    if let ("download", Some(args)) = matches.subcommand() {
+        return download_command(&conn, args);
+    }
+    #[cfg(feature = "export")]
+    {
+        if let ("export", Some(args)) = matches.subcommand() {
+            return export_command(&conn, args);
+        }
+    }
+
+    let mut sink = sink(&matches).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+
+    if let ("backfill", Some(args)) = matches.subcommand() {
+        return backfill_command(&conn, args, &mut sink);
+    }
+
     let etag: Option<&str> = None;
     let (xml, _) = get_rss(SEC_RSS_URL, etag).unwrap();
     let entries = read_rss(&xml, HashSet::new());
+    let look_up_items = matches.is_present("items") || matches.is_present("webhook-item");
     if let Ok(entries) = entries {
-        write_table(log_file, entries).expect("Could not write table");
+        let mut new = Vec::new();
+        write_new_entries(&conn, entries, |entries| {
+            sink.queue(&conn, &entries)?;
+            new = entries;
+            Ok(())
+        })
+        .expect("Could not write table");
+        // Outside the transaction, as each 8-K takes a request to EDGAR
+        if look_up_items {
+            if let Err(e) = items::look_up(&conn, new, items::fetch) {
+                eprintln!("{}", e);
+            }
+        }
+    }
+    if let Err(e) = sink.deliver(&conn) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn backfill_command(conn: &DbConnection, args: &ArgMatches, sink: &mut FanOut<'static>) {
+    let result = backfill(args).and_then(|backfill| backfill.run(conn, edgar::get, sink));
+    match result {
+        Ok(report) => eprintln!(
+            "Read {} index file(s) ({} missing): {} filing(s) listed, {} new, {} of unhandled forms",
+            report.files, report.missing, report.listed, report.new, report.skipped
+        ),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn backfill(args: &ArgMatches) -> Result<Backfill> {
+    let date = |name: &str| -> Result<Option<NaiveDate>> {
+        match args.value_of(name) {
+            Some(v) => Ok(Some(
+                NaiveDate::parse_from_str(v, "%Y%m%d")
+                    .chain_err(|| format!("--{} should be a date as yyyymmdd", name))?,
+            )),
+            None => Ok(None),
+        }
+    };
+    let from = date("from")?.unwrap();
+    let to = date("to")?.unwrap_or_else(|| Utc::now().naive_utc().date());
+    let kind = match args.value_of("index") {
+        Some("form") => IndexKind::Form,
+        _ => IndexKind::Master,
+    };
+    Ok(Backfill::new(from, to)
+        .index(kind)
+        .gzip(args.is_present("gzip")))
+}
+
+/// The output file, or standard output for `--output -`, plus whatever else was
+/// asked for on the command line
+fn sink(matches: &ArgMatches) -> Result<FanOut<'static>> {
+    let format = output_format(matches)?;
+    let columns = output_columns(matches, format)?;
+    let mut sink = FanOut::new();
+    let streamed = matches.value_of("output") == Some("-") || matches.is_present("stdout");
+    if format == Format::Json && streamed {
+        return Err(STREAMED_JSON.into());
+    }
+    if matches.value_of("output") == Some("-") {
+        if matches.is_present("append") {
+            return Err("--append and --rotate need an output file, not -".into());
+        }
+        sink.push("stdout", Box::new(StdoutSink::new(format).columns(columns)));
+    } else {
+        let output = matches.value_of("output").unwrap();
+        let mut file = output_file(matches)?.columns(columns.clone());
+        if !matches.is_present("append") {
+            // The file holds this run's filings and nothing else, even if there are none
+            file.write(&[])?;
+            file = file.append(true);
+        }
+        sink.push(&format!("output {}", output), Box::new(file));
+        if matches.is_present("stdout") {
+            sink.push("stdout", Box::new(StdoutSink::new(format).columns(columns)));
+        }
+    }
+    for url in matches.values_of("copy-to").into_iter().flatten() {
+        sink.push(
+            &sink_name("copy-to", url),
+            Box::new(PostgresSink::connect(url)?),
+        );
+    }
+    for url in matches.values_of("webhook").into_iter().flatten() {
+        let mut webhook = WebhookSink::new(url)?;
+        if let Some(secret) = matches.value_of("webhook-secret") {
+            webhook = webhook.secret(secret.as_bytes());
+        }
+        if let Some(path) = matches.value_of("dead-letter") {
+            webhook = webhook.dead_letter(path);
+        }
+        let name = sink_name("webhook", url);
+        match matches.values_of("webhook-item") {
+            Some(items) => sink.push(
+                &name,
+                Box::new(ItemFilter::new(webhook, items.map(String::from).collect())),
+            ),
+            None => sink.push(&name, Box::new(webhook)),
+        }
+    }
+    Ok(sink)
+}
+
+/// Names a sink by a digest of its URL, keeping any password or token in it out
+/// of the delivery queue
+fn sink_name(kind: &str, url: &str) -> String {
+    let digest = hex::encode(Sha256::digest(url.as_bytes()));
+    format!("{} {}", kind, &digest[..16])
+}
+
+fn output_file(matches: &ArgMatches) -> Result<OutputFile> {
+    let rotation = match matches.value_of("rotate") {
+        Some(rotate) => rotate.parse::<Rotation>()?,
+        None => Rotation::Never,
+    };
+    Ok(
+        OutputFile::new(matches.value_of("output").unwrap(), output_format(matches)?)
+            .append(matches.is_present("append"))
+            .rotation(rotation),
+    )
+}
+
+fn output_columns(matches: &ArgMatches, format: Format) -> Result<Columns> {
+    // `--columns` is global, so it may also follow the subcommand
+    let columns = matches
+        .subcommand()
+        .1
+        .and_then(|args| args.value_of("columns"))
+        .or_else(|| matches.value_of("columns"));
+    match (format, columns) {
+        (Format::Table(_), Some(spec)) => spec.parse::<Columns>(),
+        (_, Some(_)) => Err("--columns only applies to tsv and csv output".into()),
+        (_, None) => Ok(Columns::default()),
+    }
+}
+
+fn output_format(matches: &ArgMatches) -> Result<Format> {
+    let format = matches.value_of("format").unwrap().parse::<Format>()?;
+    match (format, matches.value_of("delimiter")) {
+        (Format::Table(_), Some(value)) => Ok(Format::Table(delimiter(value)?)),
+        (_, Some(_)) => Err("--delimiter only applies to tsv and csv output".into()),
+        (format, None) => Ok(format),
+    }
+}
+
+/// A single byte delimiter, accepting `\t` for a tab since shells make that awkward
+fn delimiter(value: &str) -> Result<u8> {
+    match value {
+        "\\t" => Ok(b'\t'),
+        v if v.len() == 1 => Ok(v.as_bytes()[0]),
+        _ => Err(format!("--delimiter should be a single character, not {:?}", value).into()),
+    }
+}
+
+fn db_command(conn: &DbConnection, matches: &ArgMatches) {
+    match matches.subcommand_name() {
+        Some("migrate") => {
+            run_pending(conn, &mut io::stdout()).expect("Could not migrate database");
+        }
+        Some("status") => {
+            for migration in status(conn).expect("Could not read migrations") {
+                let mark = if migration.applied { "X" } else { " " };
+                println!("[{}] {}", mark, migration.version);
+            }
+        }
+        Some("prune") => {
+            let args = matches.subcommand_matches("prune").unwrap();
+            let report = check_schema(conn)
+                .and_then(|_| retention_policy(args))
+                .and_then(|policy| prune(conn, &policy))
+                .unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                });
+            println!(
+                "Forgot {} accession numbers, archived {} filings",
+                report.forgotten, report.archived
+            );
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn retention_policy(args: &ArgMatches) -> Result<RetentionPolicy> {
+    let days = |name: &str| -> Result<Option<u32>> {
+        match args.value_of(name) {
+            Some(v) => {
+                Ok(Some(v.parse::<u32>().chain_err(|| {
+                    format!("--{} should be a number of days", name)
+                })?))
+            }
+            None => Ok(None),
+        }
+    };
+
+    Ok(RetentionPolicy {
+        seen_days: days("keep-days")?.unwrap_or(RetentionPolicy::default().seen_days),
+        archive_days: days("archive-after-days")?,
+    })
+}
+
+/// Prints the filings as a tab separated table, with `--columns` if given
+fn filings_command(conn: &DbConnection, args: &ArgMatches) {
+    let format = Format::Table(b'\t');
+    let page = output_columns(args, format)
+        .and_then(|columns| {
+            let page = filings_query(args)?.run(conn)?;
+            write_body(io::stdout(), &page.filings, format, &columns, true)?;
+            Ok(page)
+        })
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        });
+
+    if let Some(next) = page.next {
+        eprintln!("More filings follow, pass --after {}", next);
+    }
+}
+
+/// Prints each downloaded document like `sha256sum` would
+fn download_command(conn: &DbConnection, args: &ArgMatches) {
+    let result = download(args).and_then(|(archive, cik, acc, documents)| {
+        archive
+            .fetch_filing(conn, cik, acc, &documents)
+            .map(|fetched| (archive, fetched))
+    });
+    match result {
+        Ok((archive, fetched)) => {
+            for document in fetched {
+                println!(
+                    "{}  {}",
+                    document.sha256,
+                    archive.path_of(&document).display()
+                );
+            }
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn download(args: &ArgMatches) -> Result<(Archive, usize, usize, Vec<Document>)> {
+    let cik = args
+        .value_of("cik")
+        .unwrap()
+        .parse::<usize>()
+        .chain_err(|| "--cik should be a number")?;
+    let acc = args
+        .value_of("accession")
+        .unwrap()
+        .replace('-', "")
+        .parse::<usize>()
+        .chain_err(|| "--accession should be an accession number, e.g. 0001193125-18-000001")?;
+
+    let mut documents = args
+        .values_of("document")
+        .into_iter()
+        .flatten()
+        .map(|name| Document::Named(name.to_owned()))
+        .collect::<Vec<Document>>();
+    if documents.is_empty() || args.is_present("submission") {
+        documents.insert(0, Document::Submission);
+    }
+
+    let layout = args.value_of("layout").unwrap().parse::<Layout>()?;
+    let archive = Archive::new(args.value_of("dir").unwrap()).layout(layout);
+    Ok((archive, cik, acc, documents))
+}
+
+#[cfg(feature = "export")]
+fn export_command(conn: &DbConnection, args: &ArgMatches) {
+    use sec_scraper::export::{export, ExportFormat};
+
+    let result = filings_query(args).and_then(|query| {
+        let format = args.value_of("format").unwrap().parse::<ExportFormat>()?;
+        export(
+            conn,
+            query,
+            Path::new(args.value_of("dir").unwrap()),
+            format,
+        )
+    });
+    match result {
+        Ok(report) => println!(
+            "Exported {} filing(s) into {} partition(s)",
+            report.filings, report.partitions
+        ),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn filings_query(args: &ArgMatches) -> Result<FilingQuery> {
+    let number = |name: &str| -> Result<Option<usize>> {
+        match args.value_of(name) {
+            Some(v) => Ok(Some(
+                v.parse::<usize>()
+                    .chain_err(|| format!("--{} should be a number", name))?,
+            )),
+            None => Ok(None),
+        }
+    };
+
+    let mut query = FilingQuery::new().filed_between(number("from")?, number("to")?);
+    if let Some(cik) = number("cik")? {
+        query = query.cik(cik);
+    }
+    if let Some(form) = args.value_of("form") {
+        query = query.form_type(FilingType::which(form)?);
+    }
+    if let Some(form) = args.value_of("family") {
+        query = query.form_family(FilingType::which(form)?);
+    }
+    if let Some(name) = args.value_of("name") {
+        query = query.name_contains(name);
+    }
+    if let Some(role) = args.value_of("role") {
+        query = query.role(FilingRole::which(role)?);
+    }
+    for item in args.values_of("item").into_iter().flatten() {
+        query = query.item(item);
+    }
+    if let Some(limit) = number("limit")? {
+        query = query.limit(limit);
+    }
+    if let Some(cursor) = args.value_of("after") {
+        query = query.after(cursor.parse()?);
+    }
+    if args.is_present("oldest-first") {
+        query = query.order(FilingOrder::OldestFirst);
     }
+    Ok(query)
 }
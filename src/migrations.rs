@@ -0,0 +1,146 @@
+//! The migrations in `migrations/` (and `migrations_sqlite/` for SQLite) are compiled
+//! into the binary, so a database can be brought up to date without the diesel CLI.
+//!
+//! -> `db migrate` applies whatever is pending
+//! -> `db status` lists every migration and whether it has been applied
+//! -> scraping refuses to start until nothing is pending
+use crate::db::DbConnection;
+use crate::errors::*;
+use diesel_migrations::{run_migrations, setup_database, Migration, MigrationConnection};
+use std::io::Write;
+
+#[allow(dead_code)]
+mod embedded {
+    use diesel_migrations::Migration;
+
+    #[derive(EmbedMigrations)]
+    struct _Dummy;
+
+    pub fn all() -> &'static [&'static dyn Migration] {
+        ALL_MIGRATIONS
+    }
+}
+
+#[cfg(feature = "sqlite")]
+#[allow(dead_code)]
+mod embedded_sqlite {
+    use diesel_migrations::Migration;
+
+    #[derive(EmbedMigrations)]
+    #[embed_migrations_options(migrations_path = "migrations_sqlite")]
+    struct _Dummy;
+
+    pub fn all() -> &'static [&'static dyn Migration] {
+        ALL_MIGRATIONS
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub version: String,
+    pub applied: bool,
+}
+
+/// Lists every embedded migration in order, along with whether the database has
+/// already applied it.
+pub fn status(conn: &DbConnection) -> Result<Vec<MigrationStatus>> {
+    match conn {
+        DbConnection::Postgres(c) => status_of(c, embedded::all()),
+        #[cfg(feature = "sqlite")]
+        DbConnection::Sqlite(c) => status_of(c, embedded_sqlite::all()),
+    }
+}
+
+fn status_of<C: MigrationConnection>(
+    conn: &C,
+    migrations: &[&dyn Migration],
+) -> Result<Vec<MigrationStatus>> {
+    setup_database(conn).chain_err(|| "Unable to create migrations table")?;
+    let applied = conn
+        .previously_run_migration_versions()
+        .chain_err(|| "Unable to read applied migrations")?;
+
+    Ok(migrations
+        .iter()
+        .map(|m| MigrationStatus {
+            version: m.version().to_owned(),
+            applied: applied.contains(m.version()),
+        })
+        .collect::<Vec<MigrationStatus>>())
+}
+
+/// Applies every embedded migration the database hasn't seen yet, logging each
+/// one to `out`.
+pub fn run_pending(conn: &DbConnection, out: &mut dyn Write) -> Result<()> {
+    match conn {
+        DbConnection::Postgres(c) => run_migrations(c, embedded::all().iter().cloned(), out),
+        #[cfg(feature = "sqlite")]
+        DbConnection::Sqlite(c) => run_migrations(c, embedded_sqlite::all().iter().cloned(), out),
+    }
+    .chain_err(|| "Unable to run migrations")
+}
+
+/// Fails with a message naming the pending migrations if the database schema is
+/// older than the one this binary was built against.
+pub fn check_schema(conn: &DbConnection) -> Result<()> {
+    let pending = status(conn)?
+        .into_iter()
+        .filter(|m| !m.applied)
+        .map(|m| m.version)
+        .collect::<Vec<String>>();
+
+    if !pending.is_empty() {
+        bail!(
+            "Database schema is out of date, {} migration(s) pending ({}). \
+             Run `sec_scraper db migrate` to apply them.",
+            pending.len(),
+            pending.join(", ")
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod migrations_tests {
+    use super::*;
+    use crate::test_db::TestDb;
+
+    #[test]
+    fn migrated_schema_test() {
+        let conn = TestDb::new();
+        assert!(status(&conn).unwrap().iter().all(|m| m.applied));
+        assert!(check_schema(&conn).is_ok());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn migrated_sqlite_schema_test() {
+        let conn = TestDb::sqlite();
+        assert!(status(&conn).unwrap().iter().all(|m| m.applied));
+        assert!(check_schema(&conn).is_ok());
+    }
+
+    #[test]
+    fn out_of_date_schema_test() {
+        use diesel::connection::SimpleConnection;
+
+        let conn = TestDb::new();
+        let latest = status(&conn).unwrap().pop().unwrap().version;
+        conn.pg()
+            .batch_execute(&format!(
+                "DELETE FROM __diesel_schema_migrations WHERE version = '{}'",
+                latest
+            ))
+            .unwrap();
+
+        assert_eq!(
+            status(&conn).unwrap().pop(),
+            Some(MigrationStatus {
+                version: latest.clone(),
+                applied: false,
+            })
+        );
+        let err = check_schema(&conn).unwrap_err();
+        assert!(err.to_string().contains(&latest));
+    }
+}
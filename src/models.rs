@@ -1,7 +1,12 @@
 #![allow(proc_macro_derive_resolution_fallback)]
 
-use super::schema::{accession_numbers, test_accession_numbers};
+use super::schema::{
+    accession_numbers, beneficial_ownership, documents, filing_items, filing_parties, filings,
+    ownership_documents, ownership_footnotes, ownership_transactions, pending_deliveries,
+    reporting_owners, xbrl_facts,
+};
 use bigdecimal::BigDecimal;
+use chrono::NaiveDateTime;
 
 #[derive(Queryable, PartialEq, Eq, Debug)]
 pub struct AccessionNumber {
@@ -16,13 +21,166 @@ pub struct NewAccessionNumber {
 }
 
 #[derive(Queryable, PartialEq, Eq, Debug)]
-pub struct TestAccessionNumber {
-    pub id: i32,
-    pub accession_number: BigDecimal,
+pub struct Filing {
+    pub accession_number: i64,
+    pub form_type: String,
+    pub name: String,
+    pub cik: i64,
+    pub role: Option<String>,
+    pub filing_date: i32,
+    pub timestamp: String,
 }
 
 #[derive(Insertable)]
-#[table_name = "test_accession_numbers"]
-pub struct TestNewAccessionNumber {
-    pub accession_number: BigDecimal,
+#[table_name = "filings"]
+pub struct NewFiling<'a> {
+    pub accession_number: i64,
+    pub form_type: &'a str,
+    pub name: &'a str,
+    pub cik: i64,
+    pub role: Option<&'a str>,
+    pub filing_date: i32,
+    pub timestamp: &'a str,
+}
+
+#[derive(Insertable)]
+#[table_name = "filing_items"]
+pub struct NewFilingItem<'a> {
+    pub accession_number: i64,
+    pub item: &'a str,
+}
+
+#[derive(Insertable)]
+#[table_name = "filing_parties"]
+pub struct NewFilingParty<'a> {
+    pub accession_number: i64,
+    pub cik: i64,
+    pub name: &'a str,
+    pub role: Option<&'a str>,
+}
+
+#[derive(Insertable)]
+#[table_name = "pending_deliveries"]
+pub struct PendingDelivery<'a> {
+    pub sink: &'a str,
+    pub accession_number: i64,
+}
+
+#[derive(Queryable, PartialEq, Eq, Debug, Clone)]
+pub struct ArchivedDocument {
+    pub accession_number: i64,
+    pub filename: String,
+    pub cik: i64,
+    pub path: String,
+    pub sha256: String,
+    pub size: i64,
+    pub fetched_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[table_name = "documents"]
+pub struct NewArchivedDocument<'a> {
+    pub accession_number: i64,
+    pub filename: &'a str,
+    pub cik: i64,
+    pub path: &'a str,
+    pub sha256: &'a str,
+    pub size: i64,
+}
+
+#[derive(Queryable, Insertable, PartialEq, Eq, Debug)]
+#[table_name = "ownership_documents"]
+pub struct OwnershipDocumentRow {
+    pub accession_number: i64,
+    pub document_type: String,
+    pub period_of_report: Option<i32>,
+    pub issuer_cik: i64,
+    pub issuer_name: String,
+    pub trading_symbol: Option<String>,
+}
+
+#[derive(Queryable, Insertable, PartialEq, Eq, Debug)]
+#[table_name = "reporting_owners"]
+pub struct ReportingOwnerRow {
+    pub accession_number: i64,
+    pub cik: i64,
+    pub name: String,
+    pub is_director: bool,
+    pub is_officer: bool,
+    pub is_ten_percent_owner: bool,
+    pub is_other: bool,
+    pub officer_title: Option<String>,
+    pub position: i32,
+}
+
+#[derive(Queryable, Insertable, PartialEq, Debug)]
+#[table_name = "ownership_transactions"]
+pub struct OwnershipTransactionRow {
+    pub accession_number: i64,
+    pub position: i32,
+    pub derivative: bool,
+    pub security_title: String,
+    pub transaction_date: Option<i32>,
+    pub code: Option<String>,
+    pub shares: Option<f64>,
+    pub price: Option<f64>,
+    pub acquired_disposed: Option<String>,
+    pub shares_owned_after: Option<f64>,
+    pub direct: Option<bool>,
+    pub exercise_price: Option<f64>,
+    pub exercise_date: Option<i32>,
+    pub expiration_date: Option<i32>,
+    pub underlying_title: Option<String>,
+    pub underlying_shares: Option<f64>,
+    pub footnotes: String,
+}
+
+#[derive(Queryable, Insertable, PartialEq, Eq, Debug)]
+#[table_name = "ownership_footnotes"]
+pub struct OwnershipFootnoteRow {
+    pub accession_number: i64,
+    pub id: String,
+    pub text: String,
+}
+
+#[derive(Queryable, Insertable, PartialEq, Debug)]
+#[table_name = "beneficial_ownership"]
+pub struct BeneficialOwnershipRow {
+    pub accession_number: i64,
+    pub position: i32,
+    pub form_type: Option<String>,
+    pub structured: bool,
+    pub subject_cik: Option<i64>,
+    pub subject_name: Option<String>,
+    pub cusip: Option<String>,
+    pub class_title: Option<String>,
+    pub event_date: Option<i32>,
+    pub filer_cik: Option<i64>,
+    pub filer_name: Option<String>,
+    pub reporting_person: String,
+    pub reporting_person_cik: Option<i64>,
+    pub citizenship: Option<String>,
+    pub type_of_person: Option<String>,
+    pub sole_voting_power: Option<f64>,
+    pub shared_voting_power: Option<f64>,
+    pub sole_dispositive_power: Option<f64>,
+    pub shared_dispositive_power: Option<f64>,
+    pub aggregate_amount: Option<f64>,
+    pub percent_of_class: Option<f64>,
+}
+
+#[derive(Queryable, Insertable, PartialEq, Debug)]
+#[table_name = "xbrl_facts"]
+pub struct XbrlFactRow {
+    pub accession_number: i64,
+    pub position: i32,
+    pub concept: String,
+    pub context: String,
+    pub unit: Option<String>,
+    pub period_start: Option<i32>,
+    pub period_end: Option<i32>,
+    pub dimensions: String,
+    pub value: String,
+    pub number: Option<f64>,
+    pub decimals: Option<String>,
 }
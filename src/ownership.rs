@@ -0,0 +1,675 @@
+//! Reads the ownership XML of Forms 3, 4 and 5, where insiders report what they
+//! hold in a company and what they bought and sold
+//!
+//! -> `parse` reads an `ownershipDocument` into an `OwnershipDocument`
+//! -> `from_submission` and `fetch` find it among a filing's documents
+//! -> `write` and `load` keep it in the `ownership_documents`, `reporting_owners`,
+//!    `ownership_transactions` and `ownership_footnotes` tables
+//!
+//! Both tables of the form end up in `transactions`. Holdings reported without a
+//! transaction (all of a Form 3's) have no date or code.
+use crate::db::{on_backend, DbConnection};
+use crate::errors::*;
+use crate::models::{
+    OwnershipDocumentRow, OwnershipFootnoteRow, OwnershipTransactionRow, ReportingOwnerRow,
+};
+use crate::schema::{
+    ownership_documents, ownership_footnotes, ownership_transactions, reporting_owners,
+};
+use crate::sec_entry::SECEntry;
+use crate::submission::{self, Submission};
+use crate::values::{self, from_number, to_number};
+use crate::xml_tree::{self, Element};
+use chrono::NaiveDate;
+use diesel::prelude::*;
+use std::io::BufRead;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct OwnershipDocument {
+    /// `3`, `4`, `4/A`, `5`, ...
+    pub document_type: String,
+    pub period_of_report: Option<NaiveDate>,
+    pub issuer: Issuer,
+    pub reporting_owners: Vec<ReportingOwner>,
+    pub transactions: Vec<Transaction>,
+    pub footnotes: Vec<Footnote>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Issuer {
+    pub cik: usize,
+    pub name: String,
+    pub trading_symbol: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct ReportingOwner {
+    pub cik: usize,
+    pub name: String,
+    pub is_director: bool,
+    pub is_officer: bool,
+    pub is_ten_percent_owner: bool,
+    pub is_other: bool,
+    pub officer_title: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum AcquiredDisposed {
+    Acquired,
+    Disposed,
+}
+
+impl AcquiredDisposed {
+    fn which(code: &str) -> Option<AcquiredDisposed> {
+        match code {
+            "A" => Some(AcquiredDisposed::Acquired),
+            "D" => Some(AcquiredDisposed::Disposed),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AcquiredDisposed::Acquired => "A",
+            AcquiredDisposed::Disposed => "D",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Transaction {
+    /// From the derivative table (options, warrants, units, ...)
+    pub derivative: bool,
+    pub security_title: String,
+    pub date: Option<NaiveDate>,
+    /// e.g. `P` for a purchase, `S` for a sale or `M` for exercising an option
+    pub code: Option<String>,
+    pub shares: Option<f64>,
+    pub price: Option<f64>,
+    pub acquired_disposed: Option<AcquiredDisposed>,
+    pub shares_owned_after: Option<f64>,
+    /// Held directly rather than through e.g. a trust
+    pub direct: Option<bool>,
+    pub exercise_price: Option<f64>,
+    pub exercise_date: Option<NaiveDate>,
+    pub expiration_date: Option<NaiveDate>,
+    pub underlying_title: Option<String>,
+    pub underlying_shares: Option<f64>,
+    /// Ids of the footnotes anywhere in the row, e.g. `F1`
+    pub footnote_ids: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Footnote {
+    pub id: String,
+    pub text: String,
+}
+
+pub fn parse(xml: &str) -> Result<OwnershipDocument> {
+    let root = xml_tree::parse(xml)?;
+    if root.name != "ownershipDocument" {
+        bail!("Expected an ownershipDocument, not {}", root.name);
+    }
+
+    let issuer = root
+        .child("issuer")
+        .chain_err(|| "Ownership document has no issuer")?;
+    let issuer = Issuer {
+        cik: cik(issuer.text_at(&["issuerCik"]))?,
+        name: issuer.text_at(&["issuerName"]).unwrap_or_default(),
+        trading_symbol: issuer.text_at(&["issuerTradingSymbol"]),
+    };
+
+    let reporting_owners = root
+        .children("reportingOwner")
+        .map(reporting_owner)
+        .collect::<Result<Vec<ReportingOwner>>>()?;
+
+    let mut transactions = Vec::new();
+    for (table, derivative) in &[("nonDerivativeTable", false), ("derivativeTable", true)] {
+        if let Some(table) = root.child(table) {
+            transactions.extend(
+                table
+                    .children
+                    .iter()
+                    .map(|row| transaction(row, *derivative)),
+            );
+        }
+    }
+
+    let footnotes = root
+        .find(&["footnotes"])
+        .map(|f| {
+            f.children("footnote")
+                .map(|n| Footnote {
+                    id: n.attribute("id").unwrap_or_default().to_owned(),
+                    text: n.trimmed_text().unwrap_or_default(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(OwnershipDocument {
+        document_type: root
+            .text_at(&["documentType"])
+            .chain_err(|| "Ownership document has no documentType")?,
+        period_of_report: date(root.text_at(&["periodOfReport"])),
+        issuer,
+        reporting_owners,
+        transactions,
+        footnotes,
+    })
+}
+
+/// Parses the first ownership document among a complete submission's documents
+pub fn from_submission<R: BufRead>(submission: Submission<R>) -> Result<Option<OwnershipDocument>> {
+    for document in submission {
+        let text = document?.text();
+        if text.contains("<ownershipDocument") {
+            return parse(&text).map(Some);
+        }
+    }
+    Ok(None)
+}
+
+/// Downloads `entry`'s complete submission and parses its ownership document
+pub fn fetch(entry: &SECEntry) -> Result<OwnershipDocument> {
+    from_submission(submission::fetch(entry)?)?.chain_err(|| {
+        format!(
+            "No ownership document in {}",
+            SECEntry::dashed_accession(entry.accession_number())
+        )
+    })
+}
+
+fn reporting_owner(owner: &Element) -> Result<ReportingOwner> {
+    let flag = |name: &str| {
+        owner
+            .text_at(&["reportingOwnerRelationship", name])
+            .is_some_and(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+    };
+    Ok(ReportingOwner {
+        cik: cik(owner.text_at(&["reportingOwnerId", "rptOwnerCik"]))?,
+        name: owner
+            .text_at(&["reportingOwnerId", "rptOwnerName"])
+            .unwrap_or_default(),
+        is_director: flag("isDirector"),
+        is_officer: flag("isOfficer"),
+        is_ten_percent_owner: flag("isTenPercentOwner"),
+        is_other: flag("isOther"),
+        officer_title: owner.text_at(&["reportingOwnerRelationship", "officerTitle"]),
+    })
+}
+
+fn transaction(row: &Element, derivative: bool) -> Transaction {
+    let number = |path: &[&str]| value(row, path).and_then(|v| values::number(&v));
+
+    let mut footnote_ids = Vec::new();
+    for id in row
+        .descendants("footnoteId")
+        .into_iter()
+        .filter_map(|f| f.attribute("id"))
+    {
+        if !footnote_ids.iter().any(|i| i == id) {
+            footnote_ids.push(id.to_owned());
+        }
+    }
+
+    Transaction {
+        derivative,
+        security_title: value(row, &["securityTitle"]).unwrap_or_default(),
+        date: date(value(row, &["transactionDate"])),
+        code: row.text_at(&["transactionCoding", "transactionCode"]),
+        shares: number(&["transactionAmounts", "transactionShares"]),
+        price: number(&["transactionAmounts", "transactionPricePerShare"]),
+        acquired_disposed: value(
+            row,
+            &["transactionAmounts", "transactionAcquiredDisposedCode"],
+        )
+        .and_then(|c| AcquiredDisposed::which(&c)),
+        shares_owned_after: number(&["postTransactionAmounts", "sharesOwnedFollowingTransaction"]),
+        direct: value(row, &["ownershipNature", "directOrIndirectOwnership"]).map(|v| v == "D"),
+        exercise_price: number(&["conversionOrExercisePrice"]),
+        exercise_date: date(value(row, &["exerciseDate"])),
+        expiration_date: date(value(row, &["expirationDate"])),
+        underlying_title: value(row, &["underlyingSecurity", "underlyingSecurityTitle"]),
+        underlying_shares: number(&["underlyingSecurity", "underlyingSecurityShares"]),
+        footnote_ids,
+    }
+}
+
+/// Most fields wrap what they hold in a `<value>`, next to any footnotes
+fn value(element: &Element, path: &[&str]) -> Option<String> {
+    let field = element.find(path)?;
+    field.text_at(&["value"]).or_else(|| field.trimmed_text())
+}
+
+fn cik(text: Option<String>) -> Result<usize> {
+    text.as_ref()
+        .and_then(|c| c.parse::<usize>().ok())
+        .chain_err(|| format!("{:?} is not a CIK", text))
+}
+
+/// Dates are `yyyy-mm-dd`, sometimes with a UTC offset after them
+fn date(text: Option<String>) -> Option<NaiveDate> {
+    text.and_then(|t| values::date(&t))
+}
+
+/// Stores `document` as the ownership document of filing `acc`, replacing any
+/// stored before
+pub fn write(conn: &DbConnection, acc: usize, document: &OwnershipDocument) -> Result<()> {
+    let acc = acc as i64;
+    let row = OwnershipDocumentRow {
+        accession_number: acc,
+        document_type: document.document_type.clone(),
+        period_of_report: document.period_of_report.map(to_number),
+        issuer_cik: document.issuer.cik as i64,
+        issuer_name: document.issuer.name.clone(),
+        trading_symbol: document.issuer.trading_symbol.clone(),
+    };
+    let owners = document
+        .reporting_owners
+        .iter()
+        .enumerate()
+        .map(|(i, o)| ReportingOwnerRow {
+            accession_number: acc,
+            cik: o.cik as i64,
+            name: o.name.clone(),
+            is_director: o.is_director,
+            is_officer: o.is_officer,
+            is_ten_percent_owner: o.is_ten_percent_owner,
+            is_other: o.is_other,
+            officer_title: o.officer_title.clone(),
+            position: i as i32,
+        })
+        .collect::<Vec<ReportingOwnerRow>>();
+    let transactions = document
+        .transactions
+        .iter()
+        .enumerate()
+        .map(|(i, t)| OwnershipTransactionRow {
+            accession_number: acc,
+            position: i as i32,
+            derivative: t.derivative,
+            security_title: t.security_title.clone(),
+            transaction_date: t.date.map(to_number),
+            code: t.code.clone(),
+            shares: t.shares,
+            price: t.price,
+            acquired_disposed: t.acquired_disposed.map(|a| a.as_str().to_owned()),
+            shares_owned_after: t.shares_owned_after,
+            direct: t.direct,
+            exercise_price: t.exercise_price,
+            exercise_date: t.exercise_date.map(to_number),
+            expiration_date: t.expiration_date.map(to_number),
+            underlying_title: t.underlying_title.clone(),
+            underlying_shares: t.underlying_shares,
+            footnotes: t.footnote_ids.join(","),
+        })
+        .collect::<Vec<OwnershipTransactionRow>>();
+    let footnotes = document
+        .footnotes
+        .iter()
+        .map(|f| OwnershipFootnoteRow {
+            accession_number: acc,
+            id: f.id.clone(),
+            text: f.text.clone(),
+        })
+        .collect::<Vec<OwnershipFootnoteRow>>();
+
+    conn.transaction(|| {
+        delete(conn, acc)?;
+        on_backend!(conn, c => {
+            diesel::insert_into(ownership_documents::table)
+                .values(&row)
+                .execute(c)?;
+            diesel::insert_into(reporting_owners::table)
+                .values(&owners)
+                .execute(c)?;
+            diesel::insert_into(ownership_transactions::table)
+                .values(&transactions)
+                .execute(c)?;
+            diesel::insert_into(ownership_footnotes::table)
+                .values(&footnotes)
+                .execute(c)
+        })
+        .chain_err(|| "Unable to write ownership document")?;
+        Ok(())
+    })
+}
+
+fn delete(conn: &DbConnection, acc: i64) -> Result<()> {
+    on_backend!(conn, c => {
+        diesel::delete(
+            ownership_documents::table.filter(ownership_documents::accession_number.eq(acc)),
+        )
+        .execute(c)?;
+        diesel::delete(reporting_owners::table.filter(reporting_owners::accession_number.eq(acc)))
+            .execute(c)?;
+        diesel::delete(
+            ownership_transactions::table
+                .filter(ownership_transactions::accession_number.eq(acc)),
+        )
+        .execute(c)?;
+        diesel::delete(
+            ownership_footnotes::table.filter(ownership_footnotes::accession_number.eq(acc)),
+        )
+        .execute(c)
+    })
+    .chain_err(|| "Unable to replace ownership document")?;
+    Ok(())
+}
+
+/// The ownership document stored for filing `acc`, if there is one
+pub fn load(conn: &DbConnection, acc: usize) -> Result<Option<OwnershipDocument>> {
+    let acc = acc as i64;
+    let (row, owners, transactions, footnotes) = on_backend!(conn, c => {
+        let row = ownership_documents::table
+            .find(acc)
+            .first::<OwnershipDocumentRow>(c)
+            .optional()?;
+        let owners = reporting_owners::table
+            .filter(reporting_owners::accession_number.eq(acc))
+            .order((reporting_owners::position, reporting_owners::cik))
+            .load::<ReportingOwnerRow>(c)?;
+        let transactions = ownership_transactions::table
+            .filter(ownership_transactions::accession_number.eq(acc))
+            .order(ownership_transactions::position)
+            .load::<OwnershipTransactionRow>(c)?;
+        let footnotes = ownership_footnotes::table
+            .filter(ownership_footnotes::accession_number.eq(acc))
+            .order(ownership_footnotes::id)
+            .load::<OwnershipFootnoteRow>(c)?;
+        Ok::<_, diesel::result::Error>((row, owners, transactions, footnotes))
+    })
+    .chain_err(|| "Unable to load ownership document")?;
+
+    let row = match row {
+        Some(row) => row,
+        None => return Ok(None),
+    };
+    Ok(Some(OwnershipDocument {
+        document_type: row.document_type,
+        period_of_report: row.period_of_report.and_then(from_number),
+        issuer: Issuer {
+            cik: row.issuer_cik as usize,
+            name: row.issuer_name,
+            trading_symbol: row.trading_symbol,
+        },
+        reporting_owners: owners
+            .into_iter()
+            .map(|o| ReportingOwner {
+                cik: o.cik as usize,
+                name: o.name,
+                is_director: o.is_director,
+                is_officer: o.is_officer,
+                is_ten_percent_owner: o.is_ten_percent_owner,
+                is_other: o.is_other,
+                officer_title: o.officer_title,
+            })
+            .collect(),
+        transactions: transactions
+            .into_iter()
+            .map(|t| Transaction {
+                derivative: t.derivative,
+                security_title: t.security_title,
+                date: t.transaction_date.and_then(from_number),
+                code: t.code,
+                shares: t.shares,
+                price: t.price,
+                acquired_disposed: t
+                    .acquired_disposed
+                    .and_then(|a| AcquiredDisposed::which(&a)),
+                shares_owned_after: t.shares_owned_after,
+                direct: t.direct,
+                exercise_price: t.exercise_price,
+                exercise_date: t.exercise_date.and_then(from_number),
+                expiration_date: t.expiration_date.and_then(from_number),
+                underlying_title: t.underlying_title,
+                underlying_shares: t.underlying_shares,
+                footnote_ids: t
+                    .footnotes
+                    .split(',')
+                    .filter(|f| !f.is_empty())
+                    .map(str::to_owned)
+                    .collect(),
+            })
+            .collect(),
+        footnotes: footnotes
+            .into_iter()
+            .map(|f| Footnote {
+                id: f.id,
+                text: f.text,
+            })
+            .collect(),
+    }))
+}
+
+#[cfg(test)]
+mod ownership_tests {
+    use super::*;
+    use crate::test_db::TestDb;
+    use std::io::Cursor;
+
+    const FORM4: &str = r#"<?xml version="1.0"?>
+<ownershipDocument>
+    <schemaVersion>X0306</schemaVersion>
+    <documentType>4</documentType>
+    <periodOfReport>2018-08-30</periodOfReport>
+    <issuer>
+        <issuerCik>0001660134</issuerCik>
+        <issuerName>Okta, Inc.</issuerName>
+        <issuerTradingSymbol>OKTA</issuerTradingSymbol>
+    </issuer>
+    <reportingOwner>
+        <reportingOwnerId>
+            <rptOwnerCik>0001655081</rptOwnerCik>
+            <rptOwnerName>Wang Janet</rptOwnerName>
+        </reportingOwnerId>
+        <reportingOwnerRelationship>
+            <isDirector>0</isDirector>
+            <isOfficer>1</isOfficer>
+            <isTenPercentOwner>0</isTenPercentOwner>
+            <isOther>0</isOther>
+            <officerTitle>Chief Accounting Officer</officerTitle>
+        </reportingOwnerRelationship>
+    </reportingOwner>
+    <nonDerivativeTable>
+        <nonDerivativeTransaction>
+            <securityTitle><value>Class A Common Stock</value></securityTitle>
+            <transactionDate><value>2018-08-30</value></transactionDate>
+            <transactionCoding>
+                <transactionFormType>4</transactionFormType>
+                <transactionCode>S</transactionCode>
+                <equitySwapInvolved>0</equitySwapInvolved>
+                <footnoteId id="F1"/>
+            </transactionCoding>
+            <transactionAmounts>
+                <transactionShares><value>2,500</value></transactionShares>
+                <transactionPricePerShare>
+                    <value>61.05</value>
+                    <footnoteId id="F2"/>
+                </transactionPricePerShare>
+                <transactionAcquiredDisposedCode><value>D</value></transactionAcquiredDisposedCode>
+            </transactionAmounts>
+            <postTransactionAmounts>
+                <sharesOwnedFollowingTransaction><value>61234</value></sharesOwnedFollowingTransaction>
+            </postTransactionAmounts>
+            <ownershipNature>
+                <directOrIndirectOwnership><value>D</value></directOrIndirectOwnership>
+            </ownershipNature>
+        </nonDerivativeTransaction>
+        <nonDerivativeHolding>
+            <securityTitle><value>Class B Common Stock</value></securityTitle>
+            <postTransactionAmounts>
+                <sharesOwnedFollowingTransaction><value>1000</value></sharesOwnedFollowingTransaction>
+            </postTransactionAmounts>
+            <ownershipNature>
+                <directOrIndirectOwnership><value>I</value></directOrIndirectOwnership>
+                <natureOfOwnership><value>By trust</value></natureOfOwnership>
+            </ownershipNature>
+        </nonDerivativeHolding>
+    </nonDerivativeTable>
+    <derivativeTable>
+        <derivativeTransaction>
+            <securityTitle><value>Stock Option (right to buy)</value></securityTitle>
+            <conversionOrExercisePrice><value>4.12</value></conversionOrExercisePrice>
+            <transactionDate><value>2018-08-30-04:00</value></transactionDate>
+            <transactionCoding>
+                <transactionFormType>4</transactionFormType>
+                <transactionCode>M</transactionCode>
+            </transactionCoding>
+            <transactionAmounts>
+                <transactionShares><value>2500</value></transactionShares>
+                <transactionPricePerShare><value>0</value></transactionPricePerShare>
+                <transactionAcquiredDisposedCode><value>D</value></transactionAcquiredDisposedCode>
+            </transactionAmounts>
+            <exerciseDate><footnoteId id="F1"/></exerciseDate>
+            <expirationDate><value>2026-02-18</value></expirationDate>
+            <underlyingSecurity>
+                <underlyingSecurityTitle><value>Class B Common Stock</value></underlyingSecurityTitle>
+                <underlyingSecurityShares><value>2500</value></underlyingSecurityShares>
+            </underlyingSecurity>
+            <postTransactionAmounts>
+                <sharesOwnedFollowingTransaction><value>10000</value></sharesOwnedFollowingTransaction>
+            </postTransactionAmounts>
+            <ownershipNature>
+                <directOrIndirectOwnership><value>D</value></directOrIndirectOwnership>
+            </ownershipNature>
+        </derivativeTransaction>
+    </derivativeTable>
+    <footnotes>
+        <footnote id="F1">Sold under a Rule 10b5-1 trading plan.</footnote>
+        <footnote id="F2">Weighted average price.</footnote>
+    </footnotes>
+</ownershipDocument>
+"#;
+
+    #[test]
+    fn parse_test() {
+        let document = parse(FORM4).unwrap();
+        assert_eq!(document.document_type, "4");
+        assert_eq!(
+            document.period_of_report,
+            NaiveDate::from_ymd_opt(2018, 8, 30)
+        );
+        assert_eq!(
+            document.issuer,
+            Issuer {
+                cik: 1660134,
+                name: String::from("Okta, Inc."),
+                trading_symbol: Some(String::from("OKTA")),
+            }
+        );
+        assert_eq!(
+            document.reporting_owners,
+            vec![ReportingOwner {
+                cik: 1655081,
+                name: String::from("Wang Janet"),
+                is_officer: true,
+                officer_title: Some(String::from("Chief Accounting Officer")),
+                ..ReportingOwner::default()
+            }]
+        );
+        assert_eq!(document.footnotes.len(), 2);
+        assert_eq!(document.footnotes[1].text, "Weighted average price.");
+    }
+
+    #[test]
+    fn transactions_test() {
+        let document = parse(FORM4).unwrap();
+        assert_eq!(document.transactions.len(), 3);
+
+        assert_eq!(
+            document.transactions[0],
+            Transaction {
+                derivative: false,
+                security_title: String::from("Class A Common Stock"),
+                date: NaiveDate::from_ymd_opt(2018, 8, 30),
+                code: Some(String::from("S")),
+                shares: Some(2500.0),
+                price: Some(61.05),
+                acquired_disposed: Some(AcquiredDisposed::Disposed),
+                shares_owned_after: Some(61234.0),
+                direct: Some(true),
+                footnote_ids: vec![String::from("F1"), String::from("F2")],
+                ..Transaction::default()
+            }
+        );
+
+        let holding = &document.transactions[1];
+        assert_eq!(holding.date, None);
+        assert_eq!(holding.code, None);
+        assert_eq!(holding.direct, Some(false));
+
+        let option = &document.transactions[2];
+        assert!(option.derivative);
+        assert_eq!(option.date, NaiveDate::from_ymd_opt(2018, 8, 30));
+        assert_eq!(option.exercise_price, Some(4.12));
+        assert_eq!(option.exercise_date, None);
+        assert_eq!(option.expiration_date, NaiveDate::from_ymd_opt(2026, 2, 18));
+        assert_eq!(
+            option.underlying_title,
+            Some(String::from("Class B Common Stock"))
+        );
+        assert_eq!(option.underlying_shares, Some(2500.0));
+        assert_eq!(option.footnote_ids, vec![String::from("F1")]);
+    }
+
+    #[test]
+    fn malformed_test() {
+        assert!(parse("<edgarSubmission/>").is_err());
+        assert!(
+            parse("<ownershipDocument><documentType>4</documentType></ownershipDocument>").is_err()
+        );
+    }
+
+    #[test]
+    fn from_submission_test() {
+        let text = format!(
+            "<SEC-DOCUMENT>\n<SEC-HEADER>\nACCESSION NUMBER:\t0001209191-18-047000\n</SEC-HEADER>\n\
+             <DOCUMENT>\n<TYPE>4\n<SEQUENCE>1\n<FILENAME>doc4.xml\n<TEXT>\n<XML>\n{}</XML>\n</TEXT>\n\
+             </DOCUMENT>\n</SEC-DOCUMENT>\n",
+            FORM4
+        );
+        let submission = Submission::new(Cursor::new(text.as_bytes())).unwrap();
+        assert_eq!(
+            from_submission(submission).unwrap(),
+            Some(parse(FORM4).unwrap())
+        );
+    }
+
+    fn store_test(conn: &DbConnection) {
+        let mut document = parse(FORM4).unwrap();
+        assert_eq!(load(conn, 120919118047000).unwrap(), None);
+
+        // Owners come back in the form's order, not by CIK
+        document.reporting_owners.push(ReportingOwner {
+            cik: 1000232,
+            name: String::from("Okta Holdings LLC"),
+            is_ten_percent_owner: true,
+            ..ReportingOwner::default()
+        });
+
+        write(conn, 120919118047000, &document).unwrap();
+        assert_eq!(load(conn, 120919118047000).unwrap(), Some(document.clone()));
+
+        // Writing it again replaces it
+        let mut amended = document;
+        amended.document_type = String::from("4/A");
+        amended.transactions.truncate(1);
+        write(conn, 120919118047000, &amended).unwrap();
+        assert_eq!(load(conn, 120919118047000).unwrap(), Some(amended));
+    }
+
+    #[test]
+    fn postgres_store_test() {
+        store_test(&TestDb::new());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_store_test() {
+        store_test(&TestDb::sqlite());
+    }
+}
@@ -1,5 +1,6 @@
-use super::models::{AccessionNumber, NewAccessionNumber};
+use super::models::{AccessionNumber, NewAccessionNumber, NewArchivedDocument};
 use bigdecimal::*;
+use chrono::NaiveDateTime;
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use dotenv::dotenv;
@@ -17,10 +18,10 @@ pub fn establish_connection(url: &str) -> PgConnection {
     }
 }
 
-pub fn write_number(
-    conn: &PgConnection,
-    acc_number: usize,
-) -> Result<usize, diesel::result::Error> {
+/// Records an accession number as seen, returning `true` if it was not already
+/// in the table. The unique index on `accession_number` makes this a single
+/// atomic check-and-insert, so concurrent scrapers never store duplicates.
+pub fn write_number(conn: &PgConnection, acc_number: usize) -> Result<bool, diesel::result::Error> {
     use super::schema::accession_numbers;
 
     let new_post = NewAccessionNumber {
@@ -29,6 +30,74 @@ pub fn write_number(
 
     diesel::insert_into(accession_numbers::table)
         .values(&new_post)
+        .on_conflict_do_nothing()
+        .returning(accession_numbers::id)
+        .get_result::<i32>(conn)
+        .optional()
+        .map(|inserted| inserted.is_some())
+}
+
+/// Records a page's worth of accession numbers with a single multi-row insert,
+/// returning the ones that had not been seen before.
+pub fn write_numbers(
+    conn: &PgConnection,
+    acc_numbers: &[usize],
+) -> Result<Vec<usize>, diesel::result::Error> {
+    use super::schema::accession_numbers;
+
+    if acc_numbers.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let new_posts = acc_numbers
+        .iter()
+        .map(|&acc| NewAccessionNumber {
+            accession_number: BigDecimal::from(acc as u64),
+        })
+        .collect::<Vec<NewAccessionNumber>>();
+
+    diesel::insert_into(accession_numbers::table)
+        .values(&new_posts)
+        .on_conflict_do_nothing()
+        .returning(accession_numbers::accession_number)
+        .get_results::<BigDecimal>(conn)
+        .map(|c| {
+            c.into_iter()
+                .filter_map(|x| x.to_u64())
+                .map(|x| x as usize)
+                .collect::<Vec<usize>>()
+        })
+}
+
+/// Forgets accession numbers first seen before `cutoff`, returning how many went
+pub fn delete_numbers_seen_before(
+    conn: &PgConnection,
+    cutoff: NaiveDateTime,
+) -> Result<usize, diesel::result::Error> {
+    use super::schema::accession_numbers::dsl::*;
+
+    diesel::delete(accession_numbers.filter(seen_at.lt(cutoff))).execute(conn)
+}
+
+/// Records a downloaded document, replacing what was known about an earlier download
+pub fn write_document(
+    conn: &PgConnection,
+    document: &NewArchivedDocument,
+) -> Result<usize, diesel::result::Error> {
+    use super::schema::documents::dsl::*;
+    use diesel::pg::upsert::excluded;
+
+    diesel::insert_into(documents)
+        .values(document)
+        .on_conflict((accession_number, filename))
+        .do_update()
+        .set((
+            cik.eq(excluded(cik)),
+            path.eq(excluded(path)),
+            sha256.eq(excluded(sha256)),
+            size.eq(excluded(size)),
+            fetched_at.eq(excluded(fetched_at)),
+        ))
         .execute(conn)
 }
 
@@ -43,6 +112,7 @@ pub fn get_number(conn: &PgConnection, acc: usize) -> Option<Vec<AccessionNumber
     use super::schema::accession_numbers::dsl::*;
 
     accession_numbers
+        .select((id, accession_number))
         .filter(accession_number.eq(BigDecimal::from(acc as u64)))
         .limit(5)
         .load::<AccessionNumber>(conn)
@@ -54,6 +124,7 @@ pub fn get_numbers(conn: &PgConnection) -> Option<Vec<BigDecimal>> {
     use super::schema::accession_numbers::dsl::*;
 
     accession_numbers
+        .select((id, accession_number))
         .limit(5)
         .load::<AccessionNumber>(conn)
         .ok()
@@ -68,6 +139,7 @@ pub fn get_posts(conn: &PgConnection) {
     use super::schema::accession_numbers::dsl::*;
 
     let results = accession_numbers
+        .select((id, accession_number))
         .limit(5)
         .load::<AccessionNumber>(conn)
         .expect("Error loading posts");
@@ -85,49 +157,10 @@ pub fn delete_all_posts(conn: &PgConnection) {
         .expect("Error deleting posts");
 }
 
-pub fn test_write_number(
-    conn: &PgConnection,
-    acc_number: usize,
-) -> Result<usize, diesel::result::Error> {
-    use super::schema::test_accession_numbers;
-    use crate::models::TestNewAccessionNumber;
-
-    let new_post = TestNewAccessionNumber {
-        accession_number: BigDecimal::from(acc_number as u64),
-    };
-
-    diesel::insert_into(test_accession_numbers::table)
-        .values(&new_post)
-        .execute(conn)
-}
-
-pub fn test_delete_all_posts(conn: &PgConnection) {
-    use super::schema::test_accession_numbers::dsl::*;
-
-    diesel::delete(test_accession_numbers)
-        .execute(conn)
-        .expect("Error deleting posts");
-}
-
-pub fn test_get_numbers(conn: &PgConnection) -> Option<Vec<BigDecimal>> {
-    use super::schema::test_accession_numbers::dsl::*;
-    use crate::models::TestAccessionNumber;
-
-    test_accession_numbers
-        .limit(5)
-        .load::<TestAccessionNumber>(conn)
-        .ok()
-        .map(|c| {
-            c.into_iter()
-                .map(|x| x.accession_number)
-                .collect::<Vec<BigDecimal>>()
-        })
-}
-
 #[cfg(test)]
 mod postgres_tests {
     use super::*;
-    use crate::models::TestAccessionNumber;
+    use crate::test_db::TestDb;
     use bigdecimal::BigDecimal;
     #[test]
     fn connection_test() {
@@ -137,49 +170,70 @@ mod postgres_tests {
 
     #[test]
     fn write_test() {
-        use crate::schema::test_accession_numbers::dsl::*;
-        let conn = establish_connection("");
-        test_delete_all_posts(&conn);
-        assert!(test_write_number(&conn, 6).is_ok());
-        let results = test_accession_numbers
+        use crate::schema::accession_numbers::dsl::*;
+        let db = TestDb::new();
+        let conn = db.pg();
+        assert!(write_number(conn, 6).unwrap());
+        let results = accession_numbers
+            .select((id, accession_number))
             .limit(1)
-            .load::<TestAccessionNumber>(&conn)
+            .load::<AccessionNumber>(conn)
             .expect("Error loading posts");
         let b = results
             .iter()
             .any(|a| a.accession_number == BigDecimal::from(6));
-        test_delete_all_posts(&conn);
         assert!(b);
     }
 
     #[test]
-    fn delete_test() {
-        use crate::schema::test_accession_numbers::dsl::*;
+    fn write_twice_test() {
+        let db = TestDb::new();
+        let conn = db.pg();
+        assert!(write_number(conn, 6).unwrap());
+        assert!(!write_number(conn, 6).unwrap());
+        assert_eq!(get_number(conn, 6).unwrap().len(), 1);
+    }
 
-        let conn = establish_connection("");
-        assert!(test_write_number(&conn, 6).is_ok());
-        test_delete_all_posts(&conn);
-        let results = test_accession_numbers
+    #[test]
+    fn write_numbers_test() {
+        let db = TestDb::new();
+        let conn = db.pg();
+        assert!(write_number(conn, 2).unwrap());
+        let mut unseen = write_numbers(conn, &[1, 2, 3]).unwrap();
+        unseen.sort();
+        assert_eq!(unseen, vec![1, 3]);
+        assert_eq!(write_numbers(conn, &[]).unwrap(), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn delete_test() {
+        use crate::schema::accession_numbers::dsl::*;
+
+        let db = TestDb::new();
+        let conn = db.pg();
+        assert!(write_number(conn, 6).is_ok());
+        delete_all_posts(conn);
+        let results = accession_numbers
+            .select((id, accession_number))
             .limit(1)
-            .load::<TestAccessionNumber>(&conn)
+            .load::<AccessionNumber>(conn)
             .expect("Error Loading posts");
         assert!(results.iter().next().is_none());
     }
 
     #[test]
     fn get_numbers_test() {
-        let conn = establish_connection("");
-        test_delete_all_posts(&conn);
+        let db = TestDb::new();
+        let conn = db.pg();
 
-        assert!(test_write_number(&conn, 1).is_ok());
-        assert!(test_write_number(&conn, 2).is_ok());
-        assert!(test_write_number(&conn, 3).is_ok());
+        assert!(write_number(conn, 1).is_ok());
+        assert!(write_number(conn, 2).is_ok());
+        assert!(write_number(conn, 3).is_ok());
         let v = vec![1, 2, 3];
         let v = v
             .into_iter()
             .map(|x| BigDecimal::from(x))
             .collect::<Vec<BigDecimal>>();
-        assert_eq!(v, test_get_numbers(&conn).unwrap());
-        test_delete_all_posts(&conn);
+        assert_eq!(v, get_numbers(conn).unwrap());
     }
 }
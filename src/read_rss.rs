@@ -1,6 +1,7 @@
-use crate::models::AccessionNumber;
-use crate::postgres::*;
-use crate::sec_entry::{FilingType, SECEntry};
+use crate::db::*;
+use crate::filings::{write_filings, write_parties};
+use crate::sec_entry::{FilingRole, FilingType, SECEntry};
+use lazy_static::lazy_static;
 use regex::Regex;
 use std::collections::HashSet;
 use xml::reader::{EventReader, XmlEvent};
@@ -57,8 +58,9 @@ pub fn clean_xml(xml: Vec<String>, ignore: HashSet<FilingType>) -> Result<Vec<SE
     // Routine for every 4 entries
     let mut element_it = xml.iter();
     for _ in xml.iter().step_by(NUM_ENTRY_ELEMENTS) {
+        let title = element_it.next();
         let (filing_type, conformed_name, cik) =
-            clean_title(element_it.next()).expect("Unable to get title element");
+            clean_title(title).expect("Unable to get title element");
 
         let filing_enum =
             FilingType::which(filing_type).chain_err(|| "Unknown filing type given")?;
@@ -84,35 +86,45 @@ pub fn clean_xml(xml: Vec<String>, ignore: HashSet<FilingType>) -> Result<Vec<SE
                 acc_number,
                 date,
                 timestamp.to_owned(),
-            );
-            if !has_accession_number(acc_number).is_some() {
-                write_accession_number(acc_number);
-                entries.push(entry);
-            }
+            )
+            .with_role(clean_role(title));
+            entries.push(entry);
         }
     }
     Ok(entries)
 }
 
-/// This function will check to see if an accesion number is not unique, and thus
-/// must be ignore. The Programmer regrets this function, and will replace it with
-/// database query
-/// ^
-/// It's replaced :)
-fn has_accession_number(acc_number: usize) -> Option<Vec<AccessionNumber>> {
-    let conn = establish_connection("");
-    get_number(&conn, acc_number).and_then(|c| if c.len() == 0 { None } else { Some(c) })
-}
-
-fn write_accession_number(acc_number: usize) {
-    // -> Result<(i32, String)> {
-    let conn = establish_connection("");
-    write_number(&conn, acc_number).chain_err(|| "Unable to write accession Number");
-}
-
-fn delete_accession_number(acc_number: usize) -> Result<usize> {
-    let conn = establish_connection("");
-    delete_number(&conn, acc_number).chain_err(|| "Unable to delete accession Number")
+/// Hands the entries of a fetched page that have not been seen before to `write`,
+/// returning how many there were. Their accession numbers and the filings
+/// themselves are recorded with multi-row inserts in the same transaction, so if
+/// `write` fails nothing is marked as seen and the filings are picked up again on
+/// the next poll.
+pub fn write_new_entries<F>(conn: &DbConnection, entries: Vec<SECEntry>, write: F) -> Result<usize>
+where
+    F: FnOnce(Vec<SECEntry>) -> Result<()>,
+{
+    conn.transaction(|| {
+        let acc_numbers = entries
+            .iter()
+            .map(SECEntry::accession_number)
+            .collect::<Vec<usize>>();
+        let mut unseen = write_numbers(conn, &acc_numbers)?
+            .into_iter()
+            .collect::<HashSet<usize>>();
+
+        /* The feed lists a filing once per role. Every role is recorded, but only
+         * the first is emitted */
+        write_parties(conn, &entries)?;
+        let new_entries = entries
+            .into_iter()
+            .filter(|entry| unseen.remove(&entry.accession_number()))
+            .collect::<Vec<SECEntry>>();
+        let count = new_entries.len();
+
+        write_filings(conn, &new_entries)?;
+        write(new_entries)?;
+        Ok(count)
+    })
 }
 
 /// This function cleans the string received in the filing information from the xml
@@ -187,6 +199,18 @@ pub fn clean_title<'a>(input: Option<&'a String>) -> Result<(&'a str, &'a str, u
     }
 }
 
+/// Pulls the role out of the last pair of parens in a title, e.g. `Reporting` in
+///      4 - Wang Janet (0001655081) (Reporting)
+/// Titles without a role we know about give `None`.
+pub fn clean_role(input: Option<&String>) -> Option<FilingRole> {
+    lazy_static! {
+        static ref ROLE: Regex = Regex::new(r"\(([^()\d]+)\)\s*$").unwrap();
+    }
+    input
+        .and_then(|t| ROLE.captures(t))
+        .and_then(|c| FilingRole::which(&c[1]).ok())
+}
+
 #[inline]
 pub fn ignore_filing<T: Iterator>(iter: &mut T) {
     iter.next();
@@ -197,7 +221,8 @@ pub fn ignore_filing<T: Iterator>(iter: &mut T) {
 #[cfg(test)]
 mod rss_tests {
     use super::*;
-    use bigdecimal::BigDecimal;
+    use crate::filings::FilingQuery;
+    use crate::test_db::TestDb;
 
     #[test]
     fn clean_title_test_s1a() {
@@ -218,6 +243,24 @@ mod rss_tests {
         );
     }
 
+    #[test]
+    fn clean_role_test() {
+        assert_eq!(
+            clean_role(Some(&"4 - Wang Janet (0001655081) (Reporting)".to_owned())),
+            Some(FilingRole::Reporting)
+        );
+        assert_eq!(
+            clean_role(Some(
+                &"SC 13G - ACME CORP (0000012345) (Filed by)".to_owned()
+            )),
+            Some(FilingRole::FiledBy)
+        );
+        assert_eq!(
+            clean_role(Some(&"FWP - WELLS FARGO (0000072971)".to_owned())),
+            None
+        );
+    }
+
     #[test]
     fn clean_filing_test() {
         assert_eq!(
@@ -255,9 +298,6 @@ mod rss_tests {
             String::from("2018-07-05T20:51:01-04:00"),
         );
 
-        // Previous Test will write number back to database
-        delete_accession_number(145438718000188);
-
         let test = vec![
             "4/A - Wilson Andrew (0001545193) (Reporting)",
             "\n <b>Filed:</b> 2018-07-05 <b>AccNo:</b> 0001454387-18-000188 <b>Size:</b> 5 KB\n",
@@ -278,7 +318,8 @@ mod rss_tests {
             145438718000188,
             20180705,
             String::from("2018-07-05T20:51:01-04:00"),
-        );
+        )
+        .with_role(Some(FilingRole::Reporting));
 
         assert_eq!(Some(entry), clean_xml(vec, ignore_set).unwrap().pop());
     }
@@ -290,7 +331,6 @@ mod rss_tests {
     "\n <b>Filed:</b> 2018-07-05 <b>AccNo:</b> 0001454387-18-000188 <b>Size:</b> 5 KB\n",
     "2018-07-05T20:51:01-04:00",
                       "urn:tag:sec.gov,2008:accession-number=0001454387-18-000188"];
-        delete_accession_number(145438718000188);
         let vec = test.into_iter().map(str::to_owned).collect::<Vec<String>>();
         let entry = SECEntry::new(
             FilingType::Sec4A,
@@ -299,7 +339,8 @@ mod rss_tests {
             145438718000188,
             20180705,
             String::from("2018-07-05T20:51:01-04:00"),
-        );
+        )
+        .with_role(Some(FilingRole::Reporting));
         assert_eq!(Some(entry), clean_xml(vec, HashSet::new()).unwrap().pop());
     }
 
@@ -323,11 +364,6 @@ mod rss_tests {
         .map(String::from)
         .collect::<Vec<String>>();
 
-        // Previous Test will write number back to database
-        delete_accession_number(138713118004493);
-        delete_accession_number(112760218026759);
-        delete_accession_number(112760218026760);
-
         if let Ok(x) = clean_xml(test, HashSet::new()) {
             println!("{:#?}", &x);
             assert_eq!(x.len(), 3);
@@ -348,8 +384,6 @@ mod rss_tests {
         .map(String::from)
         .collect::<Vec<String>>();
 
-        delete_accession_number(92773018000500);
-
         let entry = SECEntry::new(
             FilingType::Sec497,
             String::from("JACKSON NATIONAL SEPARATE ACCOUNT"),
@@ -357,7 +391,8 @@ mod rss_tests {
             92773018000500,
             20180905,
             String::from("2018-09-05T13:06:11-04:00"),
-        );
+        )
+        .with_role(Some(FilingRole::Filer));
 
         if let Ok(mut x) = clean_xml(test, HashSet::new()) {
             assert_eq!(x.pop().unwrap(), entry);
@@ -366,21 +401,63 @@ mod rss_tests {
         }
     }
 
+    fn entry_with_number(acc_number: usize) -> SECEntry {
+        SECEntry::new(
+            FilingType::Sec4,
+            String::from("Wilson Andrew"),
+            1545193,
+            acc_number,
+            20180705,
+            String::from("2018-07-05T20:51:01-04:00"),
+        )
+    }
+
     #[test]
-    fn accession_number_test() {
-        let x = 1337;
-        let val = BigDecimal::from(1337);
-        delete_accession_number(x).expect("Better Not Fail");
-        assert_eq!(has_accession_number(x), None);
-        write_accession_number(x);
+    fn write_new_entries_test() {
+        let conn = TestDb::new();
+
+        let issuer = SECEntry::new(
+            FilingType::Sec4,
+            String::from("MARTIN MARIETTA"),
+            916076,
+            1338,
+            20180705,
+            String::from("2018-07-05T20:51:01-04:00"),
+        )
+        .with_role(Some(FilingRole::Issuer));
+        let page = vec![entry_with_number(1338), issuer, entry_with_number(1339)];
+        let mut written = Vec::new();
+        let count = write_new_entries(&conn, page, |new| {
+            written = new;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(count, 2);
         assert_eq!(
-            has_accession_number(x)
-                .unwrap()
-                .pop()
-                .unwrap()
-                .accession_number,
-            val
+            written,
+            vec![entry_with_number(1338), entry_with_number(1339)]
         );
-        assert!(delete_accession_number(x).is_ok());
+        assert_eq!(FilingQuery::new().run(&conn).unwrap().filings.len(), 2);
+        let by_issuer = FilingQuery::new().cik(916076).run(&conn).unwrap();
+        assert_eq!(by_issuer.filings, vec![entry_with_number(1338)]);
+
+        let count = write_new_entries(&conn, vec![entry_with_number(1338)], |new| {
+            assert!(new.is_empty());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn write_new_entries_rollback_test() {
+        let conn = TestDb::new();
+
+        let res = write_new_entries(&conn, vec![entry_with_number(1340)], |_| {
+            bail!("Write failed")
+        });
+        assert!(res.is_err());
+        assert_eq!(get_number(&conn, 1340), None);
+        assert!(FilingQuery::new().run(&conn).unwrap().filings.is_empty());
     }
 }
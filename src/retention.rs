@@ -0,0 +1,177 @@
+//! Keeps the seen-set from growing forever
+//!
+//! The feed only ever lists the last few days of filings, so accession numbers seen
+//! long ago can be forgotten without it listing them again. Old filings can be moved
+//! out to `filings_archive` at the same time, keeping `filings` small too. Their
+//! parties and items are dropped, and filings still queued for delivery stay put
+//! until they have been delivered.
+//!
+//! A backfill does re-read old dates, and checks `filings` and `filings_archive`
+//! as well as the seen-set, so filings emitted before are still skipped.
+use crate::db::{archive_filings_before, delete_numbers_seen_before, DbConnection};
+use crate::errors::*;
+use chrono::{Duration, Utc};
+
+/// Anything shorter could forget filings the feed is still listing
+pub const MIN_SEEN_DAYS: u32 = 7;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct RetentionPolicy {
+    /// Days to remember an accession number after first seeing it
+    pub seen_days: u32,
+    /// Days after its filing date to move a filing into `filings_archive`, if ever
+    pub archive_days: Option<u32>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> RetentionPolicy {
+        RetentionPolicy {
+            seen_days: 90,
+            archive_days: None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct PruneReport {
+    pub forgotten: usize,
+    pub archived: usize,
+}
+
+/// Applies `policy` in a single transaction.
+pub fn prune(conn: &DbConnection, policy: &RetentionPolicy) -> Result<PruneReport> {
+    if policy.seen_days < MIN_SEEN_DAYS {
+        bail!(
+            "Accession numbers must be kept for at least {} days, the feed may still list them",
+            MIN_SEEN_DAYS
+        );
+    }
+
+    let now = Utc::now().naive_utc();
+    conn.transaction(|| {
+        let forgotten =
+            delete_numbers_seen_before(conn, now - Duration::days(i64::from(policy.seen_days)))?;
+        let archived = match policy.archive_days {
+            Some(days) => {
+                let cutoff = (now - Duration::days(i64::from(days))).date();
+                let date = cutoff
+                    .format("%Y%m%d")
+                    .to_string()
+                    .parse::<i32>()
+                    .chain_err(|| "Unable to format cutoff date")?;
+                archive_filings_before(conn, date)?
+            }
+            None => 0,
+        };
+
+        Ok(PruneReport {
+            forgotten,
+            archived,
+        })
+    })
+}
+
+#[cfg(test)]
+mod retention_tests {
+    use super::*;
+    use crate::db::{get_number, on_backend, write_number, write_pending_deliveries};
+    use crate::filings::{write_filings, FilingQuery};
+    use crate::models::PendingDelivery;
+    use crate::schema::{filing_items, filing_parties};
+    use crate::sec_entry::{FilingType, SECEntry};
+    use crate::test_db::TestDb;
+    use diesel::prelude::*;
+
+    fn entry(acc: usize, date: usize) -> SECEntry {
+        SECEntry::new(
+            FilingType::Sec4,
+            String::from("Wilson Andrew"),
+            1545193,
+            acc,
+            date,
+            String::from("2018-07-05T20:51:01-04:00"),
+        )
+    }
+
+    /// The parties and items stored for `acc`
+    fn details(conn: &TestDb, acc: i64) -> (i64, i64) {
+        on_backend!(&**conn, c => {
+            let parties = filing_parties::table
+                .filter(filing_parties::accession_number.eq(acc))
+                .count()
+                .get_result::<i64>(c)?;
+            let items = filing_items::table
+                .filter(filing_items::accession_number.eq(acc))
+                .count()
+                .get_result::<i64>(c)?;
+            Ok::<_, diesel::result::Error>((parties, items))
+        })
+        .unwrap()
+    }
+
+    fn prune_test(conn: &TestDb) {
+        write_number(conn, 1).unwrap();
+        write_number(conn, 2).unwrap();
+        conn.execute(
+            "UPDATE accession_numbers SET seen_at = '2000-01-01 00:00:00' WHERE accession_number = 1",
+        );
+        let old = entry(1, 20000101).with_items(vec![String::from("2.02")]);
+        write_filings(conn, &[old, entry(2, 30000101), entry(3, 20000101)]).unwrap();
+        let queued = PendingDelivery {
+            sink: "webhook",
+            accession_number: 3,
+        };
+        write_pending_deliveries(conn, &[queued]).unwrap();
+        assert_eq!(details(conn, 1), (1, 1));
+
+        let policy = RetentionPolicy {
+            seen_days: 30,
+            archive_days: Some(30),
+        };
+        assert_eq!(
+            prune(conn, &policy).unwrap(),
+            PruneReport {
+                forgotten: 1,
+                archived: 1,
+            }
+        );
+        assert_eq!(get_number(conn, 1), None);
+        assert!(get_number(conn, 2).is_some());
+
+        assert_eq!(details(conn, 1), (0, 0));
+
+        // Still to be delivered, so kept until it has been
+        let filings = FilingQuery::new().run(conn).unwrap().filings;
+        assert_eq!(filings, vec![entry(2, 30000101), entry(3, 20000101)]);
+
+        // Already pruned, so nothing more to do
+        assert_eq!(
+            prune(conn, &policy).unwrap(),
+            PruneReport {
+                forgotten: 0,
+                archived: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn postgres_prune_test() {
+        prune_test(&TestDb::new());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_prune_test() {
+        prune_test(&TestDb::sqlite());
+    }
+
+    #[test]
+    fn short_retention_test() {
+        let conn = TestDb::new();
+        let policy = RetentionPolicy {
+            seen_days: 1,
+            archive_days: None,
+        };
+        assert!(prune(&conn, &policy).is_err());
+    }
+}
@@ -2,17 +2,178 @@ table! {
     accession_numbers (id) {
         id -> Int4,
         accession_number -> Numeric,
+        seen_at -> Timestamp,
     }
 }
 
 table! {
-    test_accession_numbers (id) {
-        id -> Int4,
-        accession_number -> Numeric,
+    beneficial_ownership (accession_number, position) {
+        accession_number -> Int8,
+        position -> Int4,
+        form_type -> Nullable<Text>,
+        structured -> Bool,
+        subject_cik -> Nullable<Int8>,
+        subject_name -> Nullable<Text>,
+        cusip -> Nullable<Text>,
+        class_title -> Nullable<Text>,
+        event_date -> Nullable<Int4>,
+        filer_cik -> Nullable<Int8>,
+        filer_name -> Nullable<Text>,
+        reporting_person -> Text,
+        reporting_person_cik -> Nullable<Int8>,
+        citizenship -> Nullable<Text>,
+        type_of_person -> Nullable<Text>,
+        sole_voting_power -> Nullable<Float8>,
+        shared_voting_power -> Nullable<Float8>,
+        sole_dispositive_power -> Nullable<Float8>,
+        shared_dispositive_power -> Nullable<Float8>,
+        aggregate_amount -> Nullable<Float8>,
+        percent_of_class -> Nullable<Float8>,
+    }
+}
+
+table! {
+    documents (accession_number, filename) {
+        accession_number -> Int8,
+        filename -> Text,
+        cik -> Int8,
+        path -> Text,
+        sha256 -> Text,
+        size -> Int8,
+        fetched_at -> Timestamp,
+    }
+}
+
+table! {
+    filing_items (accession_number, item) {
+        accession_number -> Int8,
+        item -> Text,
+    }
+}
+
+table! {
+    filing_parties (accession_number, cik) {
+        accession_number -> Int8,
+        cik -> Int8,
+        name -> Text,
+        role -> Nullable<Text>,
+    }
+}
+
+table! {
+    filings (accession_number) {
+        accession_number -> Int8,
+        form_type -> Text,
+        name -> Text,
+        cik -> Int8,
+        role -> Nullable<Text>,
+        filing_date -> Int4,
+        timestamp -> Text,
+    }
+}
+
+table! {
+    filings_archive (accession_number) {
+        accession_number -> Int8,
+        form_type -> Text,
+        name -> Text,
+        cik -> Int8,
+        role -> Nullable<Text>,
+        filing_date -> Int4,
+        timestamp -> Text,
+    }
+}
+
+table! {
+    ownership_documents (accession_number) {
+        accession_number -> Int8,
+        document_type -> Text,
+        period_of_report -> Nullable<Int4>,
+        issuer_cik -> Int8,
+        issuer_name -> Text,
+        trading_symbol -> Nullable<Text>,
+    }
+}
+
+table! {
+    ownership_footnotes (accession_number, id) {
+        accession_number -> Int8,
+        id -> Text,
+        text -> Text,
+    }
+}
+
+table! {
+    ownership_transactions (accession_number, position) {
+        accession_number -> Int8,
+        position -> Int4,
+        derivative -> Bool,
+        security_title -> Text,
+        transaction_date -> Nullable<Int4>,
+        code -> Nullable<Text>,
+        shares -> Nullable<Float8>,
+        price -> Nullable<Float8>,
+        acquired_disposed -> Nullable<Text>,
+        shares_owned_after -> Nullable<Float8>,
+        direct -> Nullable<Bool>,
+        exercise_price -> Nullable<Float8>,
+        exercise_date -> Nullable<Int4>,
+        expiration_date -> Nullable<Int4>,
+        underlying_title -> Nullable<Text>,
+        underlying_shares -> Nullable<Float8>,
+        footnotes -> Text,
+    }
+}
+
+table! {
+    pending_deliveries (sink, accession_number) {
+        sink -> Text,
+        accession_number -> Int8,
+    }
+}
+
+table! {
+    reporting_owners (accession_number, cik) {
+        accession_number -> Int8,
+        cik -> Int8,
+        name -> Text,
+        is_director -> Bool,
+        is_officer -> Bool,
+        is_ten_percent_owner -> Bool,
+        is_other -> Bool,
+        officer_title -> Nullable<Text>,
+        position -> Int4,
+    }
+}
+
+table! {
+    xbrl_facts (accession_number, position) {
+        accession_number -> Int8,
+        position -> Int4,
+        concept -> Text,
+        context -> Text,
+        unit -> Nullable<Text>,
+        period_start -> Nullable<Int4>,
+        period_end -> Nullable<Int4>,
+        dimensions -> Text,
+        value -> Text,
+        number -> Nullable<Float8>,
+        decimals -> Nullable<Text>,
     }
 }
 
 allow_tables_to_appear_in_same_query!(
     accession_numbers,
-    test_accession_numbers,
+    beneficial_ownership,
+    documents,
+    filing_items,
+    filing_parties,
+    filings,
+    filings_archive,
+    ownership_documents,
+    ownership_footnotes,
+    ownership_transactions,
+    pending_deliveries,
+    reporting_owners,
+    xbrl_facts,
 );
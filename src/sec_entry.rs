@@ -1,10 +1,13 @@
+use crate::archive::Document;
+use crate::edgar::{self, ARCHIVES_URL};
 use crate::errors::*;
+use serde::{Serialize, Serializer};
 use std::fmt::Write;
 
 pub static SUPPORTED_FILING_TYPES: &'static str = "S-1/A,
 5,4,4/A,3,6-K,FWP,425,8-K/A,8-K,497,D,424B2,13F-HR,SC13G,SC13G/A,SC13D/A,497,497K,POSAM,D/A,424B5,PRE14A,DFAN14A,144,F-10,F-3D,PRER14A,PRE14C,SCTO-I,485BPOS";
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
 pub struct SECEntry {
     filing_type: FilingType,
     name: String,
@@ -13,6 +16,9 @@ pub struct SECEntry {
     date: usize,
     timestamp: String,
     url: String,
+    role: Option<FilingRole>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    items: Vec<String>,
 }
 
 impl SECEntry {
@@ -34,9 +40,60 @@ impl SECEntry {
             date,
             timestamp,
             url: url_,
+            role: None,
+            items: Vec::new(),
         }
     }
 
+    /// Sets the role the entity played in the filing, as given after its CIK in the feed
+    pub fn with_role(mut self, role: Option<FilingRole>) -> SECEntry {
+        self.role = role;
+        self
+    }
+
+    /// Sets the numbers of the items an 8-K reports, e.g. `2.02`
+    pub fn with_items(mut self, items: Vec<String>) -> SECEntry {
+        self.items = items;
+        self
+    }
+
+    pub fn filing_type(&self) -> FilingType {
+        self.filing_type
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn cik(&self) -> usize {
+        self.cik
+    }
+
+    pub fn accession_number(&self) -> usize {
+        self.accession_number
+    }
+
+    pub fn date(&self) -> usize {
+        self.date
+    }
+
+    pub fn timestamp(&self) -> &str {
+        &self.timestamp
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn role(&self) -> Option<FilingRole> {
+        self.role
+    }
+
+    /// Empty for anything but an 8-K whose items have been looked up
+    pub fn items(&self) -> &[String] {
+        &self.items
+    }
+
     pub fn string(&self) -> String {
         let mut s = String::new();
         write!(
@@ -53,6 +110,30 @@ impl SECEntry {
         .expect("Could not write string");
         s
     }
+    /// An accession number as EDGAR prints it, e.g. `0001140361-18-030802`
+    pub fn dashed_accession(acc: usize) -> String {
+        format!(
+            "{:010}-{:02}-{:06}",
+            acc / 100_000_000,
+            acc / 1_000_000 % 100,
+            acc % 1_000_000
+        )
+    }
+
+    /// The JSON listing of a filing's documents
+    pub fn get_index_json_url(cik: usize, acc: usize) -> String {
+        format!(
+            "https://www.sec.gov/Archives/edgar/data/{}/{:018}/index.json",
+            cik, acc
+        )
+    }
+
+    /// The complete submission text file, every document of the filing in one
+    pub fn get_submission_url(cik: usize, acc: usize) -> String {
+        let filename = Document::Submission.filename(acc);
+        edgar::document_url(ARCHIVES_URL, cik, acc, &filename)
+    }
+
     pub fn get_url(cik: usize, acc: usize) -> String {
         let mut s = String::new();
         let size_url = 18;
@@ -123,6 +204,7 @@ impl FilingType {
             "4" => Ok(FilingType::Sec4),
             "4/A" => Ok(FilingType::Sec4A),
             "3" => Ok(FilingType::Sec3),
+            "3/A" => Ok(FilingType::Sec3A),
             "6-K" => Ok(FilingType::Sec6K),
             "FWP" => Ok(FilingType::SecFWP),
             "425" => Ok(FilingType::Sec425),
@@ -149,9 +231,108 @@ impl FilingType {
             "PRE 14C" => Ok(FilingType::SecPRE14C),
             "SC TO-I" => Ok(FilingType::SecSCTOI),
             "485BPOS" => Ok(FilingType::Sec485BPOS),
+            "485APOS" => Ok(FilingType::Sec485APOS),
+            "10-12G/A" => Ok(FilingType::Sec1012GA),
+            "N-2" => Ok(FilingType::SecN2),
+            "10-K" => Ok(FilingType::Sec10K),
+            "10-Q" => Ok(FilingType::Sec10Q),
             _ => Err("Filing not recognized")?,
         }
     }
+
+    /// The form name as the SEC writes it, the inverse of `which`
+    pub fn form(self) -> &'static str {
+        match self {
+            FilingType::SecS1 => "S-1/A",
+            FilingType::Sec3 => "3",
+            FilingType::Sec3A => "3/A",
+            FilingType::Sec4 => "4",
+            FilingType::Sec4A => "4/A",
+            FilingType::Sec5 => "5",
+            FilingType::Sec6K => "6-K",
+            FilingType::SecD => "D",
+            FilingType::SecDA => "D/A",
+            FilingType::SecFWP => "FWP",
+            FilingType::Sec424B2 => "424B2",
+            FilingType::Sec497 => "497",
+            FilingType::Sec497K => "497K",
+            FilingType::Sec1012GA => "10-12G/A",
+            FilingType::Sec485APOS => "485APOS",
+            FilingType::SecN2 => "N-2",
+            FilingType::Sec10K => "10-K",
+            FilingType::Sec10Q => "10-Q",
+            FilingType::Sec8K => "8-K",
+            FilingType::Sec8KA => "8-K/A",
+            FilingType::Sec13FHR => "13F-HR",
+            FilingType::Sec13GA => "SC 13G/A",
+            FilingType::SecPOSAM => "POS AM",
+            FilingType::Sec424B5 => "424B5",
+            FilingType::SecPRE14A => "PRE 14A",
+            FilingType::SecDFAN14A => "DFAN14A",
+            FilingType::SecSC13DA => "SC 13D/A",
+            FilingType::Sec144 => "144",
+            FilingType::Sec13G => "SC 13G",
+            FilingType::SecF10 => "F-10",
+            FilingType::Sec425 => "425",
+            FilingType::SecF3D => "F-3D",
+            FilingType::SecPRER14A => "PRER14A",
+            FilingType::SecPRE14C => "PRE 14C",
+            FilingType::SecSCTOI => "SC TO-I",
+            FilingType::Sec485BPOS => "485BPOS",
+        }
+    }
+
+    /// The form with any amendment suffix dropped, so `8-K/A` belongs to `8-K`
+    pub fn family(self) -> &'static str {
+        let form = self.form();
+        form.trim_end_matches("/A")
+    }
+}
+
+/// Serializes as the form name, e.g. `"S-1/A"`, rather than the variant name
+impl Serialize for FilingType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.form())
+    }
+}
+
+/// The part an entity played in a filing, e.g. the issuer or reporting owner of a Form 4
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum FilingRole {
+    Filer,
+    FiledBy,
+    Issuer,
+    Reporting,
+    Subject,
+}
+
+impl FilingRole {
+    pub fn which(role: &str) -> Result<FilingRole> {
+        match role {
+            "Filer" => Ok(FilingRole::Filer),
+            "Filed by" => Ok(FilingRole::FiledBy),
+            "Issuer" => Ok(FilingRole::Issuer),
+            "Reporting" => Ok(FilingRole::Reporting),
+            "Subject" => Ok(FilingRole::Subject),
+            _ => Err("Role not recognized")?,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FilingRole::Filer => "Filer",
+            FilingRole::FiledBy => "Filed by",
+            FilingRole::Issuer => "Issuer",
+            FilingRole::Reporting => "Reporting",
+            FilingRole::Subject => "Subject",
+        }
+    }
+}
+
+impl Serialize for FilingRole {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
 }
 
 #[cfg(test)]
@@ -171,6 +352,27 @@ mod entry_tests {
         assert_eq!(FilingType::which("5").unwrap(), FilingType::Sec5);
     }
 
+    #[test]
+    fn form_round_trip_test() {
+        for form in &[
+            "S-1/A", "3/A", "8-K/A", "10-K", "SC 13G/A", "POS AM", "SC TO-I",
+        ] {
+            assert_eq!(FilingType::which(form).unwrap().form(), *form);
+        }
+        assert_eq!(FilingType::Sec8KA.family(), "8-K");
+        assert_eq!(FilingType::Sec8K.family(), "8-K");
+    }
+
+    #[test]
+    fn which_role_test() {
+        assert_eq!(FilingRole::which("Filed by").unwrap(), FilingRole::FiledBy);
+        assert_eq!(
+            FilingRole::which(FilingRole::Issuer.as_str()).unwrap(),
+            FilingRole::Issuer
+        );
+        assert!(FilingRole::which("Bollocks").is_err());
+    }
+
     #[test]
     fn stringify_entry() {
         let entry = SECEntry::new(
@@ -201,4 +403,11 @@ mod entry_tests {
         assert_eq!(s,"https://www.sec.gov/Archives/edgar/data/1525201/000090445418000574/0000904454-18-000574-index.htm");
     }
 
+    #[test]
+    fn get_submission_url_test() {
+        assert_eq!(
+            SECEntry::get_submission_url(894158, 114420418049861),
+            "https://www.sec.gov/Archives/edgar/data/894158/000114420418049861/0001144204-18-049861.txt"
+        );
+    }
 }
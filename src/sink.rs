@@ -0,0 +1,377 @@
+//! Where new filings go once the scraper has found them
+//!
+//! -> `OutputFile` writes them to a file, see `write_entries`
+//! -> `StdoutSink` prints them
+//! -> `PostgresSink` copies them into the filings table of another database
+//! -> `webhook::WebhookSink` POSTs them to a URL
+//! -> `FanOut` queues them for several sinks at once, and delivers them once
+//!    they are recorded
+//!
+//! Anything else can implement `Sink` itself and go into a `FanOut` beside these.
+use crate::columns::Columns;
+use crate::db::{on_backend, write_pending_deliveries, DbConnection};
+use crate::errors::*;
+use crate::filings;
+use crate::models::PendingDelivery;
+use crate::schema::pending_deliveries;
+use crate::sec_entry::SECEntry;
+use crate::write_entries::{write_body, Format, OutputFile};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use std::io::{self, Write};
+use std::slice;
+
+pub trait Sink {
+    /// Delivers a batch of new filings. Through a `FanOut`, an error leaves them
+    /// queued for this sink, so the next run offers them again.
+    fn emit(&mut self, entries: &[SECEntry]) -> Result<()>;
+}
+
+/// Lets a closure stand in for a sink, e.g. to collect filings in a test
+impl<F> Sink for F
+where
+    F: FnMut(&[SECEntry]) -> Result<()>,
+{
+    fn emit(&mut self, entries: &[SECEntry]) -> Result<()> {
+        self(entries)
+    }
+}
+
+/// Unless it appends anyway, the file is replaced by the first batch of a run and
+/// each later batch is added to it, so a run that delivers in chunks keeps them all
+impl Sink for OutputFile {
+    fn emit(&mut self, entries: &[SECEntry]) -> Result<()> {
+        self.write(entries)?;
+        *self = self.clone().append(true);
+        Ok(())
+    }
+}
+
+/// Prints filings to standard output, or any other writer, with the table header
+/// before the first filing. Each filing is flushed as soon as it is written so
+/// whatever reads the other end of a pipe sees it straight away. A JSON array
+/// can't be written a filing at a time, or even a batch at a time, so that format
+/// is refused in favour of JSON lines.
+pub struct StdoutSink<W = io::Stdout> {
+    out: W,
+    format: Format,
+    columns: Columns,
+    header: bool,
+}
+
+impl StdoutSink {
+    pub fn new(format: Format) -> StdoutSink {
+        StdoutSink::with_writer(io::stdout(), format)
+    }
+}
+
+impl<W: Write> StdoutSink<W> {
+    pub fn with_writer(out: W, format: Format) -> StdoutSink<W> {
+        StdoutSink {
+            out,
+            format,
+            columns: Columns::default(),
+            header: true,
+        }
+    }
+
+    /// Picks the columns of table output
+    pub fn columns(mut self, columns: Columns) -> StdoutSink<W> {
+        self.columns = columns;
+        self
+    }
+}
+
+impl<W: Write> Sink for StdoutSink<W> {
+    fn emit(&mut self, entries: &[SECEntry]) -> Result<()> {
+        if self.format == Format::Json {
+            bail!("{}", STREAMED_JSON);
+        }
+        for entry in entries {
+            write_body(
+                &mut self.out,
+                slice::from_ref(entry),
+                self.format,
+                &self.columns,
+                self.header,
+            )?;
+            self.header = false;
+            self.out.flush().chain_err(|| "Write failed")?;
+        }
+        Ok(())
+    }
+}
+
+/// Why `StdoutSink` turns down `Format::Json`
+pub const STREAMED_JSON: &str =
+    "A JSON array can't be streamed to standard output, use --format jsonl instead";
+
+/// Copies filings, and the items of any 8-Ks, into the `filings` table of a
+/// Postgres database, skipping any already there
+pub struct PostgresSink {
+    conn: DbConnection,
+}
+
+impl PostgresSink {
+    pub fn new(conn: PgConnection) -> PostgresSink {
+        PostgresSink {
+            conn: DbConnection::Postgres(conn),
+        }
+    }
+
+    pub fn connect(url: &str) -> Result<PostgresSink> {
+        PgConnection::establish(url)
+            .map(PostgresSink::new)
+            .chain_err(|| format!("Unable to connect to {}", url))
+    }
+}
+
+impl Sink for PostgresSink {
+    fn emit(&mut self, entries: &[SECEntry]) -> Result<()> {
+        filings::write_filings(&self.conn, entries).map(|_| ())
+    }
+}
+
+/// Filings handed to a sink at a time when working through its queue
+const DELIVERY_CHUNK: i64 = 1000;
+
+/// Several sinks, each with its own queue of filings still to take.
+///
+/// -> `queue` adds a batch to every sink's queue, in the transaction that marks
+///    it as seen
+/// -> `deliver` hands each sink its queue once that has committed, and drops
+///    what it took
+///
+/// Nothing slow happens while the transaction holds its locks, and a sink that
+/// fails keeps its queue for the next run without the others getting the batch
+/// twice. Sinks are told apart by name, so a name should stay the same from one
+/// run to the next.
+#[derive(Default)]
+pub struct FanOut<'a> {
+    sinks: Vec<(String, Box<dyn Sink + 'a>)>,
+}
+
+impl<'a> FanOut<'a> {
+    pub fn new() -> FanOut<'a> {
+        FanOut { sinks: Vec::new() }
+    }
+
+    pub fn with<S: Sink + 'a>(mut self, name: &str, sink: S) -> FanOut<'a> {
+        self.sinks.push((name.to_owned(), Box::new(sink)));
+        self
+    }
+
+    pub fn push(&mut self, name: &str, sink: Box<dyn Sink + 'a>) {
+        self.sinks.push((name.to_owned(), sink));
+    }
+
+    /// Adds `entries`, which must be in the filings table, to every sink's queue
+    pub fn queue(&self, conn: &DbConnection, entries: &[SECEntry]) -> Result<()> {
+        let deliveries = self
+            .sinks
+            .iter()
+            .flat_map(|(name, _)| {
+                entries.iter().map(move |entry| PendingDelivery {
+                    sink: name,
+                    accession_number: entry.accession_number() as i64,
+                })
+            })
+            .collect::<Vec<PendingDelivery>>();
+        write_pending_deliveries(conn, &deliveries).map(|_| ())
+    }
+
+    /// Hands every sink what is queued for it, returning how many filings went to
+    /// the sinks all told. All of them get a go even if one fails, and the first
+    /// error is returned.
+    pub fn deliver(&mut self, conn: &DbConnection) -> Result<usize> {
+        let mut delivered = 0;
+        let mut first_error = None;
+        for (name, sink) in self.sinks.iter_mut() {
+            match deliver_to(conn, name, sink.as_mut()) {
+                Ok(count) => delivered += count,
+                Err(e) => {
+                    let e = Error::with_chain(e, format!("Unable to deliver filings to {}", name));
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        first_error.map_or(Ok(delivered), Err)
+    }
+}
+
+/// Empties the queue of the sink called `name` into it, a chunk at a time
+fn deliver_to(conn: &DbConnection, name: &str, sink: &mut dyn Sink) -> Result<usize> {
+    let mut delivered = 0;
+    loop {
+        let queued = on_backend!(conn, c => {
+            pending_deliveries::table
+                .filter(pending_deliveries::sink.eq(name))
+                .select(pending_deliveries::accession_number)
+                .order(pending_deliveries::accession_number)
+                .limit(DELIVERY_CHUNK)
+                .load::<i64>(c)
+        })
+        .chain_err(|| "Unable to load queued filings")?;
+        if queued.is_empty() {
+            return Ok(delivered);
+        }
+
+        let accession_numbers = queued
+            .iter()
+            .map(|&acc| acc as usize)
+            .collect::<Vec<usize>>();
+        let entries = filings::load(conn, &accession_numbers)?;
+        if !entries.is_empty() {
+            sink.emit(&entries)?;
+        }
+        delivered += entries.len();
+
+        on_backend!(conn, c => {
+            diesel::delete(
+                pending_deliveries::table
+                    .filter(pending_deliveries::sink.eq(name))
+                    .filter(pending_deliveries::accession_number.eq_any(&queued)),
+            )
+            .execute(c)
+        })
+        .chain_err(|| "Unable to clear delivered filings")?;
+    }
+}
+
+#[cfg(test)]
+mod sink_tests {
+    use super::*;
+    use crate::filings::{write_filings, FilingQuery};
+    use crate::sec_entry::FilingType;
+    use crate::test_db::TestDb;
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    /// Keeps everything it is given, or fails while `fail` is set
+    struct Collect {
+        seen: Rc<RefCell<Vec<usize>>>,
+        fail: Rc<Cell<bool>>,
+    }
+
+    impl Sink for Collect {
+        fn emit(&mut self, entries: &[SECEntry]) -> Result<()> {
+            if self.fail.get() {
+                bail!("Sink failed");
+            }
+            let mut seen = self.seen.borrow_mut();
+            seen.extend(entries.iter().map(SECEntry::accession_number));
+            Ok(())
+        }
+    }
+
+    fn entry(acc: usize) -> SECEntry {
+        SECEntry::new(
+            FilingType::Sec8K,
+            String::from("Name"),
+            1,
+            acc,
+            20261019,
+            String::from("12:00:00"),
+        )
+    }
+
+    fn fan_out_test(conn: &DbConnection) {
+        let sinks = (0..3)
+            .map(|_| (Rc::new(RefCell::new(Vec::new())), Rc::new(Cell::new(false))))
+            .collect::<Vec<_>>();
+        let mut sink = FanOut::new();
+        for (i, (seen, fail)) in sinks.iter().enumerate() {
+            let collect = Collect {
+                seen: seen.clone(),
+                fail: fail.clone(),
+            };
+            sink.push(&format!("collect {}", i), Box::new(collect));
+        }
+        let seen = |i: usize| sinks[i].0.borrow().clone();
+
+        sinks[1].1.set(true);
+        write_filings(conn, &[entry(1), entry(2)]).unwrap();
+        sink.queue(conn, &[entry(1), entry(2)]).unwrap();
+        assert!(sink.deliver(conn).is_err());
+        assert_eq!(seen(0), vec![1, 2]);
+        assert!(seen(1).is_empty());
+        assert_eq!(seen(2), vec![1, 2]);
+
+        // The sink that failed catches up, and the others only get what's new
+        sinks[1].1.set(false);
+        write_filings(conn, &[entry(3)]).unwrap();
+        sink.queue(conn, &[entry(3)]).unwrap();
+        assert_eq!(sink.deliver(conn).unwrap(), 5);
+        assert_eq!(seen(0), vec![1, 2, 3]);
+        assert_eq!(seen(1), vec![1, 2, 3]);
+        assert_eq!(seen(2), vec![1, 2, 3]);
+        assert_eq!(sink.deliver(conn).unwrap(), 0);
+
+        assert_eq!(FanOut::new().deliver(conn).unwrap(), 0);
+    }
+
+    #[test]
+    fn postgres_fan_out_test() {
+        fan_out_test(&TestDb::new());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_fan_out_test() {
+        fan_out_test(&TestDb::sqlite());
+    }
+
+    /// Notes where each flush fell in what was written
+    #[derive(Default)]
+    struct Flushes {
+        written: Vec<u8>,
+        flushed_at: Vec<usize>,
+    }
+
+    impl Write for Flushes {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushed_at.push(self.written.len());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn stdout_sink_test() {
+        let mut sink = StdoutSink::with_writer(Flushes::default(), Format::Table(b'\t'));
+        sink.emit(&[entry(1), entry(2)]).unwrap();
+        sink.emit(&[entry(3)]).unwrap();
+
+        let written = String::from_utf8(sink.out.written.clone()).unwrap();
+        let lines = written.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("Filing Type"));
+        assert!(lines[3].starts_with("8-K\tName\t1\t3\t"));
+
+        let mut json = StdoutSink::with_writer(Vec::new(), Format::Json);
+        assert!(json.emit(&[entry(1)]).is_err());
+
+        // Every record is out before the next one is written
+        let ends = written
+            .match_indices('\n')
+            .map(|(i, _)| i + 1)
+            .skip(1)
+            .collect::<Vec<usize>>();
+        for end in ends {
+            assert!(sink.out.flushed_at.contains(&end));
+        }
+    }
+
+    #[test]
+    fn postgres_sink_test() {
+        let db = TestDb::new();
+        let mut sink = PostgresSink::new(db.another_pg());
+
+        sink.emit(&[entry(4), entry(5)]).unwrap();
+        sink.emit(&[entry(5)]).unwrap();
+        assert_eq!(FilingQuery::new().run(&db).unwrap().filings.len(), 2);
+    }
+}
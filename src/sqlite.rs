@@ -0,0 +1,127 @@
+//! The seen-set stored in a SQLite file, for machines without Postgres
+//!
+//! Accession numbers are kept as 64 bit integers here rather than `NUMERIC`,
+//! which SQLite would hand back through a lossy `f64`.
+use crate::models::{AccessionNumber, NewArchivedDocument};
+use bigdecimal::BigDecimal;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+
+mod schema {
+    table! {
+        accession_numbers (id) {
+            id -> Integer,
+            accession_number -> BigInt,
+            seen_at -> Timestamp,
+        }
+    }
+}
+
+use self::schema::accession_numbers;
+
+#[derive(Insertable)]
+#[table_name = "accession_numbers"]
+struct NewAccessionNumber {
+    accession_number: i64,
+}
+
+/// Records an accession number as seen, returning `true` if it was not already
+/// in the table.
+pub fn write_number(
+    conn: &SqliteConnection,
+    acc_number: usize,
+) -> Result<bool, diesel::result::Error> {
+    let new_post = NewAccessionNumber {
+        accession_number: acc_number as i64,
+    };
+
+    diesel::insert_or_ignore_into(accession_numbers::table)
+        .values(&new_post)
+        .execute(conn)
+        .map(|inserted| inserted == 1)
+}
+
+/// SQLite can't say which rows of a multi-row insert it ignored, so each number
+/// is inserted on its own. Callers batch these inside a single transaction.
+pub fn write_numbers(
+    conn: &SqliteConnection,
+    acc_numbers: &[usize],
+) -> Result<Vec<usize>, diesel::result::Error> {
+    let mut unseen = Vec::new();
+    for &acc in acc_numbers {
+        if write_number(conn, acc)? {
+            unseen.push(acc);
+        }
+    }
+    Ok(unseen)
+}
+
+pub fn delete_numbers_seen_before(
+    conn: &SqliteConnection,
+    cutoff: NaiveDateTime,
+) -> Result<usize, diesel::result::Error> {
+    use self::schema::accession_numbers::dsl::*;
+
+    diesel::delete(accession_numbers.filter(seen_at.lt(cutoff))).execute(conn)
+}
+
+pub fn write_document(
+    conn: &SqliteConnection,
+    document: &NewArchivedDocument,
+) -> Result<usize, diesel::result::Error> {
+    use crate::schema::documents;
+
+    diesel::replace_into(documents::table)
+        .values(document)
+        .execute(conn)
+}
+
+pub fn delete_number(conn: &SqliteConnection, acc: usize) -> Result<usize, diesel::result::Error> {
+    use self::schema::accession_numbers::dsl::*;
+
+    diesel::delete(accession_numbers.filter(accession_number.eq(acc as i64))).execute(conn)
+}
+
+pub fn get_number(conn: &SqliteConnection, acc: usize) -> Option<Vec<AccessionNumber>> {
+    use self::schema::accession_numbers::dsl::*;
+
+    accession_numbers
+        .select((id, accession_number))
+        .filter(accession_number.eq(acc as i64))
+        .limit(5)
+        .load::<(i32, i64)>(conn)
+        .ok()
+        .map(|c| {
+            c.into_iter()
+                .map(|(i, x)| AccessionNumber {
+                    id: i,
+                    accession_number: BigDecimal::from(x),
+                })
+                .collect::<Vec<AccessionNumber>>()
+        })
+        .and_then(|x| if x.is_empty() { None } else { Some(x) })
+}
+
+pub fn get_numbers(conn: &SqliteConnection) -> Option<Vec<BigDecimal>> {
+    use self::schema::accession_numbers::dsl::*;
+
+    accession_numbers
+        .select(accession_number)
+        .limit(5)
+        .load::<i64>(conn)
+        .ok()
+        .map(|c| {
+            c.into_iter()
+                .map(BigDecimal::from)
+                .collect::<Vec<BigDecimal>>()
+        })
+}
+
+pub fn delete_all_posts(conn: &SqliteConnection) {
+    use self::schema::accession_numbers::dsl::*;
+
+    diesel::delete(accession_numbers)
+        .execute(conn)
+        .expect("Error deleting posts");
+}
@@ -0,0 +1,602 @@
+//! Reads a complete submission, the `<accession>.txt` file that bundles a
+//! filing's header and every one of its documents
+//!
+//! -> `Submission::new` reads the `<SEC-HEADER>` into a `SubmissionHeader`: the
+//!    form, dates, items and a block for each company involved (filer, issuer, ...)
+//! -> iterating over the `Submission` then yields its documents one at a time,
+//!    with uuencoded ones (images, PDFs, spreadsheets) decoded back into bytes
+//!
+//! Only the document being read is held in memory, so `fetch` can go through a
+//! submission of several hundred megabytes while it downloads.
+use crate::edgar;
+use crate::errors::*;
+use crate::filing_index::IndexCompany;
+use crate::sec_entry::{FilingRole, SECEntry};
+use chrono::{NaiveDate, NaiveDateTime};
+use reqwest::Response;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct SubmissionHeader {
+    pub accession_number: Option<usize>,
+    /// e.g. `10-K`
+    pub form_type: Option<String>,
+    pub document_count: Option<u32>,
+    pub period_of_report: Option<NaiveDate>,
+    pub filing_date: Option<NaiveDate>,
+    /// When EDGAR accepted the submission, in Eastern time
+    pub accepted: Option<NaiveDateTime>,
+    /// The `ITEM INFORMATION` lines, e.g. `Results of Operations and Financial Condition`
+    pub items: Vec<String>,
+    pub companies: Vec<IndexCompany>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct SubmissionDocument {
+    /// e.g. `10-K`, `EX-21.1` or `GRAPHIC`
+    pub document_type: String,
+    pub sequence: Option<u32>,
+    pub filename: Option<String>,
+    pub description: Option<String>,
+    /// Decoded if it was uuencoded, and without an `<XML>`, `<XBRL>` or `<PDF>` wrapper
+    pub contents: Vec<u8>,
+}
+
+impl SubmissionDocument {
+    /// The contents as text, with anything that isn't UTF-8 replaced
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.contents).into_owned()
+    }
+}
+
+pub struct Submission<R> {
+    reader: R,
+    header: SubmissionHeader,
+    done: bool,
+}
+
+/// Downloads `entry`'s complete submission, reading it as it arrives
+pub fn fetch(entry: &SECEntry) -> Result<Submission<BufReader<Response>>> {
+    let url = SECEntry::get_submission_url(entry.cik(), entry.accession_number());
+    let res = edgar::open(&url)?.chain_err(|| format!("{} not found", url))?;
+    Submission::new(BufReader::new(res))
+}
+
+/// Opens a complete submission on disk, e.g. one kept by `archive::Archive`
+pub fn open(path: &Path) -> Result<Submission<BufReader<File>>> {
+    let file = File::open(path).chain_err(|| format!("Unable to open {}", path.display()))?;
+    Submission::new(BufReader::new(file))
+}
+
+impl<R: BufRead> Submission<R> {
+    /// Reads as far as the end of the header
+    pub fn new(mut reader: R) -> Result<Submission<R>> {
+        let mut lines = Vec::new();
+        let mut in_header = false;
+        let mut line = Vec::new();
+        loop {
+            if !read_line(&mut reader, &mut line)? {
+                bail!("Submission ended before its header did");
+            }
+            let text = String::from_utf8_lossy(trim_end(&line)).into_owned();
+            if text.starts_with("<SEC-HEADER>") || text.starts_with("<IMS-HEADER>") {
+                in_header = true;
+            } else if text.starts_with("</SEC-HEADER>") || text.starts_with("</IMS-HEADER>") {
+                break;
+            } else if text.starts_with("<DOCUMENT>") {
+                bail!("Submission has no header");
+            } else if in_header {
+                lines.push(text);
+            }
+        }
+
+        Ok(Submission {
+            reader,
+            header: parse_header(&lines)?,
+            done: false,
+        })
+    }
+
+    pub fn header(&self) -> &SubmissionHeader {
+        &self.header
+    }
+
+    /// Reads the next document, or `None` once there are no more
+    fn next_document(&mut self) -> Result<Option<SubmissionDocument>> {
+        let mut line = Vec::new();
+        loop {
+            if !read_line(&mut self.reader, &mut line)? {
+                return Ok(None);
+            }
+            match trim_end(&line) {
+                b"<DOCUMENT>" => break,
+                b"</SEC-DOCUMENT>" | b"</IMS-DOCUMENT>" => return Ok(None),
+                _ => {}
+            }
+        }
+
+        let mut document = SubmissionDocument {
+            document_type: String::new(),
+            sequence: None,
+            filename: None,
+            description: None,
+            contents: Vec::new(),
+        };
+        loop {
+            if !read_line(&mut self.reader, &mut line)? {
+                bail!("Submission ended inside a document");
+            }
+            let text = String::from_utf8_lossy(trim_end(&line)).into_owned();
+            if text.starts_with("<TEXT>") {
+                break;
+            }
+            if let Some((tag, value)) = tag(&text) {
+                let value = value.trim().to_owned();
+                match tag {
+                    "TYPE" => document.document_type = value,
+                    "SEQUENCE" => document.sequence = value.parse::<u32>().ok(),
+                    "FILENAME" => document.filename = Some(value).filter(|v| !v.is_empty()),
+                    "DESCRIPTION" => document.description = Some(value).filter(|v| !v.is_empty()),
+                    _ => {}
+                }
+            }
+        }
+
+        document.contents = self.read_text()?;
+        loop {
+            if !read_line(&mut self.reader, &mut line)? || trim_end(&line) == b"</DOCUMENT>" {
+                break;
+            }
+        }
+        Ok(Some(document))
+    }
+
+    /// Reads up to `</TEXT>`, decoding the contents if they are uuencoded
+    fn read_text(&mut self) -> Result<Vec<u8>> {
+        let mut contents = Vec::new();
+        let mut line = Vec::new();
+        let mut first = true;
+        let mut wrapped = false;
+        let mut encoded = false;
+        let mut decoded = false;
+        loop {
+            if !read_line(&mut self.reader, &mut line)? {
+                bail!("Submission ended inside a document's text");
+            }
+            let trimmed = trim_end(&line);
+            if trimmed == b"</TEXT>" {
+                break;
+            }
+            if first {
+                if trimmed.is_empty() {
+                    continue;
+                }
+                if is_wrapper(trimmed) {
+                    wrapped = true;
+                    continue;
+                }
+                first = false;
+                if trimmed.starts_with(b"begin ") {
+                    encoded = true;
+                    continue;
+                }
+            }
+
+            if encoded {
+                if trimmed == b"end" {
+                    encoded = false;
+                    decoded = true;
+                } else {
+                    uudecode_line(trimmed, &mut contents)?;
+                }
+            } else if !decoded {
+                contents.extend_from_slice(&line);
+            }
+        }
+
+        if wrapped && !decoded {
+            let body = trim_end(&contents);
+            let last = body.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+            if is_wrapper(&body[last..]) {
+                contents.truncate(last);
+            }
+        }
+        Ok(contents)
+    }
+}
+
+impl<R: BufRead> Iterator for Submission<R> {
+    type Item = Result<SubmissionDocument>;
+
+    fn next(&mut self) -> Option<Result<SubmissionDocument>> {
+        if self.done {
+            return None;
+        }
+        let document = self.next_document().transpose();
+        if !matches!(document, Some(Ok(_))) {
+            self.done = true;
+        }
+        document
+    }
+}
+
+/// Reads one line into `line`, ending and all, returning `false` at the end of the file
+fn read_line<R: BufRead>(reader: &mut R, line: &mut Vec<u8>) -> Result<bool> {
+    line.clear();
+    let read = reader
+        .read_until(b'\n', line)
+        .chain_err(|| "Unable to read submission")?;
+    Ok(read > 0)
+}
+
+fn trim_end(line: &[u8]) -> &[u8] {
+    let end = line
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(0, |i| i + 1);
+    &line[..end]
+}
+
+/// The `<XML>`, `<XBRL>` and `<PDF>` tags EDGAR puts around some documents' contents
+fn is_wrapper(line: &[u8]) -> bool {
+    matches!(
+        line,
+        b"<XML>" | b"</XML>" | b"<XBRL>" | b"</XBRL>" | b"<PDF>" | b"</PDF>"
+    )
+}
+
+/// Splits `<TAG>value` into its tag and value
+fn tag(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix('<')?;
+    let end = rest.find('>')?;
+    Some((&rest[..end], &rest[end + 1..]))
+}
+
+/// Decodes a line of uuencoded data onto the end of `out`
+fn uudecode_line(line: &[u8], out: &mut Vec<u8>) -> Result<()> {
+    let sixbits = |c: u8| c.wrapping_sub(b' ') & 0x3f;
+    let length = match line.first() {
+        Some(c) => sixbits(*c) as usize,
+        None => return Ok(()),
+    };
+    let mut decoded = Vec::with_capacity(length + 2);
+    // Trailing spaces are often trimmed off, and stand for zeros
+    for group in line[1..].chunks(4) {
+        let mut c = [0u8; 4];
+        for (i, b) in group.iter().enumerate() {
+            c[i] = sixbits(*b);
+        }
+        decoded.push(c[0] << 2 | c[1] >> 4);
+        decoded.push(c[1] << 4 | c[2] >> 2);
+        decoded.push(c[2] << 6 | c[3]);
+    }
+    if decoded.len() < length {
+        bail!("uuencoded line is shorter than it says");
+    }
+    out.extend_from_slice(&decoded[..length]);
+    Ok(())
+}
+
+fn parse_header(lines: &[String]) -> Result<SubmissionHeader> {
+    let mut header = SubmissionHeader::default();
+    let mut company: Option<HeaderCompany> = None;
+
+    for line in lines {
+        if let Some(("ACCEPTANCE-DATETIME", value)) = tag(line) {
+            header.accepted = NaiveDateTime::parse_from_str(value.trim(), "%Y%m%d%H%M%S").ok();
+            continue;
+        }
+        let (key, value) = match line.find(':') {
+            Some(i) => (line[..i].trim(), line[i + 1..].trim()),
+            None => continue,
+        };
+        let top_level = !line.starts_with(char::is_whitespace);
+
+        if top_level && value.is_empty() {
+            if let Some(c) = company.take() {
+                header.companies.push(c.build()?);
+            }
+            company = Some(HeaderCompany::new(role(key)));
+        } else if let Some(c) = company.as_mut().filter(|_| !top_level) {
+            if value.is_empty() {
+                c.section = key.to_owned();
+            } else {
+                c.fields
+                    .push((c.section.clone(), key.to_owned(), value.to_owned()));
+            }
+        } else {
+            let date = || NaiveDate::parse_from_str(value, "%Y%m%d").ok();
+            match key {
+                "ACCESSION NUMBER" => {
+                    header.accession_number = value.replace('-', "").parse::<usize>().ok()
+                }
+                "CONFORMED SUBMISSION TYPE" => header.form_type = Some(value.to_owned()),
+                "PUBLIC DOCUMENT COUNT" => header.document_count = value.parse::<u32>().ok(),
+                "CONFORMED PERIOD OF REPORT" => header.period_of_report = date(),
+                "FILED AS OF DATE" => header.filing_date = date(),
+                "ITEM INFORMATION" => header.items.push(value.to_owned()),
+                _ => {}
+            }
+        }
+    }
+    if let Some(c) = company.take() {
+        header.companies.push(c.build()?);
+    }
+    Ok(header)
+}
+
+/// The role a company block's heading, e.g. `SUBJECT COMPANY`, stands for
+fn role(heading: &str) -> Option<FilingRole> {
+    match heading {
+        "FILER" => Some(FilingRole::Filer),
+        "FILED BY" => Some(FilingRole::FiledBy),
+        "ISSUER" => Some(FilingRole::Issuer),
+        "REPORTING-OWNER" => Some(FilingRole::Reporting),
+        "SUBJECT COMPANY" => Some(FilingRole::Subject),
+        _ => None,
+    }
+}
+
+/// The fields of one company block, as (section, key, value)
+struct HeaderCompany {
+    role: Option<FilingRole>,
+    section: String,
+    fields: Vec<(String, String, String)>,
+}
+
+impl HeaderCompany {
+    fn new(role: Option<FilingRole>) -> HeaderCompany {
+        HeaderCompany {
+            role,
+            section: String::new(),
+            fields: Vec::new(),
+        }
+    }
+
+    fn get(&self, section: &str, key: &str) -> Option<String> {
+        self.fields
+            .iter()
+            .find(|(s, k, _)| s == section && k == key)
+            .map(|(_, _, v)| v.clone())
+    }
+
+    /// From `COMPANY DATA` for companies and `OWNER DATA` for people
+    fn data(&self, key: &str) -> Option<String> {
+        self.get("COMPANY DATA", key)
+            .or_else(|| self.get("OWNER DATA", key))
+    }
+
+    /// Laid out like the filing index page lays addresses out
+    fn address(&self, section: &str) -> Vec<String> {
+        let field = |key: &str| self.get(section, key);
+        let place = ["CITY", "STATE", "ZIP"]
+            .iter()
+            .filter_map(|key| field(key))
+            .collect::<Vec<String>>()
+            .join(" ");
+        vec![
+            field("STREET 1"),
+            field("STREET 2"),
+            Some(place).filter(|p| !p.is_empty()),
+            field("BUSINESS PHONE"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn build(self) -> Result<IndexCompany> {
+        let name = self
+            .data("COMPANY CONFORMED NAME")
+            .or_else(|| self.data("CONFORMED NAME"))
+            .chain_err(|| "No name in company block of submission header")?;
+        let cik = self
+            .data("CENTRAL INDEX KEY")
+            .and_then(|c| c.parse::<usize>().ok())
+            .chain_err(|| format!("No CIK for {} in submission header", name))?;
+        // e.g. `SERVICES-PREPACKAGED SOFTWARE [7372]`
+        let sic = self
+            .data("STANDARD INDUSTRIAL CLASSIFICATION")
+            .and_then(|s| {
+                let start = s.rfind('[')?;
+                s[start + 1..].trim_end_matches(']').parse::<u32>().ok()
+            });
+
+        Ok(IndexCompany {
+            name,
+            cik,
+            role: self.role,
+            mailing_address: self.address("MAIL ADDRESS"),
+            business_address: self.address("BUSINESS ADDRESS"),
+            irs_number: self.data("IRS NUMBER"),
+            state_of_incorporation: self.data("STATE OF INCORPORATION"),
+            fiscal_year_end: self.data("FISCAL YEAR END"),
+            sic,
+        })
+    }
+}
+
+#[cfg(test)]
+mod submission_tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const SUBMISSION: &str = "<SEC-DOCUMENT>0001660134-18-000007.txt : 20180904
+<SEC-HEADER>0001660134-18-000007.hdr.sgml : 20180904
+<ACCEPTANCE-DATETIME>20180904161652
+ACCESSION NUMBER:\t\t0001660134-18-000007
+CONFORMED SUBMISSION TYPE:\t8-K
+PUBLIC DOCUMENT COUNT:\t\t3
+CONFORMED PERIOD OF REPORT:\t20180830
+ITEM INFORMATION:\t\tResults of Operations and Financial Condition
+ITEM INFORMATION:\t\tFinancial Statements and Exhibits
+FILED AS OF DATE:\t\t20180904
+DATE AS OF CHANGE:\t\t20180904
+
+FILER:
+
+\tCOMPANY DATA:\t
+\t\tCOMPANY CONFORMED NAME:\t\t\tOkta, Inc.
+\t\tCENTRAL INDEX KEY:\t\t\t0001660134
+\t\tSTANDARD INDUSTRIAL CLASSIFICATION:\tSERVICES-PREPACKAGED SOFTWARE [7372]
+\t\tIRS NUMBER:\t\t\t\t261592408
+\t\tSTATE OF INCORPORATION:\t\t\tDE
+\t\tFISCAL YEAR END:\t\t\t0131
+
+\tFILING VALUES:
+\t\tFORM TYPE:\t\t8-K
+\t\tSEC ACT:\t\t1934 Act
+
+\tBUSINESS ADDRESS:\t
+\t\tSTREET 1:\t\t100 FIRST STREET
+\t\tCITY:\t\t\tSAN FRANCISCO
+\t\tSTATE:\t\t\tCA
+\t\tZIP:\t\t\t94105
+\t\tBUSINESS PHONE:\t\t888-722-7871
+
+\tMAIL ADDRESS:\t
+\t\tSTREET 1:\t\t100 FIRST STREET
+\t\tSTREET 2:\t\t6TH FLOOR
+\t\tCITY:\t\t\tSAN FRANCISCO
+\t\tSTATE:\t\t\tCA
+\t\tZIP:\t\t\t94105
+</SEC-HEADER>
+<DOCUMENT>
+<TYPE>8-K
+<SEQUENCE>1
+<FILENAME>a8-k.htm
+<DESCRIPTION>8-K
+<TEXT>
+<html>
+<body>Item 2.02</body>
+</html>
+</TEXT>
+</DOCUMENT>
+<DOCUMENT>
+<TYPE>EX-101.INS
+<SEQUENCE>2
+<FILENAME>okta-20180830.xml
+<TEXT>
+<XML>
+<?xml version=\"1.0\"?>
+<xbrl/>
+</XML>
+</TEXT>
+</DOCUMENT>
+<DOCUMENT>
+<TYPE>GRAPHIC
+<SEQUENCE>3
+<FILENAME>cat.txt
+<DESCRIPTION>
+<TEXT>
+begin 644 cat.txt
+#0V%T
+`
+end
+</TEXT>
+</DOCUMENT>
+</SEC-DOCUMENT>
+";
+
+    fn submission() -> Submission<Cursor<&'static [u8]>> {
+        Submission::new(Cursor::new(SUBMISSION.as_bytes())).unwrap()
+    }
+
+    #[test]
+    fn parse_header_test() {
+        let submission = submission();
+        let header = submission.header();
+        assert_eq!(header.accession_number, Some(166013418000007));
+        assert_eq!(header.form_type, Some(String::from("8-K")));
+        assert_eq!(header.document_count, Some(3));
+        assert_eq!(
+            header.period_of_report,
+            Some(NaiveDate::from_ymd_opt(2018, 8, 30).unwrap())
+        );
+        assert_eq!(
+            header.filing_date,
+            Some(NaiveDate::from_ymd_opt(2018, 9, 4).unwrap())
+        );
+        assert_eq!(
+            header.accepted,
+            NaiveDate::from_ymd_opt(2018, 9, 4)
+                .unwrap()
+                .and_hms_opt(16, 16, 52)
+        );
+        assert_eq!(
+            header.items,
+            vec![
+                "Results of Operations and Financial Condition",
+                "Financial Statements and Exhibits"
+            ]
+        );
+        assert_eq!(
+            header.companies,
+            vec![IndexCompany {
+                name: String::from("Okta, Inc."),
+                cik: 1660134,
+                role: Some(FilingRole::Filer),
+                mailing_address: vec![
+                    String::from("100 FIRST STREET"),
+                    String::from("6TH FLOOR"),
+                    String::from("SAN FRANCISCO CA 94105"),
+                ],
+                business_address: vec![
+                    String::from("100 FIRST STREET"),
+                    String::from("SAN FRANCISCO CA 94105"),
+                    String::from("888-722-7871"),
+                ],
+                irs_number: Some(String::from("261592408")),
+                state_of_incorporation: Some(String::from("DE")),
+                fiscal_year_end: Some(String::from("0131")),
+                sic: Some(7372),
+            }]
+        );
+    }
+
+    #[test]
+    fn documents_test() {
+        let documents = submission()
+            .collect::<Result<Vec<SubmissionDocument>>>()
+            .unwrap();
+        assert_eq!(documents.len(), 3);
+
+        assert_eq!(
+            documents[0],
+            SubmissionDocument {
+                document_type: String::from("8-K"),
+                sequence: Some(1),
+                filename: Some(String::from("a8-k.htm")),
+                description: Some(String::from("8-K")),
+                contents: b"<html>\n<body>Item 2.02</body>\n</html>\n".to_vec(),
+            }
+        );
+        assert_eq!(documents[1].description, None);
+        assert_eq!(documents[1].text(), "<?xml version=\"1.0\"?>\n<xbrl/>\n");
+        assert_eq!(documents[2].document_type, "GRAPHIC");
+        assert_eq!(documents[2].contents, b"Cat");
+    }
+
+    #[test]
+    fn malformed_test() {
+        assert!(Submission::new(Cursor::new(&b"<DOCUMENT>\n"[..])).is_err());
+
+        let cut = &SUBMISSION[..SUBMISSION.find("</TEXT>").unwrap()];
+        let documents = Submission::new(Cursor::new(cut.as_bytes()))
+            .unwrap()
+            .collect::<Vec<Result<SubmissionDocument>>>();
+        assert_eq!(documents.len(), 1);
+        assert!(documents[0].is_err());
+    }
+
+    #[test]
+    fn uudecode_test() {
+        let mut out = Vec::new();
+        uudecode_line(b"#0V%T", &mut out).unwrap();
+        uudecode_line(b"`", &mut out).unwrap();
+        assert_eq!(out, b"Cat");
+        assert!(uudecode_line(b"M0V%T", &mut out).is_err());
+    }
+}
@@ -0,0 +1,101 @@
+//! Throwaway databases for tests
+//!
+//! Every Postgres `TestDb` gets its own schema on `DATABASE_URL` with all of the
+//! migrations applied, and drops it again when it goes out of scope. Tests never
+//! see each other's rows, so `cargo test` can run them in parallel. SQLite ones
+//! live in memory and vanish with their connection.
+use crate::db::DbConnection;
+use crate::migrations::run_pending;
+use crate::postgres::establish_connection;
+use diesel::connection::SimpleConnection;
+use diesel::pg::PgConnection;
+use std::io;
+use std::ops::Deref;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_SCHEMA: AtomicUsize = AtomicUsize::new(0);
+
+pub struct TestDb {
+    conn: DbConnection,
+    schema: Option<String>,
+}
+
+impl TestDb {
+    pub fn new() -> TestDb {
+        let schema = format!(
+            "test_{}_{}",
+            process::id(),
+            NEXT_SCHEMA.fetch_add(1, Ordering::SeqCst)
+        );
+        let conn = establish_connection("");
+
+        conn.batch_execute(&format!(
+            "DROP SCHEMA IF EXISTS {0} CASCADE; CREATE SCHEMA {0}; SET search_path TO {0};",
+            schema
+        ))
+        .expect("Unable to create test schema");
+
+        TestDb::migrated(DbConnection::Postgres(conn), Some(schema))
+    }
+
+    #[cfg(feature = "sqlite")]
+    pub fn sqlite() -> TestDb {
+        use diesel::prelude::*;
+        use diesel::sqlite::SqliteConnection;
+
+        let conn = SqliteConnection::establish(":memory:").expect("Unable to open SQLite");
+        TestDb::migrated(DbConnection::Sqlite(conn), None)
+    }
+
+    fn migrated(conn: DbConnection, schema: Option<String>) -> TestDb {
+        run_pending(&conn, &mut io::sink()).expect("Unable to run migrations");
+        TestDb { conn, schema }
+    }
+
+    /// Runs raw SQL, for setting up rows the public functions can't write
+    pub fn execute(&self, sql: &str) {
+        match self.conn {
+            DbConnection::Postgres(ref conn) => conn.batch_execute(sql),
+            #[cfg(feature = "sqlite")]
+            DbConnection::Sqlite(ref conn) => conn.batch_execute(sql),
+        }
+        .expect("Unable to run SQL");
+    }
+
+    /// A connection of its own to this database's schema, for code that wants to own one
+    pub fn another_pg(&self) -> PgConnection {
+        let conn = establish_connection("");
+        let schema = self.schema.as_ref().expect("Not a Postgres test database");
+        conn.batch_execute(&format!("SET search_path TO {};", schema))
+            .expect("Unable to use test schema");
+        conn
+    }
+
+    /// The underlying connection, for tests of Postgres-only functions.
+    pub fn pg(&self) -> &PgConnection {
+        match self.conn {
+            DbConnection::Postgres(ref conn) => conn,
+            #[cfg(feature = "sqlite")]
+            _ => panic!("Not a Postgres test database"),
+        }
+    }
+}
+
+impl Deref for TestDb {
+    type Target = DbConnection;
+
+    fn deref(&self) -> &DbConnection {
+        &self.conn
+    }
+}
+
+impl Drop for TestDb {
+    fn drop(&mut self) {
+        if let Some(ref schema) = self.schema {
+            let _ = self
+                .pg()
+                .batch_execute(&format!("DROP SCHEMA {} CASCADE", schema));
+        }
+    }
+}
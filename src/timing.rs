@@ -14,7 +14,6 @@ pub fn get_rss(website: &str, _cached_etag: Option<&str>) -> Result<(String, Str
         .header(ETAG, "Blah")
         .send()
         .chain_err(|| "Website not reached")?;
-    println!("{:#?}", &res);
     // Todo figure how etags work
     let etag = "NO TAG".to_string();
 
@@ -48,5 +47,4 @@ mod timing_test {
         }
         assert!(true);
     }
-
 }
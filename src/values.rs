@@ -0,0 +1,64 @@
+//! Numbers and dates as the SEC's structured forms write them
+//!
+//! Ownership reports, 13Fs, Form Ds, Schedules 13D and 13G and XBRL all write
+//! amounts with thousands separators and dates as `yyyy-mm-dd`. Dates are stored
+//! as `yyyymmdd` numbers, like the filings table's.
+use chrono::{Datelike, NaiveDate};
+
+/// An amount, which may have thousands separators or a `%` after it. Cover pages
+/// write nothing at all as `-0-`.
+pub(crate) fn number(text: &str) -> Option<f64> {
+    let text = text.trim();
+    if text == "-0-" {
+        return Some(0.0);
+    }
+    text.replace([',', '%'], "").trim().parse::<f64>().ok()
+}
+
+/// An amount rounded to a whole number, or `None` if it's negative
+pub(crate) fn whole_number(text: &str) -> Option<u64> {
+    number(text).filter(|n| *n >= 0.0).map(|n| n.round() as u64)
+}
+
+/// A `yyyy-mm-dd` date, ignoring any time or UTC offset after it
+pub(crate) fn date(text: &str) -> Option<NaiveDate> {
+    let text = text.trim();
+    NaiveDate::parse_from_str(text.get(..10).unwrap_or(text), "%Y-%m-%d").ok()
+}
+
+/// A date as stored, `yyyymmdd` like the filings table's
+pub(crate) fn to_number(date: NaiveDate) -> i32 {
+    date.year() * 10000 + date.month() as i32 * 100 + date.day() as i32
+}
+
+pub(crate) fn from_number(date: i32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(date / 10000, (date / 100 % 100) as u32, (date % 100) as u32)
+}
+
+#[cfg(test)]
+mod values_tests {
+    use super::*;
+
+    #[test]
+    fn number_test() {
+        assert_eq!(number("1,234.5"), Some(1234.5));
+        assert_eq!(number(" 5.2% "), Some(5.2));
+        assert_eq!(number("-0-"), Some(0.0));
+        assert_eq!(number("n/a"), None);
+
+        assert_eq!(whole_number("1,234.5"), Some(1235));
+        assert_eq!(whole_number("-3"), None);
+    }
+
+    #[test]
+    fn date_test() {
+        let day = NaiveDate::from_ymd_opt(2018, 10, 15).unwrap();
+        assert_eq!(date("2018-10-15"), Some(day));
+        assert_eq!(date("2018-10-15-05:00"), Some(day));
+        assert_eq!(date("10/15/2018"), None);
+
+        assert_eq!(to_number(day), 20181015);
+        assert_eq!(from_number(20181015), Some(day));
+        assert_eq!(from_number(20181315), None);
+    }
+}
@@ -0,0 +1,322 @@
+//! A `Sink` that pushes new filings to an HTTP endpoint
+//!
+//! Each batch goes out as one POST whose body is a JSON array of filings. With a
+//! secret set, the body is signed with HMAC-SHA256 and the hex digest sent as
+//! `X-Sec-Scraper-Signature: sha256=<digest>`, so the receiver can check it came
+//! from us. Failed deliveries are retried with a doubling delay for up to a
+//! minute, and a batch that still can't be delivered goes to the dead-letter file
+//! if there is one.
+use crate::errors::*;
+use crate::sec_entry::SECEntry;
+use crate::sink::Sink;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const SIGNATURE_HEADER: &str = "X-Sec-Scraper-Signature";
+
+const DEFAULT_ATTEMPTS: u32 = 4;
+const DEFAULT_BACKOFF: Duration = Duration::from_millis(500);
+const DEFAULT_DEADLINE: Duration = Duration::from_secs(60);
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct WebhookSink {
+    url: String,
+    client: reqwest::Client,
+    secret: Option<Vec<u8>>,
+    attempts: u32,
+    backoff: Duration,
+    deadline: Duration,
+    dead_letter: Option<PathBuf>,
+}
+
+/// One line of the dead-letter file
+#[derive(Serialize)]
+struct DeadLetter<'a> {
+    url: &'a str,
+    error: String,
+    entries: &'a [SECEntry],
+}
+
+impl WebhookSink {
+    pub fn new(url: &str) -> Result<WebhookSink> {
+        let client = reqwest::Client::builder()
+            .timeout(TIMEOUT)
+            .build()
+            .chain_err(|| "Unable to build HTTP client")?;
+
+        Ok(WebhookSink {
+            url: url.to_owned(),
+            client,
+            secret: None,
+            attempts: DEFAULT_ATTEMPTS,
+            backoff: DEFAULT_BACKOFF,
+            deadline: DEFAULT_DEADLINE,
+            dead_letter: None,
+        })
+    }
+
+    /// Signs every request body with `secret`
+    pub fn secret(mut self, secret: &[u8]) -> WebhookSink {
+        self.secret = Some(secret.to_vec());
+        self
+    }
+
+    /// Tries each batch up to `attempts` times, waiting `backoff` after the first
+    /// failure and twice as long after each one after that
+    pub fn retries(mut self, attempts: u32, backoff: Duration) -> WebhookSink {
+        self.attempts = attempts.max(1);
+        self.backoff = backoff;
+        self
+    }
+
+    /// Gives up on a batch rather than wait for another attempt that would start
+    /// more than `deadline` after the first. One already under way still gets
+    /// the request timeout of 30 seconds to finish.
+    pub fn deadline(mut self, deadline: Duration) -> WebhookSink {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Appends batches that run out of attempts to `path`, one JSON object per
+    /// line, instead of failing the scrape
+    pub fn dead_letter<P: Into<PathBuf>>(mut self, path: P) -> WebhookSink {
+        self.dead_letter = Some(path.into());
+        self
+    }
+
+    fn post(&self, body: &[u8]) -> Result<()> {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_vec());
+        if let Some(ref secret) = self.secret {
+            request = request.header(SIGNATURE_HEADER, signature(secret, body));
+        }
+
+        let response = request
+            .send()
+            .chain_err(|| format!("Unable to reach {}", self.url))?;
+        if !response.status().is_success() {
+            bail!("{} answered {}", self.url, response.status());
+        }
+        Ok(())
+    }
+
+    fn write_dead_letter(&self, entries: &[SECEntry], error: &Error) -> Result<()> {
+        let path = match self.dead_letter {
+            Some(ref path) => path,
+            None => return Ok(()),
+        };
+        let letter = DeadLetter {
+            url: &self.url,
+            error: error.to_string(),
+            entries,
+        };
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .chain_err(|| format!("{:#?} not found", path))?;
+        let mut line = serde_json::to_vec(&letter).chain_err(|| "Write failed")?;
+        line.push(b'\n');
+        file.write_all(&line).chain_err(|| "Write failed")
+    }
+}
+
+impl Sink for WebhookSink {
+    fn emit(&mut self, entries: &[SECEntry]) -> Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let body = serde_json::to_vec(entries).chain_err(|| "Unable to encode filings")?;
+
+        let started = Instant::now();
+        let mut delay = self.backoff;
+        let mut attempt = 1;
+        let error = loop {
+            match self.post(&body) {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt >= self.attempts => break e,
+                Err(e) if started.elapsed() + delay > self.deadline => break e,
+                Err(_) => {
+                    thread::sleep(delay);
+                    delay *= 2;
+                    attempt += 1;
+                }
+            }
+        };
+
+        if self.dead_letter.is_none() {
+            return Err(error);
+        }
+        self.write_dead_letter(entries, &error)
+    }
+}
+
+/// `sha256=` and the hex HMAC-SHA256 of `body` under `secret`
+pub fn signature(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC takes keys of any size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod webhook_tests {
+    use super::*;
+    use crate::sec_entry::FilingType;
+    use std::io::{BufRead, BufReader, Read};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    struct Request {
+        headers: Vec<String>,
+        body: Vec<u8>,
+    }
+
+    /// Answers one request per status in `statuses`, sending back what it was sent
+    fn stand_in(statuses: Vec<u16>) -> (String, mpsc::Receiver<Request>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            for status in statuses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut headers = Vec::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim_end().is_empty() {
+                        break;
+                    }
+                    headers.push(line.trim_end().to_owned());
+                }
+                let length = headers
+                    .iter()
+                    .filter_map(|h| {
+                        let lower = h.to_lowercase();
+                        lower
+                            .strip_prefix("content-length:")
+                            .map(|l| l.trim().parse::<usize>().unwrap())
+                    })
+                    .next()
+                    .unwrap_or(0);
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 {} Stand-in\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                )
+                .unwrap();
+                sender.send(Request { headers, body }).unwrap();
+            }
+        });
+
+        (url, receiver)
+    }
+
+    fn entry(acc: usize) -> SECEntry {
+        SECEntry::new(
+            FilingType::Sec8K,
+            String::from("Name"),
+            1,
+            acc,
+            20261019,
+            String::from("12:00:00"),
+        )
+    }
+
+    fn header<'a>(request: &'a Request, name: &str) -> Option<&'a str> {
+        let prefix = format!("{}:", name.to_lowercase());
+        request
+            .headers
+            .iter()
+            .find(|h| h.to_lowercase().starts_with(&prefix))
+            .map(|h| h[prefix.len()..].trim())
+    }
+
+    #[test]
+    fn signed_delivery_test() {
+        let (url, requests) = stand_in(vec![200]);
+        let mut sink = WebhookSink::new(&url).unwrap().secret(b"key");
+        sink.emit(&[entry(1), entry(2)]).unwrap();
+
+        let request = requests.recv().unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(value[1]["accession_number"], 2);
+        assert_eq!(
+            header(&request, SIGNATURE_HEADER),
+            Some(signature(b"key", &request.body).as_str())
+        );
+    }
+
+    #[test]
+    fn retry_test() {
+        let (url, requests) = stand_in(vec![500, 503, 204]);
+        let mut sink = WebhookSink::new(&url)
+            .unwrap()
+            .retries(3, Duration::from_millis(1));
+        sink.emit(&[entry(3)]).unwrap();
+
+        assert_eq!(requests.iter().take(3).count(), 3);
+    }
+
+    #[test]
+    fn deadline_test() {
+        let (url, requests) = stand_in(vec![500, 500]);
+        let mut sink = WebhookSink::new(&url)
+            .unwrap()
+            .retries(10, Duration::from_millis(20))
+            .deadline(Duration::from_millis(30));
+        assert!(sink.emit(&[entry(6)]).is_err());
+
+        // Waiting 20ms then 40ms would run past the deadline, so it stops at two
+        assert_eq!(requests.iter().count(), 2);
+    }
+
+    #[test]
+    fn dead_letter_test() {
+        let (url, requests) = stand_in(vec![500, 500]);
+        let path = std::env::temp_dir().join(format!("dead_letter_{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut sink = WebhookSink::new(&url)
+            .unwrap()
+            .retries(2, Duration::from_millis(1));
+        assert!(sink.emit(&[entry(4)]).is_err());
+
+        let (url, _) = stand_in(vec![500, 500]);
+        let mut sink = WebhookSink::new(&url)
+            .unwrap()
+            .retries(2, Duration::from_millis(1))
+            .dead_letter(&path);
+        sink.emit(&[entry(5)]).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let letter: serde_json::Value = serde_json::from_str(contents.trim()).unwrap();
+        assert_eq!(letter["url"], url.as_str());
+        assert_eq!(letter["entries"][0]["accession_number"], 5);
+        assert_eq!(requests.iter().take(2).count(), 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn signature_test() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            signature(b"Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
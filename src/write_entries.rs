@@ -1,129 +1,680 @@
-use std::fs::File;
-use std::io::Write;
-use std::path::Path;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
+use chrono::{NaiveDate, Utc};
+
+use crate::columns::Columns;
 use crate::errors::*;
 use crate::sec_entry::SECEntry;
 
+/// How new filings are laid out in the output file
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Format {
+    /// Delimited text with a header row, see `write_delimited`
+    Table(u8),
+    /// One JSON object per line, so readers can stream it
+    JsonLines,
+    /// A single pretty printed JSON array
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    /// Parses `--format` values; tables come out tab separated
+    fn from_str(s: &str) -> Result<Format> {
+        match s {
+            "tsv" => Ok(Format::Table(b'\t')),
+            "csv" => Ok(Format::Table(b',')),
+            "jsonl" => Ok(Format::JsonLines),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("Unknown format {:?}", s).into()),
+        }
+    }
+}
+
+/// When an appending `OutputFile` moves on to a new file
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Rotation {
+    /// Keep appending to the same file
+    Never,
+    /// One file per UTC day, with the date before the extension, e.g. `filings-2026-10-18.tsv`
+    Daily,
+    /// Once the file holds at least this many bytes, move it aside as `filings.1.tsv`,
+    /// `filings.2.tsv`, ... and start a fresh one
+    Size(u64),
+}
+
+impl FromStr for Rotation {
+    type Err = Error;
+
+    /// Parses `--rotate` values: `daily`, or a size in bytes with an optional `K`, `M` or `G`
+    fn from_str(s: &str) -> Result<Rotation> {
+        if s == "daily" {
+            return Ok(Rotation::Daily);
+        }
+        let (digits, scale) = match s.chars().last() {
+            Some('K') | Some('k') => (&s[..s.len() - 1], 1 << 10),
+            Some('M') | Some('m') => (&s[..s.len() - 1], 1 << 20),
+            Some('G') | Some('g') => (&s[..s.len() - 1], 1 << 30),
+            _ => (s, 1),
+        };
+        match digits.parse::<u64>() {
+            Ok(size) if size > 0 => size
+                .checked_mul(scale)
+                .map(Rotation::Size)
+                .chain_err(|| format!("Rotation size {:?} is too large", s)),
+            _ => Err(format!("Unknown rotation {:?}, expected daily or a size", s).into()),
+        }
+    }
+}
+
+/// Where new filings are written, and how.
+///
+/// -> By default each write replaces the file
+/// -> `append(true)` adds to it instead, writing the table header only when the file is new
+/// -> `rotation` decides when appending moves on to a new file
+///
+/// A replacing write goes to a temporary file beside the real one which is then renamed
+/// over it, so a reader never sees half a batch. Appends go straight onto the end of the
+/// file and are synced, and a JSON array just has its closing bracket moved along, so
+/// each write costs the size of the batch rather than of the file. Either way a failed
+/// write leaves the old file as it was.
+#[derive(Debug, Clone)]
+pub struct OutputFile {
+    path: PathBuf,
+    format: Format,
+    columns: Columns,
+    append: bool,
+    rotation: Rotation,
+}
+
+impl OutputFile {
+    pub fn new<P: Into<PathBuf>>(path: P, format: Format) -> OutputFile {
+        OutputFile {
+            path: path.into(),
+            format,
+            columns: Columns::default(),
+            append: false,
+            rotation: Rotation::Never,
+        }
+    }
+
+    pub fn append(mut self, append: bool) -> OutputFile {
+        self.append = append;
+        self
+    }
+
+    /// Picks the columns of table output
+    pub fn columns(mut self, columns: Columns) -> OutputFile {
+        self.columns = columns;
+        self
+    }
+
+    pub fn rotation(mut self, rotation: Rotation) -> OutputFile {
+        self.rotation = rotation;
+        self
+    }
+
+    /// The file a write on `day` goes to
+    pub fn path_on(&self, day: NaiveDate) -> PathBuf {
+        match self.rotation {
+            Rotation::Daily => with_suffix(&self.path, &format!("-{}", day.format("%Y-%m-%d"))),
+            Rotation::Never | Rotation::Size(_) => self.path.clone(),
+        }
+    }
+
+    /// Writes `entries`, returning the path they went to
+    pub fn write(&self, entries: &[SECEntry]) -> Result<PathBuf> {
+        self.write_on(entries, Utc::now().naive_utc().date())
+    }
+
+    fn write_on(&self, entries: &[SECEntry], day: NaiveDate) -> Result<PathBuf> {
+        let path = self.path_on(day);
+        if !self.append {
+            write_atomically(&path, |file| {
+                write_body(file, entries, self.format, &self.columns, true)
+            })?;
+            return Ok(path);
+        }
+
+        if let Rotation::Size(max) = self.rotation {
+            if fs::metadata(&path).map(|m| m.len() >= max).unwrap_or(false) {
+                rotate_aside(&path)?;
+            }
+        }
+
+        match self.format {
+            Format::Json => append_json(&path, entries)?,
+            format => {
+                let new = fs::metadata(&path).map(|m| m.len() == 0).unwrap_or(true);
+                if let (Format::Table(delimiter), false) = (format, new) {
+                    check_header(&path, delimiter, &self.columns)?;
+                }
+                append_to(&path, |file| {
+                    write_body(file, entries, format, &self.columns, new)
+                })?;
+            }
+        }
+        Ok(path)
+    }
+}
+
+/// Writes `entries` to `path` in `format`, replacing whatever was there.
+pub fn write_formatted(path: &Path, entries: Vec<SECEntry>, format: Format) -> Result<()> {
+    OutputFile::new(path, format).write(&entries).map(|_| ())
+}
+
+/// Writes each entry as a JSON object on its own line
+pub fn write_json_lines<W: Write>(writer: &mut W, entries: &[SECEntry]) -> Result<()> {
+    for entry in entries {
+        serde_json::to_writer(&mut *writer, entry).chain_err(|| "Write failed")?;
+        writer.write_all(b"\n").chain_err(|| "Write failed")?;
+    }
+    Ok(())
+}
+
+/// Writes all of `entries` as one pretty printed JSON array
+pub fn write_json<W: Write>(writer: &mut W, entries: &[SECEntry]) -> Result<()> {
+    serde_json::to_writer_pretty(&mut *writer, entries).chain_err(|| "Write failed")?;
+    writer.write_all(b"\n").chain_err(|| "Write failed")
+}
+
+/// Writes `entries` to `path` as tab separated values, header first.
 pub fn write_table(path: &Path, entries: Vec<SECEntry>) -> Result<()> {
-    let mut file = File::create(path).chain_err(|| format!("{:#?} not found", path))?;
+    write_delimited(path, entries, b'\t')
+}
 
-    write!(
-        file,
-        "Filing Type\tName\tCIK\tAccession Number\tDate\tTime\nUrl\n"
-    )
-    .expect("Could not write file");
-    write_entries(file, entries)
+/// Writes `entries` to `path` as RFC 4180 CSV, separating fields with `delimiter`.
+/// Any field holding the delimiter, a quote or a line break is quoted, so names
+/// with tabs or newlines in them can't shift the columns.
+pub fn write_delimited(path: &Path, entries: Vec<SECEntry>, delimiter: u8) -> Result<()> {
+    write_formatted(path, entries, Format::Table(delimiter))
 }
 
-fn write_entries(mut file: File, entries: Vec<SECEntry>) -> Result<()> {
-    let mut entries: Vec<String> = entries
-        .iter()
-        .map(|entry| entry.string())
-        .collect::<Vec<String>>();
+/// Writes `entries` in `format`, starting tables with a header row if `header` is set.
+/// Tables have `columns`; JSON always has every field.
+pub fn write_body<W: Write>(
+    writer: W,
+    entries: &[SECEntry],
+    format: Format,
+    columns: &Columns,
+    header: bool,
+) -> Result<()> {
+    let mut writer = BufWriter::new(writer);
+    match format {
+        Format::Table(delimiter) => {
+            let mut writer = table_writer(writer, delimiter);
+            if header {
+                writer
+                    .write_record(columns.header())
+                    .chain_err(|| "Write failed")?;
+            }
+            return write_entries(&mut writer, entries, columns);
+        }
+        Format::JsonLines => write_json_lines(&mut writer, entries)?,
+        Format::Json => write_json(&mut writer, entries)?,
+    }
+    writer.flush().chain_err(|| "Write failed")
+}
 
-    entries.iter_mut().for_each(|entry| entry.push('\n'));
+/// A CSV writer ending records with CRLF, as RFC 4180 has it. Tab separated
+/// tables keep the plain newlines they have always had.
+fn table_writer<W: Write>(writer: W, delimiter: u8) -> csv::Writer<W> {
+    let terminator = if delimiter == b'\t' {
+        csv::Terminator::Any(b'\n')
+    } else {
+        csv::Terminator::CRLF
+    };
+    csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .terminator(terminator)
+        .from_writer(writer)
+}
 
-    entries
-        .iter()
-        .map(|entry| file.write_all(entry.as_bytes()))
-        .fold(Ok(()), |acc, x| acc.and(x))
-        .chain_err(|| "Write failed")?;
+fn write_entries<W: Write>(
+    writer: &mut csv::Writer<W>,
+    entries: &[SECEntry],
+    columns: &Columns,
+) -> Result<()> {
+    for entry in entries {
+        writer
+            .write_record(columns.record(entry))
+            .chain_err(|| "Write failed")?;
+    }
+    writer.flush().chain_err(|| "Write failed")?;
+    Ok(())
+}
+
+/// Refuses to append to a table whose header names other columns than `columns`,
+/// which would leave rows that don't line up with it
+fn check_header(path: &Path, delimiter: u8, columns: &Columns) -> Result<()> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .from_path(path)
+        .chain_err(|| format!("{:#?} not found", path))?;
+    let mut header = csv::StringRecord::new();
+    reader
+        .read_record(&mut header)
+        .chain_err(|| format!("Could not read {:#?}", path))?;
+    if header.iter().ne(columns.header()) {
+        bail!(
+            "{:#?} has the columns {:?}, not {:?}; start a new file to change them",
+            path,
+            header.iter().collect::<Vec<&str>>(),
+            columns.header()
+        );
+    }
     Ok(())
 }
 
+/// Runs `write` against a fresh temporary file beside `path`, then renames it into place.
+fn write_atomically<F>(path: &Path, write: F) -> Result<()>
+where
+    F: FnOnce(&mut File) -> Result<()>,
+{
+    let temp = with_prefix(path, ".");
+    let temp = with_suffix(&temp, ".tmp");
+
+    let result = (|| {
+        let mut file = File::create(&temp).chain_err(|| format!("{:#?} not found", temp))?;
+        write(&mut file)?;
+        file.sync_all().chain_err(|| "Write failed")?;
+        fs::rename(&temp, path).chain_err(|| format!("Could not replace {:#?}", path))
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Moves a full file out of the way to the first free `name.N.ext`
+fn rotate_aside(path: &Path) -> Result<()> {
+    let rotated = (1..)
+        .map(|n| with_suffix(path, &format!(".{}", n)))
+        .find(|p| !p.exists())
+        .unwrap();
+    fs::rename(path, &rotated).chain_err(|| format!("Could not rotate {:#?}", path))
+}
+
+/// Runs `write` against the end of `path`, creating it if need be, and syncs it.
+/// If the write fails the file is cut back to the length it had.
+fn append_to<F>(path: &Path, write: F) -> Result<()>
+where
+    F: FnOnce(&mut File) -> Result<()>,
+{
+    let mut file = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)
+        .chain_err(|| format!("{:#?} not found", path))?;
+    let len = file.metadata().chain_err(|| "Write failed")?.len();
+
+    let result = write(&mut file).and_then(|_| file.sync_all().chain_err(|| "Write failed"));
+    if result.is_err() {
+        let _ = file.set_len(len);
+    }
+    result
+}
+
+/// How much of the end of a JSON array to read looking for its closing bracket
+const JSON_TAIL: u64 = 4096;
+
+/// Adds `entries` to the JSON array in `path`, laid out as `write_json` lays out the
+/// whole array, by writing over the closing bracket rather than the whole file
+fn append_json(path: &Path, entries: &[SECEntry]) -> Result<()> {
+    if fs::metadata(path).map(|m| m.len() == 0).unwrap_or(true) {
+        return append_to(path, |file| write_json(file, entries));
+    }
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .chain_err(|| format!("{:#?} not found", path))?;
+    let len = file.metadata().chain_err(|| "Write failed")?.len();
+    let start = len.saturating_sub(JSON_TAIL);
+    let mut tail = Vec::new();
+    file.seek(SeekFrom::Start(start))
+        .and_then(|_| file.read_to_end(&mut tail))
+        .chain_err(|| format!("Could not read {:#?}", path))?;
+
+    let not_array = || format!("{:#?} is not a JSON array", path);
+    let close = tail
+        .iter()
+        .rposition(|&b| b == b']')
+        .filter(|&i| tail[i + 1..].iter().all(u8::is_ascii_whitespace))
+        .chain_err(not_array)?;
+    let last = tail[..close]
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .chain_err(not_array)?;
+    let cut = start + last as u64 + 1;
+
+    // `[\n  {...},\n  {...}\n]` less its brackets goes in after the last element
+    let array = serde_json::to_vec_pretty(entries).chain_err(|| "Write failed")?;
+    let mut addition = Vec::new();
+    if tail[last] != b'[' {
+        addition.push(b',');
+    }
+    addition.extend_from_slice(&array[1..array.len() - 1]);
+    addition.extend_from_slice(b"]\n");
+
+    let result = file
+        .set_len(cut)
+        .and_then(|_| file.seek(SeekFrom::Start(cut)))
+        .and_then(|_| file.write_all(&addition))
+        .and_then(|_| file.sync_all())
+        .chain_err(|| "Write failed");
+    if result.is_err() {
+        let _ = file
+            .set_len(cut)
+            .and_then(|_| file.seek(SeekFrom::Start(cut)))
+            .and_then(|_| file.write_all(&tail[last + 1..]));
+    }
+    result
+}
+
+/// `dir/name.ext` with `suffix` put between the name and the extension
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{}{}.{}", stem, suffix, ext.to_string_lossy()),
+        None => format!("{}{}", stem, suffix),
+    };
+    path.with_file_name(name)
+}
+
+fn with_prefix(path: &Path, prefix: &str) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}{}", prefix, name))
+}
+
 #[cfg(test)]
 mod write_entries_tests {
     use super::*;
-    use crate::sec_entry::FilingType;
+    use crate::sec_entry::{FilingRole, FilingType};
     use std::fmt::Write as FmtWrite;
-    use std::io::Read;
 
-    #[test]
-    fn write_table_test_basic() {
-        let name = String::from("asdf.txt");
-        File::create(&name).expect("unable to open file");
-        assert!(write_entries(File::open(&name).unwrap(), vec![]).is_ok());
-        std::fs::remove_file(&name).is_ok();
+    fn write_to_string(entries: Vec<SECEntry>, delimiter: u8) -> String {
+        let mut writer = table_writer(Vec::new(), delimiter);
+        write_entries(&mut writer, &entries, &Columns::default()).expect("Write failed");
+        String::from_utf8(writer.into_inner().unwrap()).unwrap()
     }
 
-    #[test]
-    fn write_table_test_intermediate() {
-        let name = String::from("int.txt");
-        let file = File::create(&name).unwrap();
-
-        let entry = SECEntry::new(
+    fn bollocks() -> SECEntry {
+        SECEntry::new(
             FilingType::SecS1,
             String::from("Bollocks"),
             0,
             0,
             0,
             String::from("Also Bollocks"),
+        )
+    }
+
+    #[test]
+    fn write_table_test_basic() {
+        assert_eq!(write_to_string(vec![], b'\t'), "");
+    }
+
+    #[test]
+    fn write_table_test_intermediate() {
+        let oracle = format!(
+            "S-1/A\tBollocks\t0\t0\t0\tAlso Bollocks\t{}\t\n",
+            SECEntry::get_url(0, 0),
         );
 
-        println!("{:#?}", entry);
+        assert_eq!(write_to_string(vec![bollocks()], b'\t'), oracle);
+    }
 
-        assert!(write_entries(file, vec![entry]).is_ok());
+    #[test]
+    fn write_table_test_advanced() {
+        let entry2 = bollocks().with_role(Some(FilingRole::Filer));
+        let url = SECEntry::get_url(0, 0);
 
-        let mut f = File::open(&name).expect("file not found");
+        let mut oracle = String::new();
+        write!(oracle,
+            "S-1/A\tBollocks\t0\t0\t0\tAlso Bollocks\t{}\t\nS-1/A\tBollocks\t0\t0\t0\tAlso Bollocks\t{}\tFiler\n",&url,&url).unwrap();
 
-        let mut string = String::new();
+        assert_eq!(oracle, write_to_string(vec![bollocks(), entry2], b'\t'));
+    }
 
-        f.read_to_string(&mut string);
+    #[test]
+    fn write_table_test_quoting() {
+        let entry = SECEntry::new(
+            FilingType::Sec4,
+            String::from("Tab\tNew\nLine \"Quoted\", Inc."),
+            1,
+            2,
+            3,
+            String::from("Time"),
+        );
+        let url = SECEntry::get_url(1, 2);
 
-        let mut oracle = String::new();
-        write!(
-            oracle,
-            "SecS1\tBollocks\t0\t0\t0\tAlso Bollocks\t{}\n",
-            SECEntry::get_url(0, 0),
+        assert_eq!(
+            write_to_string(vec![entry], b','),
+            format!(
+                "4,\"Tab\tNew\nLine \"\"Quoted\"\", Inc.\",1,2,3,Time,{},\r\n",
+                url
+            )
         );
+    }
 
-        assert_eq!(string, oracle);
+    #[test]
+    fn write_json_lines_test() {
+        let entries = vec![bollocks(), bollocks().with_role(Some(FilingRole::FiledBy))];
+        let mut out = Vec::new();
+        write_json_lines(&mut out, &entries).unwrap();
 
-        assert!(std::fs::remove_file(&name).is_ok());
+        let out = String::from_utf8(out).unwrap();
+        let lines = out.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[1],
+            format!(
+                "{{\"filing_type\":\"S-1/A\",\"name\":\"Bollocks\",\"cik\":0,\
+                 \"accession_number\":0,\"date\":0,\"timestamp\":\"Also Bollocks\",\
+                 \"url\":\"{}\",\"role\":\"Filed by\"}}",
+                SECEntry::get_url(0, 0)
+            )
+        );
+        assert!(lines[0].ends_with("\"role\":null}"));
     }
 
     #[test]
-    fn write_table_test_advanced() {
-        let name = String::from("adv.txt");
+    fn write_json_test() {
+        let mut out = Vec::new();
+        write_json(&mut out, &[bollocks(), bollocks()]).unwrap();
 
-        let file = File::create(&name).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        let array = value.as_array().unwrap();
+        assert_eq!(array.len(), 2);
+        assert_eq!(array[0]["filing_type"], "S-1/A");
+        assert_eq!(array[1]["name"], "Bollocks");
+    }
 
-        let entry1 = SECEntry::new(
-            FilingType::SecS1,
-            String::from("Bollocks"),
-            0,
-            0,
-            0,
-            String::from("Also Bollocks"),
-        );
+    #[test]
+    fn format_from_str_test() {
+        assert_eq!("tsv".parse::<Format>().unwrap(), Format::Table(b'\t'));
+        assert_eq!("csv".parse::<Format>().unwrap(), Format::Table(b','));
+        assert_eq!("jsonl".parse::<Format>().unwrap(), Format::JsonLines);
+        assert_eq!("json".parse::<Format>().unwrap(), Format::Json);
+        assert!("xml".parse::<Format>().is_err());
+    }
 
-        let entry2 = SECEntry::new(
-            FilingType::SecS1,
-            String::from("Bollocks"),
-            0,
-            0,
-            0,
-            String::from("Also Bollocks"),
+    #[test]
+    fn write_table_test_header() {
+        let dir = scratch_dir("header");
+        let name = dir.join("header.txt");
+        write_table(&name, vec![bollocks()]).unwrap();
+
+        let string = std::fs::read_to_string(&name).expect("file not found");
+        let header = string.lines().next().unwrap();
+        assert_eq!(
+            header.split('\t').collect::<Vec<&str>>(),
+            Columns::default().header()
         );
+        assert_eq!(string.lines().count(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// An empty directory of its own for a test, under the system temp dir
+    fn scratch_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sec_scraper_{}_{}", std::process::id(), test));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, d).unwrap()
+    }
 
-        assert!(write_entries(file, vec![entry1, entry2]).is_ok());
+    #[test]
+    fn replace_test() {
+        let dir = scratch_dir("replace");
+        let output = OutputFile::new(dir.join("filings.tsv"), Format::Table(b'\t'));
+        output.write(&[bollocks(), bollocks()]).unwrap();
+        let path = output.write(&[bollocks()]).unwrap();
 
-        let mut f = File::open(&name).expect("file not found");
-        let mut string = String::new();
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
 
-        f.read_to_string(&mut string);
+    #[test]
+    fn append_test() {
+        let dir = scratch_dir("append");
+        let output = OutputFile::new(dir.join("filings.tsv"), Format::Table(b'\t')).append(true);
+        output.write(&[bollocks()]).unwrap();
+        let path = output.write(&[bollocks(), bollocks()]).unwrap();
 
-        let url = SECEntry::get_url(0, 0);
+        let string = fs::read_to_string(&path).unwrap();
+        assert_eq!(string.lines().count(), 4);
+        assert_eq!(string.matches("Filing Type").count(), 1);
+        // Nothing left behind but the table itself
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
 
-        let mut oracle = String::new();
-        write!(oracle,
-            "SecS1\tBollocks\t0\t0\t0\tAlso Bollocks\t{}\nSecS1\tBollocks\t0\t0\t0\tAlso Bollocks\t{}\n",&url,&url);
+    #[test]
+    fn append_json_test() {
+        let dir = scratch_dir("append_json");
+        let output = OutputFile::new(dir.join("filings.json"), Format::Json).append(true);
+        output.write(&[bollocks()]).unwrap();
+        let path = output.write(&[bollocks(), bollocks()]).unwrap();
+
+        let value: serde_json::Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(value.as_array().unwrap().len(), 3);
 
-        println!("{}", string);
-        assert_eq!(oracle, string);
+        // Laid out just as if the array had been written in one go
+        let mut whole = Vec::new();
+        write_json(&mut whole, &[bollocks(), bollocks(), bollocks()]).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), whole);
 
-        assert!(std::fs::remove_file(&name).is_ok());
+        let empty = dir.join("empty.json");
+        fs::write(&empty, "[]\n").unwrap();
+        OutputFile::new(&empty, Format::Json)
+            .append(true)
+            .write(&[bollocks()])
+            .unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&fs::read(&empty).unwrap()).unwrap();
+        assert_eq!(value.as_array().unwrap().len(), 1);
+
+        let broken = dir.join("broken.json");
+        fs::write(&broken, "{}\n").unwrap();
+        let output = OutputFile::new(&broken, Format::Json).append(true);
+        assert!(output.write(&[bollocks()]).is_err());
+        assert_eq!(fs::read_to_string(&broken).unwrap(), "{}\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn daily_rotation_test() {
+        let dir = scratch_dir("daily");
+        let output = OutputFile::new(dir.join("filings.tsv"), Format::JsonLines)
+            .append(true)
+            .rotation(Rotation::Daily);
+        let first = output.write_on(&[bollocks()], day(18)).unwrap();
+        output.write_on(&[bollocks()], day(18)).unwrap();
+        let second = output.write_on(&[bollocks()], day(19)).unwrap();
+
+        assert_eq!(first, dir.join("filings-2026-10-18.tsv"));
+        assert_eq!(second, dir.join("filings-2026-10-19.tsv"));
+        assert_eq!(fs::read_to_string(&first).unwrap().lines().count(), 2);
+        assert_eq!(fs::read_to_string(&second).unwrap().lines().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn size_rotation_test() {
+        let dir = scratch_dir("size");
+        let output = OutputFile::new(dir.join("filings.tsv"), Format::Table(b'\t'))
+            .append(true)
+            .rotation(Rotation::Size(1));
+        output.write(&[bollocks()]).unwrap();
+        output.write(&[bollocks()]).unwrap();
+        output.write(&[bollocks()]).unwrap();
+
+        for name in &["filings.tsv", "filings.1.tsv", "filings.2.tsv"] {
+            let string = fs::read_to_string(dir.join(name)).unwrap();
+            assert!(string.starts_with("Filing Type"));
+            assert_eq!(string.lines().count(), 2);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn columns_test() {
+        let dir = scratch_dir("columns");
+        let output = OutputFile::new(dir.join("filings.csv"), Format::Table(b','))
+            .columns("cik_padded,form".parse().unwrap());
+        let path = output.write(&[bollocks()]).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "CIK (Padded),Filing Type\r\n0000000000,S-1/A\r\n"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn append_columns_test() {
+        let dir = scratch_dir("append_columns");
+        let path = dir.join("filings.csv");
+        let output = OutputFile::new(&path, Format::Table(b',')).append(true);
+        output
+            .clone()
+            .columns("cik,form".parse().unwrap())
+            .write(&[bollocks()])
+            .unwrap();
+        output
+            .clone()
+            .columns("cik,form".parse().unwrap())
+            .write(&[bollocks()])
+            .unwrap();
+
+        // Other columns would no longer line up with the header
+        assert!(output.write(&[bollocks()]).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 3);
+        fs::remove_dir_all(&dir).unwrap();
     }
 
+    #[test]
+    fn rotation_from_str_test() {
+        assert_eq!("daily".parse::<Rotation>().unwrap(), Rotation::Daily);
+        assert_eq!("512".parse::<Rotation>().unwrap(), Rotation::Size(512));
+        assert_eq!("10M".parse::<Rotation>().unwrap(), Rotation::Size(10 << 20));
+        assert!("0".parse::<Rotation>().is_err());
+        assert!("weekly".parse::<Rotation>().is_err());
+        assert!("99999999999G".parse::<Rotation>().is_err());
+    }
 }
@@ -0,0 +1,786 @@
+//! Reads the XBRL financial data filed with 10-Ks and 10-Qs
+//!
+//! -> `parse_instance` reads an XBRL instance document, the `EX-101.INS` exhibit
+//!    filings carried before inline XBRL
+//! -> `parse_inline` reads the facts tagged in an inline XBRL document, the 10-K
+//!    or 10-Q itself since 2019
+//! -> `from_submission` and `fetch` find either among a filing's documents
+//! -> `write` and `load` keep the facts in the `xbrl_facts` table
+//! -> `line_item` picks a standard US-GAAP figure, like revenue, out of the facts
+//!
+//! A fact is one value of one concept for one context: a period, and for values
+//! broken down by segment, product and so on, the member of each axis.
+use crate::db::{on_backend, DbConnection};
+use crate::errors::*;
+use crate::models::XbrlFactRow;
+use crate::schema::xbrl_facts;
+use crate::sec_entry::SECEntry;
+use crate::submission::{self, Submission};
+use crate::values::{self, from_number, to_number};
+use crate::xml_tree::{self, Element};
+use chrono::NaiveDate;
+use diesel::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::io::BufRead;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Fact {
+    /// With its prefix, e.g. `us-gaap:Revenues`
+    pub concept: String,
+    /// The id of the context the fact was reported in
+    pub context: String,
+    /// e.g. `iso4217:USD`, or `iso4217:USD/xbrli:shares` for a ratio. Only
+    /// numeric facts have one.
+    pub unit: Option<String>,
+    pub period: Period,
+    /// The member of each axis, e.g. `us-gaap:StatementBusinessSegmentsAxis` to
+    /// `aapl:AmericasSegmentMember`
+    pub dimensions: BTreeMap<String, String>,
+    /// Numbers are written out in full, with inline XBRL's scale and sign applied
+    pub value: String,
+    /// How many decimal places the value is accurate to: `-6` for millions, or `INF`
+    pub decimals: Option<String>,
+}
+
+impl Fact {
+    /// The value of a numeric fact
+    pub fn number(&self) -> Option<f64> {
+        self.unit.as_ref().and_then(|_| self.value.parse().ok())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Period {
+    Instant(NaiveDate),
+    Duration(NaiveDate, NaiveDate),
+    Forever,
+}
+
+impl Period {
+    pub fn end(self) -> Option<NaiveDate> {
+        match self {
+            Period::Instant(date) | Period::Duration(_, date) => Some(date),
+            Period::Forever => None,
+        }
+    }
+}
+
+struct Context {
+    period: Period,
+    dimensions: BTreeMap<String, String>,
+}
+
+/// `prefix:name`, as concepts, members and measures are written
+fn qualified_name(element: &Element) -> String {
+    match &element.prefix {
+        Some(prefix) => format!("{}:{}", prefix, element.name),
+        None => element.name.clone(),
+    }
+}
+
+fn date(element: &Element, name: &str) -> Option<NaiveDate> {
+    element
+        .text_at(&[name])
+        // Dates may carry a time, though the SEC doesn't allow it
+        .and_then(|t| values::date(&t))
+}
+
+fn contexts(root: &Element) -> Result<HashMap<String, Context>> {
+    let mut contexts = HashMap::new();
+    for element in root.descendants("context") {
+        let id = element
+            .attribute("id")
+            .chain_err(|| "XBRL context without an id")?;
+        let period = element
+            .child("period")
+            .chain_err(|| format!("XBRL context {} has no period", id))?;
+        let period = if let Some(instant) = date(period, "instant") {
+            Period::Instant(instant)
+        } else if let (Some(start), Some(end)) =
+            (date(period, "startDate"), date(period, "endDate"))
+        {
+            Period::Duration(start, end)
+        } else if period.child("forever").is_some() {
+            Period::Forever
+        } else {
+            bail!("XBRL context {} has an unreadable period", id)
+        };
+
+        let mut dimensions = BTreeMap::new();
+        for member in element.descendants("explicitMember") {
+            if let (Some(axis), Some(value)) =
+                (member.attribute("dimension"), member.trimmed_text())
+            {
+                dimensions.insert(axis.to_owned(), value);
+            }
+        }
+        for member in element.descendants("typedMember") {
+            if let Some(axis) = member.attribute("dimension") {
+                dimensions.insert(axis.to_owned(), member.inner_text().trim().to_owned());
+            }
+        }
+        contexts.insert(id.to_owned(), Context { period, dimensions });
+    }
+    Ok(contexts)
+}
+
+fn units(root: &Element) -> HashMap<String, String> {
+    let measures = |element: Option<&Element>| {
+        element
+            .map(|e| {
+                e.children("measure")
+                    .filter_map(Element::trimmed_text)
+                    .collect::<Vec<String>>()
+                    .join("*")
+            })
+            .unwrap_or_default()
+    };
+    root.descendants("unit")
+        .into_iter()
+        .filter_map(|unit| {
+            let measure = match unit.child("divide") {
+                Some(divide) => format!(
+                    "{}/{}",
+                    measures(divide.child("unitNumerator")),
+                    measures(divide.child("unitDenominator"))
+                ),
+                None => measures(Some(unit)),
+            };
+            Some((unit.attribute("id")?.to_owned(), measure))
+        })
+        .collect()
+}
+
+/// The fact `element` reports of `concept`, or `None` if it's nil
+fn fact(
+    element: &Element,
+    concept: String,
+    value: String,
+    contexts: &HashMap<String, Context>,
+    units: &HashMap<String, String>,
+) -> Result<Option<Fact>> {
+    if element.attribute("nil") == Some("true") {
+        return Ok(None);
+    }
+    let id = element
+        .attribute("contextRef")
+        .chain_err(|| format!("{} has no context", concept))?;
+    let context = contexts
+        .get(id)
+        .chain_err(|| format!("{} refers to missing context {}", concept, id))?;
+    let unit = match element.attribute("unitRef") {
+        Some(unit) => Some(
+            units
+                .get(unit)
+                .chain_err(|| format!("{} refers to missing unit {}", concept, unit))?
+                .clone(),
+        ),
+        None => None,
+    };
+    Ok(Some(Fact {
+        concept,
+        context: id.to_owned(),
+        unit,
+        period: context.period,
+        dimensions: context.dimensions.clone(),
+        value,
+        decimals: element.attribute("decimals").map(str::to_owned),
+    }))
+}
+
+/// Reads the facts of an XBRL instance document, in the order they're reported
+pub fn parse_instance(xml: &str) -> Result<Vec<Fact>> {
+    let root = xml_tree::parse(xml).chain_err(|| "Unable to read XBRL instance")?;
+    if root.name != "xbrl" {
+        bail!("Not an XBRL instance: the root element is {}", root.name);
+    }
+    let contexts = contexts(&root)?;
+    let units = units(&root);
+    let mut facts = Vec::new();
+    for element in root
+        .children
+        .iter()
+        .filter(|e| e.attribute("contextRef").is_some())
+    {
+        let value = element.text.trim().to_owned();
+        facts.extend(fact(
+            element,
+            qualified_name(element),
+            value,
+            &contexts,
+            &units,
+        )?);
+    }
+    Ok(facts)
+}
+
+/// Reads the facts tagged in an inline XBRL document. The document has to be
+/// well-formed XHTML, as inline XBRL requires.
+pub fn parse_inline(xhtml: &str) -> Result<Vec<Fact>> {
+    let root = xml_tree::parse(xhtml).chain_err(|| "Unable to read inline XBRL")?;
+    let contexts = contexts(&root)?;
+    let units = units(&root);
+    let mut facts = Vec::new();
+    for element in inline_facts(&root) {
+        let concept = element
+            .attribute("name")
+            .chain_err(|| "Inline XBRL fact without a name")?
+            .to_owned();
+        let text = element.inner_text();
+        let value = if element.name == "nonFraction" {
+            inline_number(&text, element)
+                .chain_err(|| format!("{} has an unreadable value: {}", concept, text.trim()))?
+        } else {
+            text.split_whitespace().collect::<Vec<&str>>().join(" ")
+        };
+        facts.extend(fact(element, concept, value, &contexts, &units)?);
+    }
+    Ok(facts)
+}
+
+/// The `ix:nonFraction` and `ix:nonNumeric` elements, in document order, which
+/// may nest inside one another
+fn inline_facts(element: &Element) -> Vec<&Element> {
+    let mut found = Vec::new();
+    for child in &element.children {
+        let tagged = child.name == "nonFraction" || child.name == "nonNumeric";
+        if tagged && child.prefix.as_deref() == Some("ix") {
+            found.push(child);
+        }
+        found.extend(inline_facts(child));
+    }
+    found
+}
+
+/// The number an `ix:nonFraction` displays, written out in full
+fn inline_number(text: &str, element: &Element) -> Option<String> {
+    let text = text.trim();
+    let format = element
+        .attribute("format")
+        .map(|f| f.rsplit(':').next().unwrap_or(f))
+        .unwrap_or("");
+    let digits = match format {
+        // A dash standing in for zero
+        "fixed-zero" | "fixedzero" | "zerodash" if !text.chars().any(|c| c.is_ascii_digit()) => {
+            String::from("0")
+        }
+        "numwordsen" | "num-word-en" if !text.chars().any(|c| c.is_ascii_digit()) => {
+            number_words(text)?.to_string()
+        }
+        "num-comma-decimal" | "numcommadecimal" | "numdotcomma" => text
+            .chars()
+            .filter(|c| c.is_ascii_digit() || *c == ',')
+            .map(|c| if c == ',' { '.' } else { c })
+            .collect(),
+        _ => text
+            .chars()
+            .filter(|c| c.is_ascii_digit() || *c == '.')
+            .collect(),
+    };
+    if digits.is_empty() || digits.matches('.').count() > 1 {
+        return None;
+    }
+    let scale = match element.attribute("scale") {
+        Some(scale) => scale.parse().ok()?,
+        None => 0,
+    };
+    let number = shift(&digits, scale);
+    if element.attribute("sign") == Some("-") && number != "0" {
+        Some(format!("-{}", number))
+    } else {
+        Some(number)
+    }
+}
+
+/// Words for numbers below a hundred, along with "no" and "none" for zero
+const NUMBER_WORDS: [(&str, u64); 30] = [
+    ("no", 0),
+    ("none", 0),
+    ("zero", 0),
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("nine", 9),
+    ("ten", 10),
+    ("eleven", 11),
+    ("twelve", 12),
+    ("thirteen", 13),
+    ("fourteen", 14),
+    ("fifteen", 15),
+    ("sixteen", 16),
+    ("seventeen", 17),
+    ("eighteen", 18),
+    ("nineteen", 19),
+    ("twenty", 20),
+    ("thirty", 30),
+    ("forty", 40),
+    ("fifty", 50),
+    ("sixty", 60),
+    ("seventy", 70),
+    ("eighty", 80),
+    ("ninety", 90),
+];
+
+/// The value of a whole number written in English words, e.g. `twenty-one` or
+/// `one hundred and five`, or `None` if there's a word it doesn't know
+fn number_words(text: &str) -> Option<u64> {
+    let text = text.to_lowercase();
+    let words = text
+        .split(|c: char| c.is_whitespace() || c == '-' || c == ',' || c == '.')
+        .filter(|w| !w.is_empty() && *w != "and");
+    let (mut total, mut group, mut any) = (0u64, 0u64, false);
+    for word in words {
+        let scale = match word {
+            "thousand" => 1_000,
+            "million" => 1_000_000,
+            "billion" => 1_000_000_000,
+            "trillion" => 1_000_000_000_000,
+            _ => 0,
+        };
+        match word {
+            "hundred" if group > 0 => group *= 100,
+            _ if scale > 0 && group > 0 => {
+                total += group * scale;
+                group = 0;
+            }
+            _ => group += NUMBER_WORDS.iter().find(|(w, _)| *w == word)?.1,
+        }
+        any = true;
+    }
+    Some(total + group).filter(|_| any)
+}
+
+/// Moves the decimal point of `digits` `scale` places to the right, without
+/// going through floating point
+fn shift(digits: &str, scale: i32) -> String {
+    let (whole, fraction) = match digits.find('.') {
+        Some(i) => (&digits[..i], &digits[i + 1..]),
+        None => (digits, ""),
+    };
+    let mut all = format!("{}{}", whole, fraction);
+    let mut point = whole.len() as i64 + i64::from(scale);
+    if point < 0 {
+        all = format!("{}{}", "0".repeat(-point as usize), all);
+        point = 0;
+    }
+    while all.len() < point as usize {
+        all.push('0');
+    }
+    let (whole, fraction) = all.split_at(point as usize);
+    let whole = whole.trim_start_matches('0');
+    let fraction = fraction.trim_end_matches('0');
+    match (whole.is_empty(), fraction.is_empty()) {
+        (true, true) => String::from("0"),
+        (true, false) => format!("0.{}", fraction),
+        (false, true) => whole.to_owned(),
+        (false, false) => format!("{}.{}", whole, fraction),
+    }
+}
+
+/// Reads the facts among a complete submission's documents, from the instance
+/// exhibit if there is one, or else from every inline XBRL document
+pub fn from_submission<R: BufRead>(submission: Submission<R>) -> Result<Vec<Fact>> {
+    let mut inline = Vec::new();
+    for document in submission {
+        let document = document?;
+        let contents = document.text();
+        if document.document_type == "EX-101.INS" {
+            return parse_instance(&contents);
+        }
+        if contents.contains("<ix:header") {
+            inline.extend(parse_inline(&contents)?);
+        }
+    }
+    Ok(inline)
+}
+
+/// Downloads `entry`'s complete submission and reads its facts
+pub fn fetch(entry: &SECEntry) -> Result<Vec<Fact>> {
+    let facts = from_submission(submission::fetch(entry)?)?;
+    if facts.is_empty() {
+        bail!(
+            "No XBRL in {}",
+            SECEntry::dashed_accession(entry.accession_number())
+        );
+    }
+    Ok(facts)
+}
+
+/// Stores `facts` as those of filing `acc`, replacing any stored before
+pub fn write(conn: &DbConnection, acc: usize, facts: &[Fact]) -> Result<()> {
+    let acc = acc as i64;
+    let rows = facts
+        .iter()
+        .enumerate()
+        .map(|(i, f)| {
+            let (start, end) = match f.period {
+                Period::Instant(date) => (None, Some(to_number(date))),
+                Period::Duration(start, end) => (Some(to_number(start)), Some(to_number(end))),
+                Period::Forever => (None, None),
+            };
+            Ok(XbrlFactRow {
+                accession_number: acc,
+                position: i as i32,
+                concept: f.concept.clone(),
+                context: f.context.clone(),
+                unit: f.unit.clone(),
+                period_start: start,
+                period_end: end,
+                dimensions: serde_json::to_string(&f.dimensions)
+                    .chain_err(|| "Unable to encode dimensions")?,
+                value: f.value.clone(),
+                number: f.number(),
+                decimals: f.decimals.clone(),
+            })
+        })
+        .collect::<Result<Vec<XbrlFactRow>>>()?;
+
+    conn.transaction(|| {
+        on_backend!(conn, c => {
+            diesel::delete(xbrl_facts::table.filter(xbrl_facts::accession_number.eq(acc)))
+                .execute(c)?;
+            // A 10-K reports thousands of facts, more than fit in one statement
+            for chunk in rows.chunks(1000) {
+                diesel::insert_into(xbrl_facts::table)
+                    .values(chunk)
+                    .execute(c)?;
+            }
+            Ok::<_, diesel::result::Error>(())
+        })
+        .chain_err(|| "Unable to write XBRL facts")
+    })
+}
+
+/// The facts stored for filing `acc`, in the order they were reported
+pub fn load(conn: &DbConnection, acc: usize) -> Result<Vec<Fact>> {
+    let acc = acc as i64;
+    let rows = on_backend!(conn, c => {
+        xbrl_facts::table
+            .filter(xbrl_facts::accession_number.eq(acc))
+            .order(xbrl_facts::position)
+            .load::<XbrlFactRow>(c)
+    })
+    .chain_err(|| "Unable to load XBRL facts")?;
+
+    rows.into_iter()
+        .map(|r| {
+            let period = match (
+                r.period_start.and_then(from_number),
+                r.period_end.and_then(from_number),
+            ) {
+                (Some(start), Some(end)) => Period::Duration(start, end),
+                (None, Some(end)) => Period::Instant(end),
+                _ => Period::Forever,
+            };
+            Ok(Fact {
+                concept: r.concept,
+                context: r.context,
+                unit: r.unit,
+                period,
+                dimensions: serde_json::from_str(&r.dimensions)
+                    .chain_err(|| "Unable to decode dimensions")?,
+                value: r.value,
+                decimals: r.decimals,
+            })
+        })
+        .collect()
+}
+
+/// Standard US-GAAP line items
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LineItem {
+    Revenues,
+    NetIncomeLoss,
+    Assets,
+}
+
+impl LineItem {
+    /// The concepts that report it, in order of preference. Most companies have
+    /// tagged revenue as contract revenue since ASC 606 took effect in 2018.
+    pub fn concepts(self) -> &'static [&'static str] {
+        match self {
+            LineItem::Revenues => &[
+                "us-gaap:Revenues",
+                "us-gaap:RevenueFromContractWithCustomerExcludingAssessedTax",
+                "us-gaap:RevenueFromContractWithCustomerIncludingAssessedTax",
+                "us-gaap:SalesRevenueNet",
+            ],
+            LineItem::NetIncomeLoss => &["us-gaap:NetIncomeLoss", "us-gaap:ProfitLoss"],
+            LineItem::Assets => &["us-gaap:Assets"],
+        }
+    }
+}
+
+/// The fact reporting `item` for the filing's latest period, leaving out any
+/// broken down by dimension. Where a 10-Q reports both the quarter and the year
+/// to date, the quarter is taken.
+pub fn line_item(facts: &[Fact], item: LineItem) -> Option<&Fact> {
+    item.concepts().iter().find_map(|concept| {
+        facts
+            .iter()
+            .filter(|f| f.concept == *concept && f.dimensions.is_empty() && f.number().is_some())
+            .max_by_key(|f| {
+                let length = match f.period {
+                    Period::Duration(start, end) => (end - start).num_days(),
+                    _ => 0,
+                };
+                (f.period.end(), -length)
+            })
+    })
+}
+
+#[cfg(test)]
+mod xbrl_tests {
+    use super::*;
+    use crate::test_db::TestDb;
+    use std::io::Cursor;
+
+    const INSTANCE: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:us-gaap="http://fasb.org/us-gaap/2018-02-01" xmlns:dei="http://xbrl.sec.gov/dei/2018-01-31" xmlns:xbrldi="http://xbrl.org/2006/xbrldi" xmlns:iso4217="http://www.xbrl.org/2003/iso4217" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <link:schemaRef xmlns:link="http://www.xbrl.org/2003/linkbase" xlink:type="simple" xlink:href="aapl-20180929.xsd" xmlns:xlink="http://www.w3.org/1999/xlink"/>
+  <xbrli:context id="FY2018">
+    <xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2017-10-01</xbrli:startDate><xbrli:endDate>2018-09-29</xbrli:endDate></xbrli:period>
+  </xbrli:context>
+  <xbrli:context id="FY2018_Americas">
+    <xbrli:entity>
+      <xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier>
+      <xbrli:segment><xbrldi:explicitMember dimension="us-gaap:StatementBusinessSegmentsAxis">aapl:AmericasSegmentMember</xbrldi:explicitMember></xbrli:segment>
+    </xbrli:entity>
+    <xbrli:period><xbrli:startDate>2017-10-01</xbrli:startDate><xbrli:endDate>2018-09-29</xbrli:endDate></xbrli:period>
+  </xbrli:context>
+  <xbrli:context id="FY2017">
+    <xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2016-09-25</xbrli:startDate><xbrli:endDate>2017-09-30</xbrli:endDate></xbrli:period>
+  </xbrli:context>
+  <xbrli:context id="I2018">
+    <xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2018-09-29</xbrli:instant></xbrli:period>
+  </xbrli:context>
+  <xbrli:unit id="usd"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <xbrli:unit id="usdPerShare">
+    <xbrli:divide>
+      <xbrli:unitNumerator><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unitNumerator>
+      <xbrli:unitDenominator><xbrli:measure>xbrli:shares</xbrli:measure></xbrli:unitDenominator>
+    </xbrli:divide>
+  </xbrli:unit>
+  <dei:DocumentType contextRef="FY2018">10-K</dei:DocumentType>
+  <us-gaap:Revenues contextRef="FY2018" unitRef="usd" decimals="-6">265595000000</us-gaap:Revenues>
+  <us-gaap:Revenues contextRef="FY2018_Americas" unitRef="usd" decimals="-6">112093000000</us-gaap:Revenues>
+  <us-gaap:Revenues contextRef="FY2017" unitRef="usd" decimals="-6">229234000000</us-gaap:Revenues>
+  <us-gaap:NetIncomeLoss contextRef="FY2018" unitRef="usd" decimals="-6">59531000000</us-gaap:NetIncomeLoss>
+  <us-gaap:EarningsPerShareBasic contextRef="FY2018" unitRef="usdPerShare" decimals="2">12.01</us-gaap:EarningsPerShareBasic>
+  <us-gaap:Assets contextRef="I2018" unitRef="usd" decimals="-6">365725000000</us-gaap:Assets>
+  <us-gaap:Goodwill contextRef="I2018" unitRef="usd" xsi:nil="true"/>
+</xbrli:xbrl>"#;
+
+    const INLINE: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:ix="http://www.xbrl.org/2013/inlineXBRL" xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:ixt="http://www.xbrl.org/inlineXBRL/transformation/2020-02-12" xmlns:us-gaap="http://fasb.org/us-gaap/2023" xmlns:dei="http://xbrl.sec.gov/dei/2023" xmlns:iso4217="http://www.xbrl.org/2003/iso4217">
+<head><title>aapl-20240629</title></head>
+<body>
+<div style="display:none"><ix:header><ix:resources>
+  <xbrli:context id="c-1"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier></xbrli:entity><xbrli:period><xbrli:startDate>2024-03-31</xbrli:startDate><xbrli:endDate>2024-06-29</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:context id="c-2"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier></xbrli:entity><xbrli:period><xbrli:startDate>2023-10-01</xbrli:startDate><xbrli:endDate>2024-06-29</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:unit id="usd"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+</ix:resources></ix:header></div>
+<p>Form <ix:nonNumeric name="dei:DocumentType" contextRef="c-1">10-Q</ix:nonNumeric></p>
+<table>
+  <tr><td>Net sales</td>
+    <td><ix:nonFraction unitRef="usd" contextRef="c-2" decimals="-6" name="us-gaap:RevenueFromContractWithCustomerExcludingAssessedTax" format="ixt:num-dot-decimal" scale="6">296,105</ix:nonFraction></td>
+    <td><ix:nonFraction unitRef="usd" contextRef="c-1" decimals="-6" name="us-gaap:RevenueFromContractWithCustomerExcludingAssessedTax" format="ixt:num-dot-decimal" scale="6">85,777</ix:nonFraction></td></tr>
+  <tr><td>Other income/(expense), net</td>
+    <td>(<ix:nonFraction unitRef="usd" contextRef="c-1" decimals="-6" name="us-gaap:NonoperatingIncomeExpense" format="ixt:num-dot-decimal" scale="6" sign="-">142</ix:nonFraction>)</td></tr>
+  <tr><td>Impairment</td>
+    <td><ix:nonFraction unitRef="usd" contextRef="c-1" decimals="-6" name="us-gaap:GoodwillImpairmentLoss" format="ixt:fixed-zero" scale="6">—</ix:nonFraction></td></tr>
+  <tr><td>Net income per share</td>
+    <td><ix:nonFraction unitRef="usd" contextRef="c-1" decimals="2" name="us-gaap:EarningsPerShareBasic" format="ixt:num-dot-decimal">1.40</ix:nonFraction></td></tr>
+</table>
+<ix:nonNumeric name="us-gaap:RevenueRecognitionPolicyTextBlock" contextRef="c-1"><p>Revenue was <span style="font-weight:bold">$5</span> million.</p></ix:nonNumeric>
+</body>
+</html>"#;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn parse_instance_test() {
+        let facts = parse_instance(INSTANCE).unwrap();
+        assert_eq!(facts.len(), 7);
+
+        assert_eq!(facts[0].concept, "dei:DocumentType");
+        assert_eq!(facts[0].value, "10-K");
+        assert_eq!(facts[0].unit, None);
+        assert_eq!(facts[0].number(), None);
+
+        assert_eq!(
+            facts[1],
+            Fact {
+                concept: String::from("us-gaap:Revenues"),
+                context: String::from("FY2018"),
+                unit: Some(String::from("iso4217:USD")),
+                period: Period::Duration(date(2017, 10, 1), date(2018, 9, 29)),
+                dimensions: BTreeMap::new(),
+                value: String::from("265595000000"),
+                decimals: Some(String::from("-6")),
+            }
+        );
+        assert_eq!(
+            facts[2]
+                .dimensions
+                .get("us-gaap:StatementBusinessSegmentsAxis"),
+            Some(&String::from("aapl:AmericasSegmentMember"))
+        );
+        assert_eq!(
+            facts[5].unit,
+            Some(String::from("iso4217:USD/xbrli:shares"))
+        );
+        assert_eq!(facts[5].number(), Some(12.01));
+        assert_eq!(facts[6].period, Period::Instant(date(2018, 9, 29)));
+
+        assert!(parse_instance("<html></html>").is_err());
+        assert!(
+            parse_instance(r#"<xbrl><Revenues contextRef="missing">1</Revenues></xbrl>"#).is_err()
+        );
+    }
+
+    #[test]
+    fn parse_inline_test() {
+        let facts = parse_inline(INLINE).unwrap();
+        let values = facts
+            .iter()
+            .map(|f| (f.concept.as_str(), f.value.as_str()))
+            .collect::<Vec<(&str, &str)>>();
+        assert_eq!(
+            values,
+            vec![
+                ("dei:DocumentType", "10-Q"),
+                (
+                    "us-gaap:RevenueFromContractWithCustomerExcludingAssessedTax",
+                    "296105000000"
+                ),
+                (
+                    "us-gaap:RevenueFromContractWithCustomerExcludingAssessedTax",
+                    "85777000000"
+                ),
+                ("us-gaap:NonoperatingIncomeExpense", "-142000000"),
+                ("us-gaap:GoodwillImpairmentLoss", "0"),
+                ("us-gaap:EarningsPerShareBasic", "1.4"),
+                (
+                    "us-gaap:RevenueRecognitionPolicyTextBlock",
+                    "Revenue was $5 million."
+                ),
+            ]
+        );
+        assert_eq!(
+            facts[2].period,
+            Period::Duration(date(2024, 3, 31), date(2024, 6, 29))
+        );
+    }
+
+    #[test]
+    fn shift_test() {
+        assert_eq!(shift("296105", 6), "296105000000");
+        assert_eq!(shift("1.5", 3), "1500");
+        assert_eq!(shift("12.34", 1), "123.4");
+        assert_eq!(shift("1.40", 0), "1.4");
+        assert_eq!(shift("25", -2), "0.25");
+        assert_eq!(shift("5", -3), "0.005");
+        assert_eq!(shift("000", 6), "0");
+    }
+
+    #[test]
+    fn number_words_test() {
+        assert_eq!(number_words("No"), Some(0));
+        assert_eq!(number_words("none"), Some(0));
+        assert_eq!(number_words("one"), Some(1));
+        assert_eq!(number_words("Twenty-one"), Some(21));
+        assert_eq!(number_words("one hundred and five"), Some(105));
+        assert_eq!(
+            number_words("two million, three hundred thousand"),
+            Some(2_300_000)
+        );
+        assert_eq!(number_words("several"), None);
+        assert_eq!(number_words("hundred"), None);
+        assert_eq!(number_words(""), None);
+
+        let element = Element {
+            attributes: vec![(String::from("format"), String::from("ixt-sec:numwordsen"))],
+            ..Element::default()
+        };
+        assert_eq!(inline_number("Three", &element), Some(String::from("3")));
+        assert_eq!(inline_number("several", &element), None);
+        assert_eq!(inline_number("12", &element), Some(String::from("12")));
+    }
+
+    #[test]
+    fn line_item_test() {
+        let facts = parse_instance(INSTANCE).unwrap();
+        let value = |item| line_item(&facts, item).and_then(Fact::number);
+        assert_eq!(value(LineItem::Revenues), Some(265595000000.0));
+        assert_eq!(value(LineItem::NetIncomeLoss), Some(59531000000.0));
+        assert_eq!(value(LineItem::Assets), Some(365725000000.0));
+
+        // The quarter rather than the nine months to date
+        let facts = parse_inline(INLINE).unwrap();
+        let revenues = line_item(&facts, LineItem::Revenues).unwrap();
+        assert_eq!(revenues.context, "c-1");
+        assert_eq!(line_item(&facts, LineItem::Assets), None);
+    }
+
+    #[test]
+    fn from_submission_test() {
+        let text = format!(
+            "<SEC-DOCUMENT>\n<SEC-HEADER>\nACCESSION NUMBER:\t0000320193-18-000145\n\
+             CONFORMED SUBMISSION TYPE:\t10-K\n</SEC-HEADER>\n\
+             <DOCUMENT>\n<TYPE>10-K\n<SEQUENCE>1\n<FILENAME>a10-k20189292018.htm\n<TEXT>\n\
+             <html><body>Annual report</body></html>\n</TEXT>\n</DOCUMENT>\n\
+             <DOCUMENT>\n<TYPE>EX-101.INS\n<SEQUENCE>8\n<FILENAME>aapl-20180929.xml\n<TEXT>\n\
+             <XBRL>\n{}\n</XBRL>\n</TEXT>\n</DOCUMENT>\n</SEC-DOCUMENT>\n",
+            INSTANCE
+        );
+        let submission = Submission::new(Cursor::new(text.as_bytes())).unwrap();
+        assert_eq!(
+            from_submission(submission).unwrap(),
+            parse_instance(INSTANCE).unwrap()
+        );
+
+        let text = format!(
+            "<SEC-DOCUMENT>\n<SEC-HEADER>\nACCESSION NUMBER:\t0000320193-24-000081\n\
+             CONFORMED SUBMISSION TYPE:\t10-Q\n</SEC-HEADER>\n\
+             <DOCUMENT>\n<TYPE>10-Q\n<SEQUENCE>1\n<FILENAME>aapl-20240629.htm\n<TEXT>\n\
+             <XBRL>\n{}\n</XBRL>\n</TEXT>\n</DOCUMENT>\n</SEC-DOCUMENT>\n",
+            INLINE
+        );
+        let submission = Submission::new(Cursor::new(text.as_bytes())).unwrap();
+        assert_eq!(from_submission(submission).unwrap().len(), 7);
+    }
+
+    fn store_test(conn: &DbConnection) {
+        let facts = parse_instance(INSTANCE).unwrap();
+        assert_eq!(load(conn, 32019318000145).unwrap(), vec![]);
+
+        write(conn, 32019318000145, &facts).unwrap();
+        assert_eq!(load(conn, 32019318000145).unwrap(), facts);
+
+        let inline = parse_inline(INLINE).unwrap();
+        write(conn, 32019318000145, &inline).unwrap();
+        assert_eq!(load(conn, 32019318000145).unwrap(), inline);
+    }
+
+    #[test]
+    fn postgres_store_test() {
+        store_test(&TestDb::new());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_store_test() {
+        store_test(&TestDb::sqlite());
+    }
+}
@@ -0,0 +1,180 @@
+//! Reads an XML document into a tree of elements, for the SEC's structured forms
+//!
+//! The feed is read event by event, but forms like Form 4 are small and nest
+//! their values several levels deep, so they are easier to pick apart by path.
+//! Elements are looked up by their local name; the prefix is kept for documents
+//! like XBRL where it matters.
+use crate::errors::*;
+use xml::reader::{EventReader, XmlEvent};
+
+#[derive(Debug, PartialEq, Eq, Default, Clone)]
+pub struct Element {
+    pub prefix: Option<String>,
+    pub name: String,
+    /// By local name
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    /// The text directly inside this element
+    pub text: String,
+    /// The text after this element, up to its next sibling or the end of its
+    /// parent, which keeps mixed content in order
+    pub tail: String,
+}
+
+pub fn parse(xml: &str) -> Result<Element> {
+    let mut stack: Vec<Element> = Vec::new();
+    for event in EventReader::from_str(xml) {
+        match event.chain_err(|| "Unable to parse XML")? {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => stack.push(Element {
+                prefix: name.prefix,
+                name: name.local_name,
+                attributes: attributes
+                    .into_iter()
+                    .map(|a| (a.name.local_name, a.value))
+                    .collect(),
+                children: Vec::new(),
+                text: String::new(),
+                tail: String::new(),
+            }),
+            XmlEvent::Characters(text) | XmlEvent::CData(text) | XmlEvent::Whitespace(text) => {
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&text);
+                    if let Some(child) = element.children.last_mut() {
+                        child.tail.push_str(&text);
+                    }
+                }
+            }
+            XmlEvent::EndElement { .. } => {
+                let element = stack.pop().chain_err(|| "Unbalanced XML")?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => return Ok(element),
+                }
+            }
+            _ => {}
+        }
+    }
+    bail!("XML document has no root element")
+}
+
+impl Element {
+    /// The first child called `name`
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    /// Follows `path` down from this element, taking the first match at each step
+    pub fn find(&self, path: &[&str]) -> Option<&Element> {
+        path.iter()
+            .try_fold(self, |element, name| element.child(name))
+    }
+
+    /// The trimmed text at `path`, or `None` if it's missing or empty
+    pub fn text_at(&self, path: &[&str]) -> Option<String> {
+        self.find(path).and_then(Element::trimmed_text)
+    }
+
+    pub fn trimmed_text(&self) -> Option<String> {
+        Some(self.text.trim().to_owned()).filter(|t| !t.is_empty())
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The lines of one of EDGAR's address blocks (`street1`, `street2`, `city`,
+    /// `stateOrCountry`, `zipCode`): the streets, then `city state zip`
+    pub fn address_lines(&self) -> Vec<String> {
+        let place = ["city", "stateOrCountry", "zipCode"]
+            .iter()
+            .filter_map(|f| self.text_at(&[f]))
+            .collect::<Vec<String>>()
+            .join(" ");
+        vec![
+            self.text_at(&["street1"]),
+            self.text_at(&["street2"]),
+            Some(place).filter(|p| !p.is_empty()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    /// The text of this element and everything below it, in document order
+    pub fn inner_text(&self) -> String {
+        // Own text is what comes before the first child, then each child's tail
+        let tails = self.children.iter().map(|c| c.tail.len()).sum::<usize>();
+        let mut text = self.text[..self.text.len() - tails].to_owned();
+        for child in &self.children {
+            text.push_str(&child.inner_text());
+            text.push_str(&child.tail);
+        }
+        text
+    }
+
+    /// Every element below this one called `name`, in document order
+    pub fn descendants<'a>(&'a self, name: &str) -> Vec<&'a Element> {
+        let mut found = Vec::new();
+        for child in &self.children {
+            if child.name == name {
+                found.push(child);
+            }
+            found.extend(child.descendants(name));
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod xml_tree_tests {
+    use super::*;
+
+    const XML: &str = r#"<?xml version="1.0"?>
+<root xmlns:ns1="urn:example">
+    <ns1:item id="a"><value> 1 </value></ns1:item>
+    <ns1:item id="b"><value><![CDATA[two]]></value><empty/></ns1:item>
+</root>"#;
+
+    #[test]
+    fn parse_test() {
+        let root = parse(XML).unwrap();
+        assert_eq!(root.name, "root");
+        assert_eq!(root.children("item").count(), 2);
+
+        let first = root.child("item").unwrap();
+        assert_eq!(first.prefix, Some(String::from("ns1")));
+        assert_eq!(first.attribute("id"), Some("a"));
+        assert_eq!(root.text_at(&["item", "value"]), Some(String::from("1")));
+        assert_eq!(root.text_at(&["item", "empty"]), None);
+
+        let values = root
+            .descendants("value")
+            .into_iter()
+            .filter_map(Element::trimmed_text)
+            .collect::<Vec<String>>();
+        assert_eq!(values, vec!["1", "two"]);
+    }
+
+    #[test]
+    fn inner_text_test() {
+        let root =
+            parse("<p>Revenue was <b>$5 <i>(net)</i></b> million <br/>in total</p>").unwrap();
+        assert_eq!(root.inner_text(), "Revenue was $5 (net) million in total");
+        assert_eq!(root.text, "Revenue was  million in total");
+    }
+
+    #[test]
+    fn malformed_test() {
+        assert!(parse("<root><open></root>").is_err());
+        assert!(parse("").is_err());
+    }
+}